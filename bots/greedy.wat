@@ -0,0 +1,80 @@
+;; a tiny example bot for the sandbox (see src/game/sandbox.rs for the ABI). It heads for the
+;; first apple it finds, and otherwise takes any move that doesn't run into something.
+;;
+;;   cargo run --release --bin snake_sim -- --self-collision die bots/greedy.wat
+(module
+  (memory (export "memory") 1)
+  (global $width (mut i32) (i32.const 0))
+  (global $height (mut i32) (i32.const 0))
+
+  ;; the board lives just past the first kilobyte
+  (func (export "init") (param $width i32) (param $height i32) (result i32)
+    (global.set $width (local.get $width))
+    (global.set $height (local.get $height))
+    ;; make room if the board doesn't fit in what we've got
+    (if (i32.gt_u (i32.add (i32.const 1024) (i32.mul (local.get $width) (local.get $height)))
+                  (i32.mul (memory.size) (i32.const 65536)))
+      (then (drop (memory.grow
+        (i32.add (i32.div_u (i32.mul (local.get $width) (local.get $height)) (i32.const 65536))
+                 (i32.const 1))))))
+    (i32.const 1024))
+
+  ;; what's at (x, y), wrapping around the edges
+  (func $cell (param $x i32) (param $y i32) (result i32)
+    (local.set $x (i32.rem_s (i32.add (local.get $x) (global.get $width)) (global.get $width)))
+    (local.set $y (i32.rem_s (i32.add (local.get $y) (global.get $height)) (global.get $height)))
+    (i32.load8_u (i32.add (i32.const 1024)
+      (i32.add (i32.mul (local.get $y) (global.get $width)) (local.get $x)))))
+
+  ;; whether moving in a direction (1 up, 2 down, 3 left, 4 right) from (x, y) is safe
+  (func $safe (param $x i32) (param $y i32) (param $move i32) (result i32)
+    (local $contents i32)
+    (if (i32.eq (local.get $move) (i32.const 1)) (then (local.set $y (i32.sub (local.get $y) (i32.const 1)))))
+    (if (i32.eq (local.get $move) (i32.const 2)) (then (local.set $y (i32.add (local.get $y) (i32.const 1)))))
+    (if (i32.eq (local.get $move) (i32.const 3)) (then (local.set $x (i32.sub (local.get $x) (i32.const 1)))))
+    (if (i32.eq (local.get $move) (i32.const 4)) (then (local.set $x (i32.add (local.get $x) (i32.const 1)))))
+    (local.set $contents (call $cell (local.get $x) (local.get $y)))
+    (i32.or (i32.eqz (local.get $contents)) (i32.eq (local.get $contents) (i32.const 5))))
+
+  (func (export "next_move") (param $x i32) (param $y i32) (param $direction i32) (result i32)
+    (local $i i32)
+    (local $size i32)
+    (local $apple_x i32)
+    (local $apple_y i32)
+    (local $move i32)
+    (local.set $apple_x (local.get $x))
+    (local.set $apple_y (local.get $y))
+
+    ;; find the first apple
+    (local.set $size (i32.mul (global.get $width) (global.get $height)))
+    (block $found
+      (loop $search
+        (br_if $found (i32.ge_u (local.get $i) (local.get $size)))
+        (if (i32.eq (i32.load8_u (i32.add (i32.const 1024) (local.get $i))) (i32.const 5))
+          (then
+            (local.set $apple_x (i32.rem_u (local.get $i) (global.get $width)))
+            (local.set $apple_y (i32.div_u (local.get $i) (global.get $width)))
+            (br $found)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $search)))
+
+    ;; towards the apple sideways, then up or down
+    (if (i32.lt_s (local.get $apple_x) (local.get $x))
+      (then (if (call $safe (local.get $x) (local.get $y) (i32.const 3)) (then (return (i32.const 3))))))
+    (if (i32.gt_s (local.get $apple_x) (local.get $x))
+      (then (if (call $safe (local.get $x) (local.get $y) (i32.const 4)) (then (return (i32.const 4))))))
+    (if (i32.lt_s (local.get $apple_y) (local.get $y))
+      (then (if (call $safe (local.get $x) (local.get $y) (i32.const 1)) (then (return (i32.const 1))))))
+    (if (i32.gt_s (local.get $apple_y) (local.get $y))
+      (then (if (call $safe (local.get $x) (local.get $y) (i32.const 2)) (then (return (i32.const 2))))))
+
+    ;; otherwise anything that doesn't hit something
+    (local.set $move (i32.const 1))
+    (block $done
+      (loop $try
+        (br_if $done (i32.gt_u (local.get $move) (i32.const 4)))
+        (if (call $safe (local.get $x) (local.get $y) (local.get $move))
+          (then (return (local.get $move))))
+        (local.set $move (i32.add (local.get $move) (i32.const 1)))
+        (br $try)))
+    (i32.const 0)))
@@ -0,0 +1,158 @@
+// turns replays into pictures, for bug reports and anywhere else a canvas can't go
+use std::{env, fs, io::BufWriter, path::PathBuf, process};
+
+use rusty_snake::game::raster::Pixmap;
+use rusty_snake::game::render::{self, Grid};
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::svg::{self, Svg};
+
+const USAGE: &str = "usage: snake_export [options] <replay>
+
+draws a replay an update at a time, the same way the game does in the browser, as a numbered
+PNG for each update, one animated SVG file that plays the whole thing, or both
+
+options:
+  --png DIR     write frame-000000.png, frame-000001.png and so on into DIR
+  --svg FILE    write an animated SVG to FILE
+  --size N      pixels across each square (default 20)
+  --from N      the first update to draw (default 0)
+  --to N        the last update to draw (default the end of the game)
+  --every N     only draw every Nth update (default 1)
+  --delay MS    how long each frame's shown in the SVG (default 75, the game's usual pace)";
+
+struct Options {
+    png: Option<PathBuf>,
+    svg: Option<PathBuf>,
+    size: u32,
+    from: u64,
+    to: u64,
+    every: u64,
+    delay: u64,
+    file: String,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut options = Options {
+        png: None,
+        svg: None,
+        size: 20,
+        from: 0,
+        to: u64::MAX,
+        every: 1,
+        delay: 75,
+        file: String::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--png" => options.png = Some(args.next().ok_or("--png needs a directory")?.into()),
+            "--svg" => options.svg = Some(args.next().ok_or("--svg needs a file")?.into()),
+            "--size" => options.size = parse_number(&arg, args.next())?,
+            "--from" => options.from = parse_number(&arg, args.next())?,
+            "--to" => options.to = parse_number(&arg, args.next())?,
+            "--every" => options.every = parse_number(&arg, args.next())?,
+            "--delay" => options.delay = parse_number(&arg, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if options.file.is_empty() => options.file = arg,
+            _ => return Err("only one replay at a time".to_string()),
+        }
+    }
+
+    if options.file.is_empty() || (options.png.is_none() && options.svg.is_none()) {
+        return Err(USAGE.to_string());
+    }
+    if options.size == 0 || options.every == 0 {
+        return Err("--size and --every have to be at least 1".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn write_png(path: &PathBuf, pixmap: &Pixmap) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("couldn't write {}: {}", path.display(), e);
+    let file = fs::File::create(path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), pixmap.width, pixmap.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer
+        .write_image_data(&pixmap.pixels)
+        .map_err(|e| error(&e))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let text = fs::read_to_string(&options.file)
+        .map_err(|e| format!("couldn't read {}: {}", options.file, e))?;
+    let replay = Replay::parse(&text).map_err(|e| format!("{}: {}", options.file, e))?;
+    if let Some(dir) = &options.png {
+        fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    }
+
+    let rect_size = options.size as f64;
+    let (width, height) = Grid::new(&replay.settings, rect_size).size();
+    let mut sim = replay.start();
+    let mut frames = vec![];
+    let mut num_frames = 0;
+    loop {
+        if sim.tick >= options.from && (sim.tick - options.from).is_multiple_of(options.every) {
+            if let Some(dir) = &options.png {
+                let mut pixmap = Pixmap::new(width as u32, height as u32);
+                render::draw_frame(&mut pixmap, &sim, rect_size);
+                write_png(&dir.join(format!("frame-{:06}.png", num_frames)), &pixmap)?;
+            }
+            if options.svg.is_some() {
+                let mut svg = Svg::new();
+                render::draw_frame(&mut svg, &sim, rect_size);
+                frames.push(svg.into_elements());
+            }
+            num_frames += 1;
+        }
+
+        // the game stops moving once it's over, whatever's left in the replay
+        let commands = match replay.ticks.get(sim.tick as usize) {
+            Some(commands) if sim.tick < options.to && !sim.is_game_over => commands,
+            _ => break,
+        };
+        sim.step(commands);
+    }
+
+    if num_frames == 0 {
+        return Err(format!(
+            "nothing to draw: the game's over by update {}",
+            sim.tick
+        ));
+    }
+    if let Some(path) = &options.svg {
+        let seconds_per_frame = options.delay as f64 / 1000.;
+        let text = svg::animate(width, height, &frames, seconds_per_frame);
+        fs::write(path, text).map_err(|e| format!("couldn't write {}: {}", path.display(), e))?;
+    }
+    println!(
+        "drew {} frames of {}, up to update {}",
+        num_frames, options.file, sim.tick
+    );
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,277 @@
+// keeps the high scores. Every score comes with the replay of its game, which is played back
+// before it's listed, so nothing gets on the board that didn't really happen
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use rusty_snake::game::leaderboard::{self, Entry, Leaderboard, Period};
+use rusty_snake::game::verify::{self, Submission};
+
+const USAGE: &str = "usage: snake_leaderboard [options]
+
+keeps a leaderboard for every board size, level and set of rules, with daily, weekly and
+all-time views. Open the game with index.html?leaderboard=http://localhost:9002&name=NAME and
+every game played alone is sent here when it ends, checked by playing it back, and listed if
+its score holds up. The protocol is documented at the top of src/game/leaderboard.rs
+
+options:
+  --address ADDR   where to listen (default 127.0.0.1:9002)
+  --file PATH      where the scores are kept (default leaderboard.txt)
+  --max-ticks N    turn down games longer than this without playing them (default 100000)";
+
+// the most anyone gets to ask for at once
+const MAX_LIMIT: usize = 100;
+const DEFAULT_LIMIT: usize = 10;
+// submissions bigger than this aren't worth reading. The longest game allowed by default is
+// about 700KB
+const MAX_SUBMISSION_BYTES: u64 = 1024 * 1024;
+
+struct Options {
+    address: String,
+    file: PathBuf,
+    max_ticks: u64,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut options = Options {
+        address: "127.0.0.1:9002".to_string(),
+        file: PathBuf::from("leaderboard.txt"),
+        max_ticks: 100_000,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--address" => options.address = args.next().ok_or("--address needs an address")?,
+            "--file" => options.file = args.next().ok_or("--file needs a path")?.into(),
+            "--max-ticks" => options.max_ticks = parse_number(&arg, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    Ok(Some(options))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// undoes the %XX and + escaping browsers do to query strings
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(index + 1..index + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_query(url: &str) -> (String, HashMap<String, String>) {
+    let (path, query) = match url.find('?') {
+        Some(index) => (&url[..index], &url[index + 1..]),
+        None => (url, ""),
+    };
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(index) => (decode(&pair[..index]), decode(&pair[index + 1..])),
+            None => (decode(pair), String::new()),
+        })
+        .collect();
+    (path.to_string(), params)
+}
+
+struct Scores {
+    leaderboard: Leaderboard,
+    file: PathBuf,
+    max_ticks: u64,
+}
+
+impl Scores {
+    fn load(file: PathBuf, max_ticks: u64) -> Result<Scores, String> {
+        let leaderboard = if file.exists() {
+            let text = fs::read_to_string(&file)
+                .map_err(|e| format!("couldn't read {}: {}", file.display(), e))?;
+            Leaderboard::parse(&text).map_err(|e| format!("{}: {}", file.display(), e))?
+        } else {
+            Leaderboard::default()
+        };
+        Ok(Scores {
+            leaderboard,
+            file,
+            max_ticks,
+        })
+    }
+
+    // checks a submission, and lists it if it holds up
+    fn submit(&mut self, name: &str, text: &str) -> Result<String, String> {
+        let submission = Submission::parse(text)?;
+        // before anything gets played
+        let num_ticks = submission.replay.ticks.len() as u64;
+        if num_ticks > self.max_ticks {
+            return Err(format!(
+                "rejected: {} updates is more than the {} allowed",
+                num_ticks, self.max_ticks
+            ));
+        }
+        let board = leaderboard::board_for(&submission.replay, now())?;
+        let verified = verify::verify(&submission, self.max_ticks).map_err(|rejection| {
+            match rejection.tick {
+                Some(tick) => format!("rejected at update {}: {}", tick, rejection.reason),
+                None => format!("rejected: {}", rejection.reason),
+            }
+        })?;
+        if !verified.is_game_over {
+            return Err("only finished games go on the leaderboard".to_string());
+        }
+
+        let entry = Entry {
+            time: now(),
+            board,
+            score: verified.score,
+            ticks: verified.ticks,
+            name: leaderboard::clean_name(name),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)
+            .map_err(|e| format!("couldn't open {}: {}", self.file.display(), e))?;
+        writeln!(file, "{}", entry.to_text())
+            .map_err(|e| format!("couldn't write {}: {}", self.file.display(), e))?;
+
+        self.leaderboard.entries.push(entry.clone());
+        let rank = self.leaderboard.rank(&entry);
+        println!(
+            "{}: {} scored {} in {} updates, rank {}",
+            entry.board, entry.name, entry.score, entry.ticks, rank
+        );
+        Ok(format!("accepted {}", rank))
+    }
+
+    fn top(&self, params: &HashMap<String, String>) -> Result<String, String> {
+        let board = params.get("board").ok_or("which board?")?;
+        let period = match params.get("period") {
+            Some(period) => Period::parse(period)?,
+            None => Period::AllTime,
+        };
+        let limit = match params.get("limit") {
+            Some(limit) => limit
+                .parse::<usize>()
+                .map_err(|_| format!("limit expects a number, not {}", limit))?,
+            None => DEFAULT_LIMIT,
+        };
+        let top = self
+            .leaderboard
+            .top(board, period, now(), limit.min(MAX_LIMIT));
+        Ok(top
+            .iter()
+            .map(|entry| format!("{}\n", entry.to_text()))
+            .collect())
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Result<String, String> {
+        let (path, params) = parse_query(request.url());
+        match (request.method(), path.as_str()) {
+            (Method::Post, "/submit") => {
+                if request
+                    .body_length()
+                    .is_some_and(|length| length as u64 > MAX_SUBMISSION_BYTES)
+                {
+                    return Err("that submission's too big".to_string());
+                }
+                // a byte over, to tell one that's too big from one that's just big enough
+                let mut text = String::new();
+                request
+                    .as_reader()
+                    .take(MAX_SUBMISSION_BYTES + 1)
+                    .read_to_string(&mut text)
+                    .map_err(|e| format!("couldn't read the submission: {}", e))?;
+                if text.len() as u64 > MAX_SUBMISSION_BYTES {
+                    return Err("that submission's too big".to_string());
+                }
+                let name = params.get("name").map(String::as_str).unwrap_or("");
+                self.submit(name, &text)
+            }
+            (Method::Get, "/top") => self.top(&params),
+            _ => Err(format!("don't know what to do with {}", path)),
+        }
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut scores = Scores::load(options.file.clone(), options.max_ticks)?;
+    let server = Server::http(&options.address)
+        .map_err(|e| format!("couldn't listen on {}: {}", options.address, e))?;
+    println!(
+        "listening on http://{}, with {} scores from {}",
+        options.address,
+        scores.leaderboard.entries.len(),
+        options.file.display()
+    );
+
+    // the game's usually served from somewhere else
+    let allow_anyone = Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap();
+    for mut request in server.incoming_requests() {
+        let response = match scores.handle(&mut request) {
+            Ok(text) => Response::from_string(text),
+            Err(err) => Response::from_string(err).with_status_code(400),
+        };
+        let response = response.with_header(allow_anyone.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("couldn't respond: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
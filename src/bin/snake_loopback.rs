@@ -0,0 +1,167 @@
+// plays online games with rollback over a pretend network, to check it holds up when the real
+// one is slow or drops things
+use std::{env, process};
+
+use rusty_snake::game::loopback::{self, LoopbackConfig};
+use rusty_snake::game::rollback;
+use rusty_snake::game::simulation::Settings;
+use rusty_snake::game::snake;
+use rusty_snake::game::tournament::{self, Bot};
+
+const USAGE: &str = "usage: snake_loopback [options] [bot]...
+
+plays online games with rollback between the given bots (default hard hard), each with its
+own copy of the game, passing messages through a pretend relay server that holds them up and
+loses some. Afterwards it checks every player settled on the same game, and that replaying the
+commands they settled on from scratch ends up in the same place. Exits with an error if not
+
+options:
+  --games N        games to play, each with the next seed (default 10)
+  --latency N      updates every message takes to arrive (default 3)
+  --jitter N       up to this many more, at random (default 2)
+  --loss X         chance of a message going missing, from 0 to 1 (default 0.05)
+  --input-delay N  updates ahead our own commands are scheduled (default 1)
+  --width N        board width (default 30)
+  --height N       board height (default 20)
+  --apples N       apples on the board at once (default 5)
+  --max-ticks N    cut each game off after this many updates (default 2000)
+  --seed N         seed for the first game (default 0)";
+
+struct Options {
+    config: LoopbackConfig,
+    games: u64,
+    bots: Vec<String>,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut config = LoopbackConfig {
+        settings: Settings::new(30, 20),
+        seed: 0,
+        input_delay: rollback::DEFAULT_INPUT_DELAY,
+        latency: 3,
+        jitter: 2,
+        loss: 0.05,
+        max_ticks: 2000,
+    };
+    let mut games = 10;
+    let mut bots = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = parse_number(&arg, args.next())?,
+            "--latency" => config.latency = parse_number(&arg, args.next())?,
+            "--jitter" => config.jitter = parse_number(&arg, args.next())?,
+            "--loss" => config.loss = parse_number(&arg, args.next())?,
+            "--input-delay" => config.input_delay = parse_number(&arg, args.next())?,
+            "--width" => config.settings.width = parse_number(&arg, args.next())?,
+            "--height" => config.settings.height = parse_number(&arg, args.next())?,
+            "--apples" => config.settings.num_apples = parse_number(&arg, args.next())?,
+            "--max-ticks" => config.max_ticks = parse_number(&arg, args.next())?,
+            "--seed" => config.seed = parse_number(&arg, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => bots.push(arg),
+        }
+    }
+
+    if config.settings.width <= 0 || config.settings.height <= 0 {
+        return Err("the board needs to be at least 1x1".to_string());
+    }
+    if !(0. ..1.).contains(&config.loss) {
+        return Err("--loss needs to be at least 0 and less than 1".to_string());
+    }
+    if bots.is_empty() {
+        bots = vec!["hard".to_string(), "hard".to_string()];
+    }
+    if bots.len() > snake::players().len() {
+        return Err(format!(
+            "at most {} bots fit on one board",
+            snake::players().len()
+        ));
+    }
+    Ok(Some(Options {
+        config,
+        games,
+        bots,
+    }))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let bots = options
+        .bots
+        .iter()
+        .map(|name| {
+            tournament::builtin_bots()
+                .into_iter()
+                .find(|bot| &bot.name == name)
+                .ok_or_else(|| format!("unknown bot {}", name))
+        })
+        .collect::<Result<Vec<Bot>, String>>()?;
+    let players: Vec<&Bot> = bots.iter().collect();
+
+    println!(
+        "{:>6} {:>8} {:>8} {:>10} {:>12} {:>8}  result",
+        "seed", "ticks", "updates", "rollbacks", "resimulated", "stalls"
+    );
+    let mut failures = 0;
+    for game in 0..options.games {
+        let mut config = options.config.clone();
+        config.seed += game;
+        let report = loopback::run(&config, &players);
+
+        // summed over every player
+        let peers = report.peers.iter();
+        let rollbacks: u64 = peers.clone().map(|peer| peer.stats.rollbacks).sum();
+        let resimulated: u64 = peers.clone().map(|peer| peer.stats.resimulated).sum();
+        let stalls: u64 = peers.clone().map(|peer| peer.stats.stalls).sum();
+        let ticks = peers.map(|peer| peer.confirmed).min().unwrap_or(0);
+
+        let result = if report.is_ok() {
+            "ok".to_string()
+        } else if !report.finished {
+            "gave up waiting".to_string()
+        } else if let Some(tick) = report.peers.iter().filter_map(|peer| peer.desync).min() {
+            format!("DESYNCED AT {}", tick)
+        } else {
+            "MISMATCH".to_string()
+        };
+        if !report.is_ok() {
+            failures += 1;
+        }
+        println!(
+            "{:>6} {:>8} {:>8} {:>10} {:>12} {:>8}  {}",
+            config.seed, ticks, report.updates, rollbacks, resimulated, stalls, result
+        );
+    }
+
+    if failures > 0 {
+        return Err(format!(
+            "{} of {} games went wrong",
+            failures, options.games
+        ));
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
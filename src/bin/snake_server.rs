@@ -0,0 +1,418 @@
+// passes commands between the players of online games. It never runs the game itself, since
+// every browser plays out the same one, in lockstep or with rollback
+use std::{
+    collections::HashMap,
+    env,
+    io::ErrorKind,
+    net::{TcpListener, TcpStream},
+    process,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::Message as Frame;
+
+use rusty_snake::game::net::Message;
+
+const USAGE: &str = "usage: snake_server [options]
+
+relays online games between browsers. Open the game with
+index.html?server=ws://localhost:9001&room=NAME in a tab for each player, and the game starts
+once the room is full. Any game can also be broadcast with &broadcast=CHANNEL, and watched
+from index.html?server=ws://localhost:9001&watch=CHANNEL
+
+options:
+  --address ADDR  where to listen (default 127.0.0.1:9001)
+  --players N     players per room (default 2)";
+
+// how long a connection waits for something to read before checking for things to send
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// How many messages a room or channel keeps for people catching up. A game that goes on longer
+// than this can't be rejoined, and watchers starting partway through wait for the next snapshot
+const MAX_HISTORY: usize = 100_000;
+
+struct Seat {
+    token: u64,
+    // None while the player's connection is down
+    outbox: Option<Sender<String>>,
+}
+
+struct Room {
+    seats: Vec<Option<Seat>>,
+    // everything sent since the last game started, for players catching back up, or None once
+    // there's been too much of it to keep
+    history: Option<Vec<String>>,
+}
+
+impl Room {
+    fn tell(&self, slot: usize, text: &str) {
+        if let Some(Some(Seat {
+            outbox: Some(outbox),
+            ..
+        })) = self.seats.get(slot)
+        {
+            // if they've just gone, they'll hear all about it when they're back
+            let _ = outbox.send(text.to_string());
+        }
+    }
+
+    fn tell_others(&self, slot: usize, text: &str) {
+        for other in 0..self.seats.len() {
+            if other != slot {
+                self.tell(other, text);
+            }
+        }
+    }
+
+    fn is_connected(&self, slot: usize) -> bool {
+        matches!(
+            self.seats.get(slot),
+            Some(Some(Seat {
+                outbox: Some(_),
+                ..
+            }))
+        )
+    }
+
+    // lets a player who just sat down know who else is here
+    fn welcome(&self, slot: usize, token: u64) {
+        let welcome = Message::Welcome {
+            slot,
+            token,
+            size: self.seats.len(),
+        };
+        self.tell(slot, &welcome.to_text());
+        for other in 0..self.seats.len() {
+            if other != slot && self.is_connected(other) {
+                self.tell(slot, &Message::Joined { slot: other }.to_text());
+            }
+        }
+        self.tell_others(slot, &Message::Joined { slot }.to_text());
+    }
+}
+
+// a game being broadcast, and whoever's watching it
+#[derive(Default)]
+struct Channel {
+    is_live: bool,
+    watchers: Vec<Sender<String>>,
+    // the last snapshot and every delta since, for anyone who starts watching partway through
+    history: Vec<String>,
+}
+
+impl Channel {
+    // starts over at each snapshot, since that's all anyone new needs
+    fn remember(&mut self, text: &str, is_snapshot: bool) {
+        if is_snapshot || self.history.len() >= MAX_HISTORY {
+            self.history.clear();
+        }
+        if is_snapshot || !self.history.is_empty() {
+            self.history.push(text.to_string());
+        }
+    }
+
+    fn tell_watchers(&mut self, text: &str) {
+        // anyone whose connection has gone gets dropped
+        self.watchers
+            .retain(|watcher| watcher.send(text.to_string()).is_ok());
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    rooms: HashMap<String, Room>,
+    channels: HashMap<String, Channel>,
+}
+
+type Shared = Arc<Mutex<Server>>;
+
+// Nothing's left half done while the lock's held, so if a connection's thread ever panics with
+// it the rooms and channels are still fine for everyone else to carry on with
+fn lock(shared: &Shared) -> MutexGuard<'_, Server> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// what a connection is doing here, once it's said
+#[derive(Clone)]
+enum Place {
+    Player(String, usize),
+    Broadcaster(String),
+    Watcher(String),
+}
+
+fn handle(
+    shared: &Shared,
+    players: usize,
+    place: &mut Option<Place>,
+    outbox: &Sender<String>,
+    text: &str,
+) -> Result<(), String> {
+    let refuse = |message: &str| {
+        let error = Message::Error {
+            message: message.to_string(),
+        };
+        let _ = outbox.send(error.to_text());
+        Ok(())
+    };
+    let message = match Message::parse(text) {
+        Ok(message) => message,
+        Err(err) => return refuse(&err),
+    };
+    let mut server = lock(shared);
+    let Server { rooms, channels } = &mut *server;
+
+    match (message, place.clone()) {
+        (Message::Join { room: name }, None) => {
+            let room = rooms.entry(name.clone()).or_insert_with(|| Room {
+                seats: (0..players).map(|_| None).collect(),
+                history: Some(vec![]),
+            });
+            let slot = match room.seats.iter().position(|seat| seat.is_none()) {
+                Some(slot) => slot,
+                None => return refuse("room is full"),
+            };
+            let token = rand::random();
+            room.seats[slot] = Some(Seat {
+                token,
+                outbox: Some(outbox.clone()),
+            });
+            room.welcome(slot, token);
+            println!("{}: player {} joined", name, slot + 1);
+            *place = Some(Place::Player(name, slot));
+        }
+
+        (
+            Message::Rejoin {
+                room: name,
+                slot,
+                token,
+            },
+            None,
+        ) => {
+            let room = match rooms.get_mut(&name) {
+                Some(room) => room,
+                None => return refuse("room is gone"),
+            };
+            if room.history.is_none() {
+                return refuse("that game's gone on too long to catch back up with");
+            }
+            match room.seats.get_mut(slot) {
+                Some(Some(seat)) if seat.token == token => seat.outbox = Some(outbox.clone()),
+                _ => return refuse("that seat isn't yours"),
+            }
+            room.welcome(slot, token);
+            for text in room.history.iter().flatten() {
+                room.tell(slot, text);
+            }
+            println!("{}: player {} is back", name, slot + 1);
+            *place = Some(Place::Player(name, slot));
+        }
+
+        (Message::Start { .. }, Some(Place::Player(name, 0))) => {
+            let room = rooms
+                .get_mut(&name)
+                .ok_or(format!("{}: room is gone", name))?;
+            room.history = Some(vec![text.to_string()]);
+            room.tell_others(0, text);
+            println!("{}: new game", name);
+        }
+
+        (Message::Input { slot, .. }, Some(Place::Player(name, me)))
+        | (Message::Inputs { slot, .. }, Some(Place::Player(name, me)))
+        | (Message::Hash { slot, .. }, Some(Place::Player(name, me)))
+            if slot == me =>
+        {
+            let room = rooms
+                .get_mut(&name)
+                .ok_or(format!("{}: room is gone", name))?;
+            if let Some(history) = room.history.as_mut() {
+                history.push(text.to_string());
+                if history.len() > MAX_HISTORY {
+                    println!("{}: too long to rejoin", name);
+                    room.history = None;
+                }
+            }
+            room.tell_others(me, text);
+        }
+
+        (Message::Broadcast { channel: name }, None) => {
+            let channel = channels.entry(name.clone()).or_default();
+            if channel.is_live {
+                return refuse("somebody's already broadcasting there");
+            }
+            channel.is_live = true;
+            println!("{}: broadcasting", name);
+            *place = Some(Place::Broadcaster(name));
+        }
+
+        (Message::Watch { channel: name }, None) => {
+            let channel = channels.entry(name.clone()).or_default();
+            for text in channel.history.iter() {
+                let _ = outbox.send(text.clone());
+            }
+            channel.watchers.push(outbox.clone());
+            println!("{}: {} watching", name, channel.watchers.len());
+            *place = Some(Place::Watcher(name));
+        }
+
+        (Message::Snapshot(_), Some(Place::Broadcaster(name))) => {
+            let channel = channels
+                .get_mut(&name)
+                .ok_or(format!("{}: channel is gone", name))?;
+            channel.remember(text, true);
+            channel.tell_watchers(text);
+        }
+
+        (Message::Delta(_), Some(Place::Broadcaster(name))) => {
+            let channel = channels
+                .get_mut(&name)
+                .ok_or(format!("{}: channel is gone", name))?;
+            channel.remember(text, false);
+            channel.tell_watchers(text);
+        }
+
+        _ => return refuse("not allowed"),
+    }
+    Ok(())
+}
+
+fn leave(shared: &Shared, place: Option<Place>) {
+    let mut server = lock(shared);
+    match place {
+        Some(Place::Player(name, slot)) => {
+            let rooms = &mut server.rooms;
+            if let Some(room) = rooms.get_mut(&name) {
+                if let Some(Some(seat)) = room.seats.get_mut(slot) {
+                    seat.outbox = None;
+                }
+                room.tell_others(slot, &Message::Left { slot }.to_text());
+                println!("{}: player {} left", name, slot + 1);
+
+                // nobody's coming back to an empty room
+                if (0..room.seats.len()).all(|slot| !room.is_connected(slot)) {
+                    rooms.remove(&name);
+                    println!("{}: closed", name);
+                }
+            }
+        }
+
+        // watchers keep the last picture up until somebody broadcasts on the channel again
+        Some(Place::Broadcaster(name)) => {
+            if let Some(channel) = server.channels.get_mut(&name) {
+                channel.is_live = false;
+                println!("{}: stopped broadcasting", name);
+                if channel.watchers.is_empty() {
+                    server.channels.remove(&name);
+                }
+            }
+        }
+
+        // they're dropped from the channel the next time sending to them fails
+        Some(Place::Watcher(name)) => println!("{}: a watcher left", name),
+
+        None => {}
+    }
+}
+
+fn serve(stream: TcpStream, shared: Shared, players: usize) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    let (outbox, inbox) = mpsc::channel();
+    let mut place = None;
+
+    let result = loop {
+        match socket.read() {
+            Ok(Frame::Text(text)) => {
+                if let Err(err) = handle(&shared, players, &mut place, &outbox, &text) {
+                    break Err(err);
+                }
+            }
+            Ok(Frame::Close(_)) => break Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {}
+            Err(err) => break Err(err.to_string()),
+        }
+
+        let sent = inbox
+            .try_iter()
+            .try_for_each(|text| socket.send(Frame::Text(text)).map_err(|e| e.to_string()));
+        if let Err(err) = sent {
+            break Err(err);
+        }
+    };
+
+    leave(&shared, place);
+    result
+}
+
+fn parse_args() -> Result<Option<(String, usize)>, String> {
+    let mut address = "127.0.0.1:9001".to_string();
+    let mut players = 2;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--address" => address = args.next().ok_or("--address needs an address")?,
+            "--players" => {
+                let value = args.next().ok_or("--players needs a value")?;
+                players = value
+                    .parse()
+                    .map_err(|_| format!("--players expects a number, not {}", value))?;
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if !(1..=4).contains(&players) {
+        return Err("rooms need between 1 and 4 players".to_string());
+    }
+    Ok(Some((address, players)))
+}
+
+fn run(address: &str, players: usize) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(address).map_err(|e| format!("couldn't listen on {}: {}", address, e))?;
+    println!("listening on ws://{}", address);
+
+    let shared = Shared::default();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("couldn't accept a connection: {}", err);
+                continue;
+            }
+        };
+        let shared = shared.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve(stream, shared, players) {
+                eprintln!("connection closed: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some((address, players)) => run(&address, players),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,340 @@
+// runs games between bots without a browser, and reports how they did
+use std::{env, fs, path::PathBuf, process, rc::Rc};
+
+use rusty_snake::game::level::Level;
+use rusty_snake::game::rules::{CollisionRule, HeadToHeadRule};
+use rusty_snake::game::sandbox::{self, WasmModule};
+use rusty_snake::game::simulation::{self, Settings};
+use rusty_snake::game::snake;
+use rusty_snake::game::spawn;
+use rusty_snake::game::tournament::{self, Bot, BotStats, GameResult};
+
+const USAGE: &str = "usage: snake_sim [options] <bot>...
+
+plays games between the given bots. With one bot it plays alone, otherwise they all share a
+board, or with --tournament every pair of bots plays each other. A bot is either one of the
+built-in ones, or the path to a .wasm or .wat file that runs in a sandbox
+
+options:
+  --games N              games to play, or games per pairing in a tournament (default 10)
+  --width N              board width (default 30)
+  --height N             board height (default 20)
+  --apples N             apples on the board at once (default 5)
+  --timed                apples move elsewhere if nobody eats them in time
+  --level NAME           empty, pillars or arena (default empty)
+  --spawn NAME           where apples go: uniform, far-from-head, near-walls, clustered,
+                         reachable-only or scripted (default uniform)
+  --self-collision RULE  block or die (default block)
+  --snake-collision RULE block or die (default die)
+  --head-to-head RULE    block, both-die or longer-wins (default both-die)
+  --max-ticks N          give up on a game after this many updates (default 10000)
+  --seed N               seed for the first game, each game after uses the next one (default 0)
+  --tournament           round robin between every pair of bots, with Elo ratings
+  --replays DIR          save the best, longest and shortest games here
+  --fuel N               instructions a sandboxed bot gets per move (default 1000000)
+  --memory-mb N          memory a sandboxed bot can use (default 16)
+  --list                 list the built-in bots";
+
+struct Options {
+    settings: Settings,
+    games: u64,
+    max_ticks: u64,
+    seed: u64,
+    is_tournament: bool,
+    replays: Option<PathBuf>,
+    fuel: u64,
+    memory: usize,
+    bots: Vec<String>,
+}
+
+// the games worth a second look
+#[derive(Default)]
+struct Notable {
+    best: Option<GameResult>,
+    longest: Option<GameResult>,
+    shortest: Option<GameResult>,
+}
+
+impl Notable {
+    fn consider(&mut self, result: GameResult) {
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|best| result.best_score() > best.best_score())
+        {
+            self.best = Some(result.clone());
+        }
+        if self
+            .longest
+            .as_ref()
+            .is_none_or(|longest| result.ticks() > longest.ticks())
+        {
+            self.longest = Some(result.clone());
+        }
+        if self
+            .shortest
+            .as_ref()
+            .is_none_or(|shortest| result.ticks() < shortest.ticks())
+        {
+            self.shortest = Some(result);
+        }
+    }
+
+    fn save(&self, dir: &PathBuf) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+        let games = [
+            ("best", &self.best),
+            ("longest", &self.longest),
+            ("shortest", &self.shortest),
+        ];
+        for (label, game) in games.iter() {
+            if let Some(game) = game {
+                let path = dir.join(format!("{}.replay", label));
+                fs::write(&path, game.replay.to_text())
+                    .map_err(|e| format!("couldn't write {}: {}", path.display(), e))?;
+                println!("saved the {} game to {}", label, path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut settings = Settings::new(30, 20);
+    let mut level = "empty".to_string();
+    let mut spawn_name = "uniform".to_string();
+    let mut options = Options {
+        settings: Settings::new(30, 20),
+        games: 10,
+        max_ticks: 10000,
+        seed: 0,
+        is_tournament: false,
+        replays: None,
+        fuel: sandbox::DEFAULT_FUEL,
+        memory: sandbox::DEFAULT_MEMORY,
+        bots: vec![],
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => options.games = parse_number(&arg, args.next())?,
+            "--width" => settings.width = parse_number(&arg, args.next())?,
+            "--height" => settings.height = parse_number(&arg, args.next())?,
+            "--apples" => settings.num_apples = parse_number(&arg, args.next())?,
+            "--timed" => settings.apple_lifetime = Some(simulation::APPLE_LIFETIME),
+            "--level" => level = args.next().ok_or("--level needs a name")?,
+            "--spawn" => spawn_name = args.next().ok_or("--spawn needs a name")?,
+            "--self-collision" => {
+                settings.rules.self_collision =
+                    CollisionRule::parse(&args.next().unwrap_or_default())?
+            }
+            "--snake-collision" => {
+                settings.rules.snake_collision =
+                    CollisionRule::parse(&args.next().unwrap_or_default())?
+            }
+            "--head-to-head" => {
+                settings.rules.head_to_head =
+                    HeadToHeadRule::parse(&args.next().unwrap_or_default())?
+            }
+            "--max-ticks" => options.max_ticks = parse_number(&arg, args.next())?,
+            "--seed" => options.seed = parse_number(&arg, args.next())?,
+            "--tournament" => options.is_tournament = true,
+            "--replays" => {
+                options.replays = Some(PathBuf::from(
+                    args.next().ok_or("--replays needs a directory")?,
+                ))
+            }
+            "--fuel" => options.fuel = parse_number(&arg, args.next())?,
+            "--memory-mb" => {
+                options.memory = parse_number::<usize>(&arg, args.next())? * 1024 * 1024
+            }
+            "--list" => {
+                for bot in tournament::builtin_bots() {
+                    println!("{}", bot.name);
+                }
+                return Ok(None);
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.bots.push(arg),
+        }
+    }
+
+    if settings.width <= 0 || settings.height <= 0 {
+        return Err("the board needs to be at least 1x1".to_string());
+    }
+    settings.level =
+        Level::find_builtin(&level).ok_or_else(|| format!("unknown level {}", level))?;
+    settings.check()?;
+    let spawn_name = spawn_name.replace('-', " ");
+    settings.spawn_strategy = spawn::builtin_strategies(&settings.level)
+        .iter()
+        .position(|strategy| strategy.name() == spawn_name)
+        .ok_or_else(|| format!("unknown spawn strategy {}", spawn_name))?;
+    options.settings = settings;
+
+    if options.bots.is_empty() {
+        return Err("which bots should play?".to_string());
+    }
+    if !options.is_tournament && options.bots.len() > snake::players().len() {
+        return Err(format!(
+            "at most {} bots fit on one board",
+            snake::players().len()
+        ));
+    }
+    if options.is_tournament && options.bots.len() < 2 {
+        return Err("a tournament needs at least two bots".to_string());
+    }
+    Ok(Some(options))
+}
+
+// along with the sandboxed modules, so we can say how many moves they forfeited
+fn find_bots(options: &Options) -> Result<(Vec<Bot>, Vec<Rc<WasmModule>>), String> {
+    let mut bots = vec![];
+    let mut modules = vec![];
+    for name in options.bots.iter() {
+        if name.ends_with(".wasm") || name.ends_with(".wat") {
+            let (bot, module) = sandbox::load_bot(name, options.fuel, options.memory)?;
+            bots.push(bot);
+            modules.push(module);
+        } else {
+            let bot = tournament::builtin_bots()
+                .into_iter()
+                .find(|bot| &bot.name == name)
+                .ok_or_else(|| format!("unknown bot {} (try --list)", name))?;
+            bots.push(bot);
+        }
+    }
+    Ok((bots, modules))
+}
+
+fn print_stats(bots: &[Bot], stats: &[BotStats], ratings: Option<&[f64]>) {
+    let mut order: Vec<usize> = (0..bots.len()).collect();
+    if let Some(ratings) = ratings {
+        order.sort_by(|a, b| ratings[*b].partial_cmp(&ratings[*a]).unwrap());
+    }
+
+    let name_width = bots
+        .iter()
+        .map(|bot| bot.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(12);
+    print!(
+        "{:<width$} {:>6} {:>10} {:>10} {:>10} {:>8}",
+        "bot",
+        "games",
+        "score",
+        "length",
+        "survived",
+        "wins",
+        width = name_width
+    );
+    if ratings.is_some() {
+        print!(" {:>8}", "elo");
+    }
+    println!();
+
+    for index in order {
+        let stat = &stats[index];
+        print!(
+            "{:<width$} {:>6} {:>10.2} {:>10.2} {:>10.1} {:>7.1}%",
+            bots[index].name,
+            stat.games,
+            stat.mean_score(),
+            stat.mean_length(),
+            stat.mean_survived(),
+            stat.win_rate() * 100.,
+            width = name_width
+        );
+        if let Some(ratings) = ratings {
+            print!(" {:>8.0}", ratings[index]);
+        }
+        println!();
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    // the same bot can be named twice, so each entry gets its own stats
+    let (bots, modules) = find_bots(&options)?;
+    let mut stats = vec![BotStats::default(); bots.len()];
+    let mut notable = Notable::default();
+    let mut seed = options.seed;
+
+    if options.is_tournament {
+        let mut ratings = vec![tournament::ELO_START; bots.len()];
+        for a in 0..bots.len() {
+            for b in a + 1..bots.len() {
+                for game in 0..options.games {
+                    let (first, second) = tournament::seats(a, b, game);
+                    let result = tournament::play_game(
+                        &options.settings,
+                        seed,
+                        &[&bots[first], &bots[second]],
+                        options.max_ticks,
+                    );
+                    seed += 1;
+
+                    for (index, bot) in [first, second].iter().enumerate() {
+                        if index < result.scores.len() {
+                            stats[*bot].record(&result, index);
+                        }
+                    }
+                    let score = result.first_seat_score();
+                    let (mut first_rating, mut second_rating) = (ratings[first], ratings[second]);
+                    tournament::update_elo(&mut first_rating, &mut second_rating, score);
+                    ratings[first] = first_rating;
+                    ratings[second] = second_rating;
+                    notable.consider(result);
+                }
+            }
+        }
+        print_stats(&bots, &stats, Some(&ratings));
+    } else {
+        let players: Vec<&Bot> = bots.iter().collect();
+        for _ in 0..options.games {
+            let result =
+                tournament::play_game(&options.settings, seed, &players, options.max_ticks);
+            seed += 1;
+            for (index, stat) in stats.iter_mut().enumerate().take(result.scores.len()) {
+                stat.record(&result, index);
+            }
+            notable.consider(result);
+        }
+        print_stats(&bots, &stats, None);
+    }
+
+    for module in modules.iter() {
+        if module.forfeits() > 0 {
+            println!("{} forfeited {} moves", module.name(), module.forfeits());
+        }
+    }
+
+    if let Some(dir) = &options.replays {
+        notable.save(dir)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
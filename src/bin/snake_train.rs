@@ -0,0 +1,169 @@
+// evolves neural network bots, and saves the best one somewhere the browser build can pick it up
+use std::{env, fs, path::PathBuf, process};
+
+use rusty_snake::game::evolve::{EvolveConfig, Trainer};
+
+const USAGE: &str = "usage: snake_train [options]
+
+evolves a population of small neural networks that steer a snake, playing alone with
+self-collisions on. Every genome plays the same games each generation, the fittest carry on
+untouched, and the rest of the next generation are their mutated children. To play against the
+best one in the browser, copy best.genome over src/game/genomes/neural.genome and rebuild
+
+options:
+  --generations N        how many generations to run (default 100)
+  --population N         genomes per generation (default 100)
+  --elite N              fittest genomes kept as they are (default 5)
+  --games N              games each genome plays per generation (default 5)
+  --hidden N,N...        sizes of the hidden layers (default 16)
+  --mutation-rate X      chance each weight of a child gets nudged (default 0.05)
+  --mutation-strength X  how far, as a standard deviation (default 0.3)
+  --width N              board width (default 30)
+  --height N             board height (default 20)
+  --apples N             apples on the board at once (default 5)
+  --seed N               seed for the first population and its games (default 0)
+  --checkpoint-dir DIR   where checkpoints and best.genome go (default checkpoints)
+  --every N              save a checkpoint every N generations (default 10)
+  --resume FILE          carry on from a checkpoint";
+
+struct Options {
+    config: EvolveConfig,
+    generations: u32,
+    checkpoint_dir: PathBuf,
+    every: u32,
+    resume: Option<PathBuf>,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let (mut width, mut height) = (30, 20);
+    let mut config = EvolveConfig::new(width, height);
+    let mut options = Options {
+        config: config.clone(),
+        generations: 100,
+        checkpoint_dir: PathBuf::from("checkpoints"),
+        every: 10,
+        resume: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--generations" => options.generations = parse_number(&arg, args.next())?,
+            "--population" => config.population = parse_number(&arg, args.next())?,
+            "--elite" => config.elite = parse_number(&arg, args.next())?,
+            "--games" => config.games = parse_number(&arg, args.next())?,
+            "--hidden" => {
+                let sizes = args.next().ok_or("--hidden needs layer sizes")?;
+                config.hidden = sizes
+                    .split(',')
+                    .map(|size| parse_number("--hidden", Some(size.to_string())))
+                    .collect::<Result<Vec<usize>, String>>()?;
+            }
+            "--mutation-rate" => config.mutation_rate = parse_number(&arg, args.next())?,
+            "--mutation-strength" => config.mutation_strength = parse_number(&arg, args.next())?,
+            "--width" => width = parse_number(&arg, args.next())?,
+            "--height" => height = parse_number(&arg, args.next())?,
+            "--apples" => config.settings.num_apples = parse_number(&arg, args.next())?,
+            "--seed" => config.seed = parse_number(&arg, args.next())?,
+            "--checkpoint-dir" => {
+                options.checkpoint_dir =
+                    PathBuf::from(args.next().ok_or("--checkpoint-dir needs a directory")?)
+            }
+            "--every" => options.every = parse_number(&arg, args.next())?,
+            "--resume" => {
+                options.resume = Some(PathBuf::from(
+                    args.next().ok_or("--resume needs a checkpoint")?,
+                ))
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if width <= 0 || height <= 0 {
+        return Err("the board needs to be at least 1x1".to_string());
+    }
+    config.settings.width = width;
+    config.settings.height = height;
+    config.max_idle_ticks = (width * height) as u64;
+    if config.population == 0 || config.hidden.contains(&0) {
+        return Err("the population and its layers can't be empty".to_string());
+    }
+    options.config = config;
+    options.every = options.every.max(1);
+    Ok(Some(options))
+}
+
+fn write(path: &PathBuf, text: &str) -> Result<(), String> {
+    fs::write(path, text).map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
+
+fn save(trainer: &Trainer, dir: &PathBuf) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    let checkpoint = dir.join(format!("gen-{}.checkpoint", trainer.generation));
+    write(&checkpoint, &trainer.to_checkpoint())?;
+    if let Some(best) = &trainer.best {
+        let text = format!(
+            "# fitness {} after {} generations\n{}",
+            best.fitness,
+            trainer.generation,
+            best.network.to_text()
+        );
+        write(&dir.join("best.genome"), &text)?;
+    }
+    println!("saved {}", checkpoint.display());
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut trainer = match &options.resume {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+            let trainer = Trainer::from_checkpoint(options.config, &text)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            println!("resuming from generation {}", trainer.generation);
+            trainer
+        }
+        None => Trainer::new(options.config),
+    };
+
+    println!(
+        "{:>10} {:>10} {:>10} {:>10}",
+        "generation", "best", "mean", "score"
+    );
+    let last = trainer.generation + options.generations;
+    while trainer.generation < last {
+        let stats = trainer.step();
+        println!(
+            "{:>10} {:>10.3} {:>10.3} {:>10.2}",
+            stats.generation, stats.best, stats.mean, stats.best_score
+        );
+        if trainer.generation % options.every == 0 || trainer.generation == last {
+            save(&trainer, &options.checkpoint_dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
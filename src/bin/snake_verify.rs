@@ -0,0 +1,122 @@
+// checks submitted scores by playing their replays back
+use std::{env, fs, process};
+
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::verify::{self, Submission};
+
+const USAGE: &str = "usage: snake_verify [options] <file>...
+
+plays back each submission (a replay with the scores it claims, see src/game/verify.rs) from
+scratch, and says whether every claimed score is what really happened. If not, it says the
+first update where they part ways. Exits with an error if any are rejected
+
+options:
+  --max-ticks N  turn down anything longer than this without playing it (default 100000)
+  --claim        instead of checking them, print the honest submission for each plain replay";
+
+struct Options {
+    max_ticks: u64,
+    is_claim: bool,
+    files: Vec<String>,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", flag, value))
+}
+
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut options = Options {
+        max_ticks: 100_000,
+        is_claim: false,
+        files: vec![],
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-ticks" => options.max_ticks = parse_number(&arg, args.next())?,
+            "--claim" => options.is_claim = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(None);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+
+    if options.files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Some(options))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut rejected = 0;
+    for file in options.files.iter() {
+        let text =
+            fs::read_to_string(file).map_err(|e| format!("couldn't read {}: {}", file, e))?;
+
+        if options.is_claim {
+            let replay = Replay::parse(&text).map_err(|e| format!("{}: {}", file, e))?;
+            print!("{}", Submission::new(replay).to_text());
+            continue;
+        }
+
+        let submission = match Submission::parse(&text) {
+            Ok(submission) => submission,
+            Err(err) => {
+                println!("{}: rejected, {}", file, err);
+                rejected += 1;
+                continue;
+            }
+        };
+        match verify::verify(&submission, options.max_ticks) {
+            Ok(verified) => println!(
+                "{}: accepted, scored {} in {} updates{}",
+                file,
+                verified.score,
+                verified.ticks,
+                if verified.is_game_over {
+                    ""
+                } else {
+                    " (unfinished)"
+                }
+            ),
+            Err(rejection) => {
+                match rejection.tick {
+                    Some(tick) => println!(
+                        "{}: rejected at update {}, {}",
+                        file, tick, rejection.reason
+                    ),
+                    None => println!("{}: rejected, {}", file, rejection.reason),
+                }
+                rejected += 1;
+            }
+        }
+    }
+
+    if rejected > 0 {
+        return Err(format!(
+            "{} of {} submissions rejected",
+            rejected,
+            options.files.len()
+        ));
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|options| match options {
+        Some(options) => run(options),
+        None => Ok(()),
+    });
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,266 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::controller::{BoardView, Command, SnakeController};
+use super::simulation::GameRng;
+use super::spawn::flood_fill;
+use super::vector::Vector2D;
+
+// what the computer opponents are called, in the slots after the human players
+pub const AI_NAMES: [&str; 3] = ["CPU 1", "CPU 2", "CPU 3"];
+
+const DIRECTIONS: [Vector2D; 4] = [
+    Vector2D { x: 0, y: -1 },
+    Vector2D { x: 0, y: 1 },
+    Vector2D { x: -1, y: 0 },
+    Vector2D { x: 1, y: 0 },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    // how many moves along a path we play out before checking we haven't boxed ourselves in
+    fn lookahead(self) -> usize {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Medium => 8,
+            Difficulty::Hard => usize::MAX,
+        }
+    }
+
+    // how many updates go by between decisions
+    fn reaction_delay(self) -> u32 {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Medium => 1,
+            Difficulty::Hard => 0,
+        }
+    }
+
+    // chance that a decision is just a random direction
+    fn mistake_rate(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.1,
+            Difficulty::Medium => 0.03,
+            Difficulty::Hard => 0.,
+        }
+    }
+}
+
+// the bits of the board the AI actually looks at when it's deciding where to go
+struct Surroundings<'a> {
+    width: i32,
+    height: i32,
+    // walls and every snake, including this one
+    blocked: &'a HashSet<Vector2D>,
+    apples: &'a [Vector2D],
+    // this snake, from head to tail
+    body: VecDeque<Vector2D>,
+    direction: Vector2D,
+}
+
+impl<'a> Surroundings<'a> {
+    fn step(&self, pos: &Vector2D, direction: &Vector2D) -> Vector2D {
+        Vector2D {
+            x: (pos.x + direction.x).rem_euclid(self.width),
+            y: (pos.y + direction.y).rem_euclid(self.height),
+        }
+    }
+}
+
+pub struct AiPlayer {
+    pub difficulty: Difficulty,
+    updates_until_decision: u32,
+    // seeded from the game and which snake this is on the first move, so the same game plays
+    // out the same way every time
+    rng: Option<GameRng>,
+}
+
+impl SnakeController for AiPlayer {
+    fn name(&self) -> &str {
+        match self.difficulty {
+            Difficulty::Easy => "easy ai",
+            Difficulty::Medium => "medium ai",
+            Difficulty::Hard => "hard ai",
+        }
+    }
+
+    fn next_command(&mut self, board: &BoardView) -> Command {
+        let seed = board.seed ^ board.me as u64;
+        self.rng.get_or_insert_with(|| GameRng::seed_from_u64(seed));
+        let blocked = board.blocked_squares();
+        let me = board.me();
+        let surroundings = Surroundings {
+            width: board.width,
+            height: board.height,
+            blocked: &blocked,
+            apples: &board.apples,
+            body: me.body(),
+            direction: me.head_direction,
+        };
+
+        match self.choose_direction(&surroundings) {
+            Some(direction) => Command::towards(direction),
+            None => Command::Continue,
+        }
+    }
+}
+
+impl AiPlayer {
+    pub fn new(difficulty: Difficulty) -> AiPlayer {
+        AiPlayer {
+            difficulty,
+            updates_until_decision: 0,
+            rng: None,
+        }
+    }
+
+    // None means keep going the way we're going
+    fn choose_direction(&mut self, surroundings: &Surroundings) -> Option<Vector2D> {
+        if self.updates_until_decision > 0 {
+            self.updates_until_decision -= 1;
+            return None;
+        }
+        self.updates_until_decision = self.difficulty.reaction_delay();
+
+        let rng = self.rng.as_mut().expect("seeded on the first move");
+        if rng.gen_bool(self.difficulty.mistake_rate()) {
+            let backwards = Vector2D {
+                x: -surroundings.direction.x,
+                y: -surroundings.direction.y,
+            };
+            let choices: Vec<Vector2D> = DIRECTIONS
+                .iter()
+                .copied()
+                .filter(|direction| *direction != backwards)
+                .collect();
+            return choices.choose(rng).copied();
+        }
+
+        self.direction_to_apple(surroundings)
+            .or_else(|| self.direction_to_survive(surroundings))
+    }
+
+    // heads for the closest apple we can get to without trapping ourselves afterwards
+    fn direction_to_apple(&self, surroundings: &Surroundings) -> Option<Vector2D> {
+        let head = *surroundings.body.front()?;
+        let mut came_from: HashMap<Vector2D, (Vector2D, Vector2D)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(head);
+        let mut apples_by_distance = vec![];
+        while let Some(pos) = queue.pop_front() {
+            if surroundings.apples.contains(&pos) {
+                apples_by_distance.push(pos);
+            }
+
+            for direction in DIRECTIONS.iter() {
+                let next = surroundings.step(&pos, direction);
+                if !surroundings.blocked.contains(&next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, (pos, *direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for apple in apples_by_distance {
+            let mut path = vec![];
+            let mut pos = apple;
+            while pos != head {
+                let (previous, direction) = came_from[&pos];
+                path.push((pos, direction));
+                pos = previous;
+            }
+            path.reverse();
+
+            if self.survives(surroundings, &path) {
+                return Some(path[0].1);
+            }
+        }
+
+        None
+    }
+
+    // plays the path out (up to our lookahead) and checks we can still reach our own tail
+    fn survives(&self, surroundings: &Surroundings, path: &[(Vector2D, Vector2D)]) -> bool {
+        let mut body = surroundings.body.clone();
+        let steps = path.len().min(self.difficulty.lookahead());
+        for (index, (pos, _)) in path.iter().take(steps).enumerate() {
+            body.push_front(*pos);
+            // the last step of a full path eats the apple, so we grow instead of moving the tail
+            if index + 1 < path.len() {
+                body.pop_back();
+            }
+        }
+
+        can_reach_tail(surroundings, &body)
+    }
+
+    // no apple is safe, so just stay alive: prefer moves that keep our tail in reach, then
+    // whichever has the most room
+    fn direction_to_survive(&self, surroundings: &Surroundings) -> Option<Vector2D> {
+        let head = *surroundings.body.front()?;
+        let mut best: Option<(bool, usize, Vector2D)> = None;
+        for direction in DIRECTIONS.iter() {
+            let next = surroundings.step(&head, direction);
+            if surroundings.blocked.contains(&next) {
+                continue;
+            }
+
+            let mut body = surroundings.body.clone();
+            body.push_front(next);
+            body.pop_back();
+            let score = (
+                can_reach_tail(surroundings, &body),
+                open_area(surroundings, &body),
+                *direction,
+            );
+            if best.is_none_or(|best| (score.0, score.1) > (best.0, best.1)) {
+                best = Some(score);
+            }
+        }
+
+        best.map(|best| best.2)
+    }
+}
+
+fn body_obstacles(surroundings: &Surroundings, body: &VecDeque<Vector2D>) -> HashSet<Vector2D> {
+    // the snake might have moved since the board was drawn up, so swap its old body for the
+    // new one
+    let mut blocked = surroundings.blocked.clone();
+    for pos in surroundings.body.iter() {
+        blocked.remove(pos);
+    }
+    blocked.extend(body.iter().copied());
+    blocked
+}
+
+fn can_reach_tail(surroundings: &Surroundings, body: &VecDeque<Vector2D>) -> bool {
+    if body.len() < 3 {
+        return true;
+    }
+
+    let head = body[0];
+    let tail = body[body.len() - 1];
+    let mut blocked = body_obstacles(surroundings, body);
+    blocked.remove(&tail);
+    flood_fill(surroundings.width, surroundings.height, &blocked, &head).contains(&tail)
+}
+
+fn open_area(surroundings: &Surroundings, body: &VecDeque<Vector2D>) -> usize {
+    let blocked = body_obstacles(surroundings, body);
+    flood_fill(surroundings.width, surroundings.height, &blocked, &body[0]).len()
+}
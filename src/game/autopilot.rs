@@ -0,0 +1,195 @@
+use super::ai::{AiPlayer, Difficulty};
+use super::controller::{BoardView, Command, SnakeController};
+use super::vector::Vector2D;
+
+const DIRECTIONS: [Vector2D; 4] = [
+    Vector2D { x: 0, y: -1 },
+    Vector2D { x: 0, y: 1 },
+    Vector2D { x: -1, y: 0 },
+    Vector2D { x: 1, y: 0 },
+];
+
+// shortcuts stop once the snake takes up this much of the board, since there's not much room
+// left to get clever with
+const SHORTCUT_LIMIT: f64 = 0.5;
+// how far short of our own tail a shortcut has to land, to leave room for growing
+const SHORTCUT_MARGIN: usize = 3;
+
+// a loop through every square on the board, so following it can never run into yourself
+struct HamiltonianCycle {
+    width: i32,
+    height: i32,
+    // where each square (indexed y * width + x) comes in the cycle
+    order: Vec<usize>,
+}
+
+impl HamiltonianCycle {
+    // columns snake down and back up below the top row, and the top row is the way home:
+    //
+    //   < < < < < <
+    //   v ^ v ^ v ^
+    //   v ^ v ^ v ^
+    //   > ^ > ^ > ^
+    //
+    // which needs an even number of columns, so odd boards get turned on their side first
+    fn new(width: i32, height: i32) -> Option<HamiltonianCycle> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+
+        let transposed = width % 2 != 0;
+        if transposed && height % 2 != 0 {
+            // no such cycle exists when both sides are odd
+            return None;
+        }
+
+        let (columns, rows) = if transposed {
+            (height, width)
+        } else {
+            (width, height)
+        };
+
+        let mut squares = vec![];
+        for column in 0..columns {
+            if column % 2 == 0 {
+                for row in 1..rows {
+                    squares.push((column, row));
+                }
+            } else {
+                for row in (1..rows).rev() {
+                    squares.push((column, row));
+                }
+            }
+        }
+        for column in (0..columns).rev() {
+            squares.push((column, 0));
+        }
+
+        let mut order = vec![0; (width * height) as usize];
+        for (index, (column, row)) in squares.into_iter().enumerate() {
+            let (x, y) = if transposed {
+                (row, column)
+            } else {
+                (column, row)
+            };
+            order[(y * width + x) as usize] = index;
+        }
+
+        Some(HamiltonianCycle {
+            width,
+            height,
+            order,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    fn index_of(&self, pos: &Vector2D) -> usize {
+        self.order[(pos.y * self.width + pos.x) as usize]
+    }
+
+    // how many steps along the cycle it takes to get from one square to the other
+    fn distance(&self, from: &Vector2D, to: &Vector2D) -> usize {
+        (self.index_of(to) + self.len() - self.index_of(from)) % self.len()
+    }
+}
+
+// plays by following a Hamiltonian cycle, cutting across it towards apples whenever that
+// can't possibly trap the snake. Falls back to the regular AI when the board isn't one it can
+// solve, like when there are walls or other snakes in the way
+pub struct Autopilot {
+    cycle: Option<HamiltonianCycle>,
+    fallback: AiPlayer,
+}
+
+impl Default for Autopilot {
+    fn default() -> Autopilot {
+        Autopilot::new()
+    }
+}
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot {
+            cycle: None,
+            fallback: AiPlayer::new(Difficulty::Hard),
+        }
+    }
+
+    fn follow_cycle(&self, cycle: &HamiltonianCycle, board: &BoardView) -> Option<Command> {
+        let me = board.me();
+        let head = me.head;
+        let blocked = board.blocked_squares();
+
+        // the body trails behind the head along the cycle, so everything from just past the
+        // head up to the tail is free
+        let free_ahead = if me.path.len() <= 1 {
+            cycle.len()
+        } else {
+            cycle.distance(&head, &me.tail)
+        };
+        let target = board
+            .apples
+            .iter()
+            .map(|apple| cycle.distance(&head, apple))
+            .min()
+            .unwrap_or(1);
+        let can_shortcut = (me.path.len() as f64) < cycle.len() as f64 * SHORTCUT_LIMIT;
+
+        let mut best: Option<(usize, Vector2D)> = None;
+        for direction in DIRECTIONS.iter() {
+            let next = Vector2D {
+                x: head.x + direction.x,
+                y: head.y + direction.y,
+            };
+            if next.x < 0 || next.y < 0 || next.x >= cycle.width || next.y >= cycle.height {
+                continue;
+            }
+            if blocked.contains(&next) {
+                continue;
+            }
+
+            let distance = cycle.distance(&head, &next);
+            let allowed = distance == 1
+                || (can_shortcut && distance <= target && distance + SHORTCUT_MARGIN < free_ahead);
+            if allowed && best.is_none_or(|(best_distance, _)| distance > best_distance) {
+                best = Some((distance, *direction));
+            }
+        }
+
+        best.map(|(_, direction)| Command::towards(direction))
+    }
+}
+
+impl SnakeController for Autopilot {
+    fn name(&self) -> &str {
+        "autopilot"
+    }
+
+    fn next_command(&mut self, board: &BoardView) -> Command {
+        let needs_new_cycle = self
+            .cycle
+            .as_ref()
+            .is_none_or(|cycle| cycle.width != board.width || cycle.height != board.height);
+        if needs_new_cycle {
+            self.cycle = HamiltonianCycle::new(board.width, board.height);
+        }
+
+        let alone = board
+            .snakes
+            .iter()
+            .enumerate()
+            .all(|(index, snake)| index == board.me || !snake.is_alive);
+        if let Some(cycle) = &self.cycle {
+            if alone && board.walls.is_empty() {
+                if let Some(command) = self.follow_cycle(cycle, board) {
+                    return command;
+                }
+            }
+        }
+
+        self.fallback.next_command(board)
+    }
+}
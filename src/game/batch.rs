@@ -0,0 +1,117 @@
+use rayon::prelude::*;
+
+use super::env::{Env, EnvConfig, Info};
+
+// a whole lot of independent environments stepped at once, spread across every core. Results
+// come back in flat buffers, one slot (or observation_len slots) per environment, and
+// environments that finish start over on their own
+pub struct BatchEnv {
+    envs: Vec<Env>,
+    // the seed each environment's next episode starts from
+    next_seeds: Vec<u64>,
+    observation_len: usize,
+
+    // num_envs * observation_len. When an episode ends this already holds the first
+    // observation of the next one
+    pub observations: Vec<f32>,
+    pub rewards: Vec<f32>,
+    pub dones: Vec<bool>,
+    // how the episode that just ended went, for every environment whose done is set
+    pub final_infos: Vec<Info>,
+}
+
+impl BatchEnv {
+    // environment i plays seeds base_seed + i, then base_seed + i + num_envs, and so on, so no
+    // two episodes in the batch share a seed
+    pub fn new(config: EnvConfig, num_envs: usize, base_seed: u64) -> BatchEnv {
+        let seeds: Vec<u64> = (0..num_envs as u64).map(|i| base_seed + i).collect();
+        BatchEnv::with_seeds(config, &seeds)
+    }
+
+    pub fn with_seeds(config: EnvConfig, seeds: &[u64]) -> BatchEnv {
+        let num_envs = seeds.len();
+        let envs: Vec<Env> = (0..num_envs).map(|_| Env::new(config.clone())).collect();
+        let observation_len = envs.first().map_or(0, |env| env.observation_len());
+
+        let mut batch = BatchEnv {
+            envs,
+            next_seeds: seeds.to_vec(),
+            observation_len,
+            observations: vec![0.; num_envs * observation_len],
+            rewards: vec![0.; num_envs],
+            dones: vec![false; num_envs],
+            final_infos: vec![Info::default(); num_envs],
+        };
+        batch.reset();
+        batch
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn observation_len(&self) -> usize {
+        self.observation_len
+    }
+
+    pub fn env(&self, index: usize) -> &Env {
+        &self.envs[index]
+    }
+
+    // starts every environment on its next seed
+    pub fn reset(&mut self) -> &[f32] {
+        let num_envs = self.envs.len() as u64;
+        let observation_len = self.observation_len.max(1);
+        self.envs
+            .par_iter_mut()
+            .zip(self.next_seeds.par_iter_mut())
+            .zip(self.observations.par_chunks_mut(observation_len))
+            .for_each(|((env, seed), observation)| {
+                env.reset(*seed);
+                *seed += num_envs;
+                env.write_observation(observation);
+            });
+
+        for (reward, done) in self.rewards.iter_mut().zip(self.dones.iter_mut()) {
+            *reward = 0.;
+            *done = false;
+        }
+        &self.observations
+    }
+
+    // takes one action per environment, in the same order as everything else
+    pub fn step(&mut self, actions: &[usize]) {
+        assert_eq!(
+            actions.len(),
+            self.envs.len(),
+            "need one action per environment"
+        );
+
+        let num_envs = self.envs.len() as u64;
+        let observation_len = self.observation_len.max(1);
+        self.envs
+            .par_iter_mut()
+            .zip(self.next_seeds.par_iter_mut())
+            .zip(self.observations.par_chunks_mut(observation_len))
+            .zip(self.rewards.par_iter_mut())
+            .zip(self.dones.par_iter_mut())
+            .zip(self.final_infos.par_iter_mut())
+            .zip(actions.par_iter())
+            .for_each(
+                |((((((env, seed), observation), reward), done), final_info), action)| {
+                    *reward = env.act(*action);
+                    *done = env.is_done();
+                    if *done {
+                        *final_info = env.info();
+                        env.reset(*seed);
+                        *seed += num_envs;
+                    }
+                    env.write_observation(observation);
+                },
+            );
+    }
+}
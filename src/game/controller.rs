@@ -0,0 +1,110 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::vector::Vector2D;
+
+// what a snake can be told to do on any given move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // keep going the same way
+    Continue,
+    Up,
+    Down,
+    Left,
+    Right,
+    SwapHead,
+}
+
+impl Command {
+    // NOTE: y is flipped here since that's the default for rendering
+    pub fn direction(self) -> Option<Vector2D> {
+        match self {
+            Command::Up => Some(Vector2D { x: 0, y: -1 }),
+            Command::Down => Some(Vector2D { x: 0, y: 1 }),
+            Command::Left => Some(Vector2D { x: -1, y: 0 }),
+            Command::Right => Some(Vector2D { x: 1, y: 0 }),
+            Command::Continue | Command::SwapHead => None,
+        }
+    }
+
+    pub fn towards(direction: Vector2D) -> Command {
+        match direction {
+            Vector2D { x: 0, y: -1 } => Command::Up,
+            Vector2D { x: 0, y: 1 } => Command::Down,
+            Vector2D { x: -1, y: 0 } => Command::Left,
+            Vector2D { x: 1, y: 0 } => Command::Right,
+            _ => Command::Continue,
+        }
+    }
+}
+
+// a read-only look at one snake
+pub struct SnakeView<'a> {
+    // stored tail-first while head_is_tail is set, and head-first otherwise
+    pub path: &'a VecDeque<Vector2D>,
+    pub head: Vector2D,
+    pub tail: Vector2D,
+    pub head_direction: Vector2D,
+    pub head_is_tail: bool,
+    pub is_alive: bool,
+    pub score: u32,
+}
+
+impl<'a> SnakeView<'a> {
+    // the snake's squares, starting from whichever end is currently the head
+    pub fn body(&self) -> VecDeque<Vector2D> {
+        if self.head_is_tail {
+            self.path.iter().rev().copied().collect()
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+// a read-only look at the whole board, from the point of view of one snake
+pub struct BoardView<'a> {
+    pub width: i32,
+    pub height: i32,
+    // which of the snakes is the one being controlled
+    pub me: usize,
+    pub snakes: Vec<SnakeView<'a>>,
+    pub apples: Vec<Vector2D>,
+    pub walls: &'a [Vector2D],
+    // what the game was seeded with, so bots that roll dice can roll the same ones every time
+    // it's played
+    pub seed: u64,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn me(&self) -> &SnakeView<'a> {
+        &self.snakes[self.me]
+    }
+
+    // walls and every live snake, including this one
+    pub fn blocked_squares(&self) -> HashSet<Vector2D> {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.is_alive)
+            .flat_map(|snake| snake.path.iter())
+            .chain(self.walls.iter())
+            .copied()
+            .collect()
+    }
+
+    // the square you end up on going one step in a direction, wrapping around the edges
+    pub fn step(&self, pos: &Vector2D, direction: &Vector2D) -> Vector2D {
+        Vector2D {
+            x: (pos.x + direction.x).rem_euclid(self.width),
+            y: (pos.y + direction.y).rem_euclid(self.height),
+        }
+    }
+}
+
+// anything that can steer a snake in place of the keyboard. It gets asked once per move
+pub trait SnakeController {
+    fn name(&self) -> &str;
+
+    fn next_command(&mut self, board: &BoardView) -> Command;
+}
+
+// builds a fresh controller for every game, so whatever's plugged into a slot survives resets
+pub type ControllerFactory = Box<dyn Fn() -> Box<dyn SnakeController>>;
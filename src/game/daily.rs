@@ -0,0 +1,227 @@
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+
+use super::leaderboard::SECONDS_PER_DAY;
+use super::level::Level;
+use super::replay::Replay;
+use super::rules::CollisionRule;
+use super::simulation::{self, GameRng, Settings, Simulation};
+
+// the built-in levels are drawn for this size, so they only turn up on boards this big
+const LEVEL_SIZE: (i32, i32) = (30, 20);
+// every board size the game's played at, which is the browser's and these
+pub const SIZES: [(i32, i32); 4] = [LEVEL_SIZE, (24, 16), (20, 20), (36, 24)];
+// only the spawn strategies that work on any level, which leaves out scripted
+const NUM_SPAWN_STRATEGIES: usize = 5;
+// mixed into the day so the challenges don't line up with anything else seeded by small numbers
+const SALT: u64 = 0x5eed_da11;
+
+// The same game for everyone on the same day: the board, the rules, the level and the seed are
+// all picked from the date, so scores from that day can be compared. Days change over at
+// midnight UTC
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    // days since the 1st of January 1970
+    pub day: u64,
+    pub settings: Settings,
+    pub seed: u64,
+}
+
+impl Challenge {
+    pub fn for_day(day: u64) -> Challenge {
+        let mut rng = GameRng::seed_from_u64(day ^ SALT);
+        let (width, height) = *SIZES.choose(&mut rng).unwrap();
+        let mut settings = Settings::new(width, height);
+        settings.num_apples = rng.gen_range(1, 6);
+        if rng.gen_bool(0.5) {
+            settings.apple_lifetime = Some(simulation::APPLE_LIFETIME);
+        }
+        // drawn as a u32, since a usize takes twice the randomness on 64-bit machines as it does
+        // in the browser, and the leaderboard server has to come up with the same challenge
+        settings.spawn_strategy = rng.gen_range(0, NUM_SPAWN_STRATEGIES as u32) as usize;
+        if rng.gen_bool(0.5) {
+            settings.rules.self_collision = CollisionRule::Die;
+        }
+        settings.level = if (width, height) == LEVEL_SIZE {
+            Level::builtin().choose(&mut rng).unwrap().clone()
+        } else {
+            Level::find_builtin("empty").unwrap()
+        };
+
+        Challenge {
+            day,
+            settings,
+            seed: rng.gen(),
+        }
+    }
+
+    // today's, for a time in seconds since 1970
+    pub fn at(time: u64) -> Challenge {
+        Challenge::for_day(time / SECONDS_PER_DAY)
+    }
+
+    pub fn date(&self) -> String {
+        date_of_day(self.day)
+    }
+
+    // daily challenges get a leaderboard of their own
+    pub fn board_key(&self) -> String {
+        format!("daily-{}", self.date())
+    }
+
+    // whether a replay is of this challenge, played alone
+    pub fn matches(&self, replay: &Replay) -> bool {
+        let challenge = Replay::new(self.settings.clone(), self.seed, replay.names.clone());
+        let played = Replay::new(replay.settings.clone(), replay.seed, replay.names.clone());
+        replay.names.len() == 1 && challenge.to_text() == played.to_text()
+    }
+
+    // something to paste into the team chat
+    pub fn result_text(&self, score: u32, length: usize, seconds: u64) -> String {
+        format!(
+            "rusty snake daily {}: score {}, length {}, {}:{:02}",
+            self.date(),
+            score,
+            length,
+            seconds / 60,
+            seconds % 60
+        )
+    }
+}
+
+// YYYY-MM-DD for a number of days since 1970, using the proleptic Gregorian calendar
+pub fn date_of_day(day: u64) -> String {
+    // counting from the 1st of March in year 0 puts leap days at the end of the year
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day_of_month)
+}
+
+// the other way around, for dates from 1970 on
+pub fn parse_date(text: &str) -> Result<u64, String> {
+    let error = || format!("expected a date like 2021-03-14, not {}", text);
+    let numbers = text
+        .split('-')
+        .map(|part| part.parse::<i64>().map_err(|_| error()))
+        .collect::<Result<Vec<i64>, String>>()?;
+    let (year, month, day_of_month) = match numbers.as_slice() {
+        &[year, month, day] if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+            (year, month, day)
+        }
+        _ => return Err(error()),
+    };
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day_of_month - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    // catches the 31st of February and friends
+    if days < 0 || date_of_day(days as u64) != text {
+        return Err(error());
+    }
+    Ok(days as u64)
+}
+
+// how today's game that counts is going
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attempt {
+    Ready,
+    // started at this many milliseconds since 1970
+    Playing(f64),
+    Done(String),
+}
+
+// The daily challenge in a browser. Only the first game of the day counts: it starts counting
+// with its first move, and starting over or leaving gives it up as it stands. Every game after
+// that is practice
+pub struct Daily {
+    pub challenge: Challenge,
+    pub attempt: Attempt,
+    // whether the game in play is one of the ones that doesn't count
+    pub is_practice: bool,
+    // what was being played before, to go back to afterwards
+    pub settings: Settings,
+}
+
+impl Daily {
+    // stored is the day of the last attempt in this browser, and its result if it finished
+    pub fn new(
+        challenge: Challenge,
+        settings: Settings,
+        stored: Option<(u64, Option<String>)>,
+    ) -> Daily {
+        let attempt = match stored {
+            Some((day, result)) if day == challenge.day => {
+                Attempt::Done(result.unwrap_or_else(|| {
+                    format!("rusty snake daily {}: didn't finish", challenge.date())
+                }))
+            }
+            _ => Attempt::Ready,
+        };
+        Daily {
+            is_practice: attempt != Attempt::Ready,
+            challenge,
+            attempt,
+            settings,
+        }
+    }
+
+    // for when a new game's set up
+    pub fn next_game(&mut self) {
+        self.is_practice = self.attempt != Attempt::Ready;
+    }
+
+    // for when the game in play makes its first move. Returns whether it's the one that counts
+    pub fn start(&mut self, now: f64) -> bool {
+        if self.is_practice || self.attempt != Attempt::Ready {
+            return false;
+        }
+        self.attempt = Attempt::Playing(now);
+        true
+    }
+
+    // for when the game in play ends, or is given up on. Returns the result if it counted
+    pub fn finish(&mut self, sim: &Simulation, now: f64) -> Option<String> {
+        let started = match self.attempt {
+            Attempt::Playing(started) => started,
+            _ => return None,
+        };
+        let snake = sim.snakes.first()?;
+        let seconds = ((now - started).max(0.) / 1000.) as u64;
+        let mut result = self
+            .challenge
+            .result_text(snake.score, snake.path.len(), seconds);
+        if !sim.is_game_over {
+            result.push_str(" (gave up)");
+        }
+        self.attempt = Attempt::Done(result.clone());
+        Some(result)
+    }
+
+    // what goes under the game over banner
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+        if let Attempt::Done(result) = &self.attempt {
+            lines.push(result.clone());
+        }
+        if self.is_practice {
+            lines.push("(practice, only the first game of the day counts)".to_string());
+        }
+        lines
+    }
+}
@@ -0,0 +1,427 @@
+use wasm_bindgen::prelude::*;
+
+use super::controller::{BoardView, Command};
+use super::simulation::{Settings, Simulation};
+use super::vector::Vector2D;
+
+pub const NUM_ACTIONS: usize = 6; // continue, up, down, left, right, swap head
+
+// how many numbers describe each square in a grid observation: our body, our head, other
+// snakes, apples and walls
+const GRID_CHANNELS: usize = 5;
+// and in an egocentric one: anything we'd run into, and apples
+const EGOCENTRIC_CHANNELS: usize = 2;
+// each ray reports how close the nearest wall, snake and apple are
+const RAY_CHANNELS: usize = 3;
+
+// the eight directions rays get cast in, relative to where the snake is heading. Each one is
+// (forward, right)
+const RAY_DIRECTIONS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct RewardConfig {
+    // for every point scored, so timed apple bonuses count too
+    pub apple: f32,
+    pub death: f32,
+    // handed out every step, usually negative so dawdling costs something
+    pub step: f32,
+    pub win: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> RewardConfig {
+        RewardConfig {
+            apple: 1.,
+            death: -1.,
+            step: -0.01,
+            win: 10.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObservationKind {
+    // the whole board, one channel at a time
+    Grid,
+    // a square window around the head, turned so the snake is always heading up
+    Egocentric { radius: i32 },
+    // distances to the nearest thing in eight directions around the head
+    Rays,
+}
+
+impl ObservationKind {
+    // accepts "grid", "rays", "egocentric" or "egocentric:<radius>"
+    pub fn parse(text: &str) -> Result<ObservationKind, String> {
+        let mut parts = text.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some("grid"), None) => Ok(ObservationKind::Grid),
+            (Some("rays"), None) => Ok(ObservationKind::Rays),
+            (Some("egocentric"), None) => Ok(ObservationKind::Egocentric { radius: 5 }),
+            (Some("egocentric"), Some(radius)) => match radius.parse() {
+                Ok(radius) if radius >= 0 => Ok(ObservationKind::Egocentric { radius }),
+                _ => Err(format!("bad egocentric radius: {}", radius)),
+            },
+            _ => Err(format!("unknown observation kind: {}", text)),
+        }
+    }
+
+    // how many numbers an observation of a board this size takes
+    pub fn len(self, width: i32, height: i32) -> usize {
+        match self {
+            ObservationKind::Grid => GRID_CHANNELS * (width * height) as usize,
+            ObservationKind::Egocentric { radius } => {
+                let side = (radius * 2 + 1) as usize;
+                EGOCENTRIC_CHANNELS * side * side
+            }
+            ObservationKind::Rays => RAY_CHANNELS * RAY_DIRECTIONS.len(),
+        }
+    }
+
+    // fills in exactly len numbers, from the point of view of board.me. Anything a controller
+    // can see, so bots get the same picture agents train on
+    pub fn write(self, board: &BoardView, out: &mut [f32]) {
+        for value in out.iter_mut() {
+            *value = 0.;
+        }
+
+        match self {
+            ObservationKind::Grid => write_grid(board, out),
+            ObservationKind::Egocentric { radius } => write_egocentric(board, out, radius),
+            ObservationKind::Rays => write_rays(board, out),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    // the agent always plays alone, so num_snakes is ignored
+    pub settings: Settings,
+    pub reward: RewardConfig,
+    pub observation: ObservationKind,
+    // episodes get cut short after this many steps without scoring, since with the default
+    // rules a snake that just goes around in circles never dies
+    pub max_idle_steps: u32,
+}
+
+impl EnvConfig {
+    pub fn new(width: i32, height: i32) -> Result<EnvConfig, String> {
+        let settings = Settings::new(width, height);
+        settings.check()?;
+        Ok(EnvConfig {
+            settings,
+            reward: RewardConfig::default(),
+            observation: ObservationKind::Grid,
+            max_idle_steps: (width * height * 2) as u32,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Info {
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u64,
+    pub won: bool,
+    pub died: bool,
+    // ended because the agent stopped scoring, rather than by the rules of the game
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+pub fn action_to_command(action: usize) -> Command {
+    match action {
+        1 => Command::Up,
+        2 => Command::Down,
+        3 => Command::Left,
+        4 => Command::Right,
+        5 => Command::SwapHead,
+        _ => Command::Continue,
+    }
+}
+
+// a gym-style wrapper around the simulation, so agents train against the same rules people
+// play by
+pub struct Env {
+    pub config: EnvConfig,
+    sim: Simulation,
+    idle_steps: u32,
+    is_truncated: bool,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Env {
+        let mut env = Env {
+            sim: Simulation::new(config.settings.clone(), 0),
+            config,
+            idle_steps: 0,
+            is_truncated: false,
+        };
+        env.reset(0);
+        env
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.sim
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        let mut settings = self.config.settings.clone();
+        settings.num_snakes = 1;
+        self.sim = Simulation::new(settings, seed);
+        self.idle_steps = 0;
+        self.is_truncated = false;
+        self.observation()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.sim.is_game_over || self.is_truncated
+    }
+
+    pub fn step(&mut self, action: usize) -> StepResult {
+        let reward = self.act(action);
+        StepResult {
+            observation: self.observation(),
+            reward,
+            done: self.is_done(),
+            info: self.info(),
+        }
+    }
+
+    // step without building an observation, for when it's going to be written somewhere else.
+    // Returns the reward
+    pub fn act(&mut self, action: usize) -> f32 {
+        if self.is_done() {
+            return 0.;
+        }
+
+        let reward_config = self.config.reward;
+        let score_before = self.sim.snakes[0].score;
+        self.sim.step(&[action_to_command(action)]);
+        let snake = &self.sim.snakes[0];
+
+        let mut reward = reward_config.step;
+        let points = snake.score - score_before;
+        if points > 0 {
+            reward += reward_config.apple * points as f32;
+            self.idle_steps = 0;
+        } else {
+            self.idle_steps += 1;
+        }
+
+        if !snake.is_alive {
+            reward += reward_config.death;
+        } else if self.sim.did_win {
+            reward += reward_config.win;
+        } else if self.config.max_idle_steps > 0 && self.idle_steps >= self.config.max_idle_steps {
+            self.is_truncated = true;
+        }
+        reward
+    }
+
+    pub fn info(&self) -> Info {
+        let snake = &self.sim.snakes[0];
+        Info {
+            score: snake.score,
+            length: snake.path.len(),
+            ticks: self.sim.tick,
+            won: self.sim.did_win,
+            died: !snake.is_alive,
+            truncated: self.is_truncated,
+        }
+    }
+
+    pub fn observation_len(&self) -> usize {
+        let settings = &self.config.settings;
+        self.config.observation.len(settings.width, settings.height)
+    }
+
+    pub fn observation(&self) -> Vec<f32> {
+        let mut observation = vec![0.; self.observation_len()];
+        self.write_observation(&mut observation);
+        observation
+    }
+
+    // fills in a slice of exactly observation_len numbers, so batches can share one buffer
+    pub fn write_observation(&self, out: &mut [f32]) {
+        self.config.observation.write(&self.sim.board_view(0), out);
+    }
+}
+
+fn write_grid(board: &BoardView, out: &mut [f32]) {
+    let width = board.width;
+    let plane = (width * board.height) as usize;
+    let index = |channel: usize, pos: &Vector2D| channel * plane + (pos.y * width + pos.x) as usize;
+
+    for (snake_index, snake) in board.snakes.iter().enumerate() {
+        if !snake.is_alive {
+            continue;
+        }
+        let is_me = snake_index == board.me;
+        let channel = if is_me { 0 } else { 2 };
+        for pos in snake.path.iter() {
+            out[index(channel, pos)] = 1.;
+        }
+        if is_me {
+            out[index(1, &snake.head)] = 1.;
+        }
+    }
+    for apple in board.apples.iter() {
+        out[index(3, apple)] = 1.;
+    }
+    for wall in board.walls.iter() {
+        out[index(4, wall)] = 1.;
+    }
+}
+
+fn write_egocentric(board: &BoardView, out: &mut [f32], radius: i32) {
+    let side = (radius * 2 + 1) as usize;
+    let plane = side * side;
+    let blocked = board.blocked_squares();
+
+    // the top row of the window is furthest ahead of the snake
+    for row in 0..side {
+        for column in 0..side {
+            let forward = radius - row as i32;
+            let right = column as i32 - radius;
+            let pos = relative_square(board, forward, right);
+            if blocked.contains(&pos) {
+                out[row * side + column] = 1.;
+            }
+            if board.apples.contains(&pos) {
+                out[plane + row * side + column] = 1.;
+            }
+        }
+    }
+}
+
+fn write_rays(board: &BoardView, out: &mut [f32]) {
+    let range = board.width.max(board.height);
+    let head = board.me().head;
+
+    // closer things give bigger numbers, and nothing in sight gives zero
+    for (ray, (forward, right)) in RAY_DIRECTIONS.iter().enumerate() {
+        let mut seen = [false; RAY_CHANNELS];
+        for distance in 1..=range {
+            let pos = relative_square(board, forward * distance, right * distance);
+            let closeness = 1. / distance as f32;
+            let found = [
+                board.walls.contains(&pos),
+                board
+                    .snakes
+                    .iter()
+                    .any(|other| other.is_alive && other.path.contains(&pos)),
+                board.apples.contains(&pos),
+            ];
+            for channel in 0..RAY_CHANNELS {
+                if found[channel] && !seen[channel] {
+                    seen[channel] = true;
+                    out[ray * RAY_CHANNELS + channel] = closeness;
+                }
+            }
+            if seen.iter().all(|seen| *seen) || pos == head {
+                break;
+            }
+        }
+    }
+}
+
+// the square this far ahead of and to the right of our head, wrapping around the edges
+fn relative_square(board: &BoardView, forward: i32, right: i32) -> Vector2D {
+    let me = board.me();
+    let heading = me.head_direction;
+    // y points down the screen, so turning right from (x, y) faces (-y, x)
+    let right_direction = Vector2D {
+        x: -heading.y,
+        y: heading.x,
+    };
+    Vector2D {
+        x: (me.head.x + heading.x * forward + right_direction.x * right).rem_euclid(board.width),
+        y: (me.head.y + heading.y * forward + right_direction.y * right).rem_euclid(board.height),
+    }
+}
+
+// the environment as seen from javascript. Seeds are u32s since u64s turn into BigInts
+#[wasm_bindgen]
+pub struct SnakeEnv {
+    env: Env,
+}
+
+#[wasm_bindgen]
+impl SnakeEnv {
+    // observation is anything ObservationKind::parse accepts
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: i32, height: i32, observation: &str) -> Result<SnakeEnv, JsValue> {
+        let mut config = EnvConfig::new(width, height).map_err(|e| JsValue::from_str(&e))?;
+        config.observation =
+            ObservationKind::parse(observation).map_err(|e| JsValue::from_str(&e))?;
+        Ok(SnakeEnv {
+            env: Env::new(config),
+        })
+    }
+
+    pub fn set_rewards(&mut self, apple: f32, death: f32, step: f32, win: f32) {
+        self.env.config.reward = RewardConfig {
+            apple,
+            death,
+            step,
+            win,
+        };
+    }
+
+    pub fn set_max_idle_steps(&mut self, max_idle_steps: u32) {
+        self.env.config.max_idle_steps = max_idle_steps;
+    }
+
+    pub fn observation_len(&self) -> usize {
+        self.env.observation_len()
+    }
+
+    pub fn num_actions(&self) -> usize {
+        NUM_ACTIONS
+    }
+
+    pub fn reset(&mut self, seed: u32) -> Vec<f32> {
+        self.env.reset(seed as u64)
+    }
+
+    // returns { observation, reward, done, info: { score, length, ticks, won, died, truncated } }
+    pub fn step(&mut self, action: usize) -> Result<JsValue, JsValue> {
+        let result = self.env.step(action);
+        let info = js_sys::Object::new();
+        set(&info, "score", result.info.score.into())?;
+        set(&info, "length", (result.info.length as u32).into())?;
+        set(&info, "ticks", (result.info.ticks as f64).into())?;
+        set(&info, "won", result.info.won.into())?;
+        set(&info, "died", result.info.died.into())?;
+        set(&info, "truncated", result.info.truncated.into())?;
+
+        let step = js_sys::Object::new();
+        let observation = js_sys::Float32Array::from(result.observation.as_slice());
+        set(&step, "observation", observation.into())?;
+        set(&step, "reward", result.reward.into())?;
+        set(&step, "done", result.done.into())?;
+        set(&step, "info", info.into())?;
+        Ok(step.into())
+    }
+}
+
+fn set(object: &js_sys::Object, key: &str, value: JsValue) -> Result<(), JsValue> {
+    js_sys::Reflect::set(object, &JsValue::from_str(key), &value)?;
+    Ok(())
+}
@@ -0,0 +1,328 @@
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use super::controller::SnakeController;
+use super::neural::{self, Network, NeuralBot};
+use super::rules::CollisionRule;
+use super::simulation::{GameRng, Settings, Simulation};
+
+// a point of fitness for every apple, and a little for staying alive, so early generations that
+// never find an apple still have something to climb
+const APPLE_FITNESS: f64 = 1.;
+const SURVIVAL_FITNESS: f64 = 0.001;
+// how many genomes get picked at random for each parent, the fittest of which wins
+const TOURNAMENT_SIZE: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct EvolveConfig {
+    pub population: usize,
+    // how many of the fittest go through to the next generation untouched
+    pub elite: usize,
+    // the chance each weight gets nudged in a child, and how far
+    pub mutation_rate: f64,
+    pub mutation_strength: f64,
+    // every genome plays the same games each generation, so they're compared fairly
+    pub games: u64,
+    pub settings: Settings,
+    // a game ends once the snake goes this long without eating, so circling forever doesn't pay
+    pub max_idle_ticks: u64,
+    pub hidden: Vec<usize>,
+    pub seed: u64,
+}
+
+impl EvolveConfig {
+    pub fn new(width: i32, height: i32) -> EvolveConfig {
+        let mut settings = Settings::new(width, height);
+        // with the default rules a snake alone on the board can't die
+        settings.rules.self_collision = CollisionRule::Die;
+        EvolveConfig {
+            population: 100,
+            elite: 5,
+            mutation_rate: 0.05,
+            mutation_strength: 0.3,
+            games: 5,
+            settings,
+            max_idle_ticks: (width * height) as u64,
+            hidden: vec![16],
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Genome {
+    pub network: Network,
+    // the mean over this generation's games, or zero before they've been played
+    pub fitness: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationStats {
+    pub generation: u32,
+    pub best: f64,
+    pub mean: f64,
+    pub best_score: f64,
+}
+
+// one game with the genome steering, giving back its fitness and the score it got
+fn play(config: &EvolveConfig, network: &Network, seed: u64) -> (f64, u32) {
+    let mut settings = config.settings.clone();
+    settings.num_snakes = 1;
+    let mut sim = Simulation::new(settings, seed);
+    let mut bot = NeuralBot::new(network.clone());
+    let mut idle_ticks = 0;
+
+    while !sim.is_game_over && idle_ticks < config.max_idle_ticks {
+        let score = sim.snakes[0].score;
+        let command = bot.next_command(&sim.board_view(0));
+        sim.step(&[command]);
+        if sim.snakes[0].score == score {
+            idle_ticks += 1;
+        } else {
+            idle_ticks = 0;
+        }
+    }
+
+    let score = sim.snakes[0].score;
+    let fitness = score as f64 * APPLE_FITNESS + sim.tick as f64 * SURVIVAL_FITNESS;
+    (fitness, score)
+}
+
+// a normally distributed number, by way of Box-Muller
+fn gaussian<R: Rng>(rng: &mut R) -> f64 {
+    let u: f64 = 1. - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2. * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+}
+
+pub struct Trainer {
+    pub config: EvolveConfig,
+    pub population: Vec<Genome>,
+    pub generation: u32,
+    // the fittest genome seen so far, in any generation
+    pub best: Option<Genome>,
+    rng: GameRng,
+}
+
+impl Trainer {
+    pub fn new(config: EvolveConfig) -> Trainer {
+        let mut rng = GameRng::seed_from_u64(config.seed);
+        let layers = Network::bot_layers(&config.hidden);
+        let population = (0..config.population)
+            .map(|_| Genome {
+                network: Network::random(layers.clone(), &mut rng),
+                fitness: 0.,
+            })
+            .collect();
+        Trainer {
+            config,
+            population,
+            generation: 0,
+            best: None,
+            rng,
+        }
+    }
+
+    // A checkpoint is the generation it was taken at, followed by every genome in the
+    // population:
+    //
+    //   generation 12
+    //   best 31.5            the fittest genome so far, which isn't always still around
+    //   layers 26 16 3
+    //   weights ...
+    //   genome 29.25         then for each genome its fitness, layers and weights
+    //   layers 26 16 3
+    //   weights ...
+    pub fn to_checkpoint(&self) -> String {
+        let mut text = format!("generation {}\n", self.generation);
+        if let Some(best) = &self.best {
+            text.push_str(&format!("best {}\n", best.fitness));
+            text.push_str(&best.network.to_text());
+        }
+        for genome in self.population.iter() {
+            text.push_str(&format!("genome {}\n", genome.fitness));
+            text.push_str(&genome.network.to_text());
+        }
+        text
+    }
+
+    // picks up where a checkpoint left off. The population and hidden layers come from the
+    // checkpoint, and everything else from the config
+    pub fn from_checkpoint(mut config: EvolveConfig, text: &str) -> Result<Trainer, String> {
+        let mut generation = None;
+        // each genome's fitness, and the lines that make up its network
+        let mut genomes: Vec<(f64, String)> = vec![];
+        let mut best = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            if line.trim().starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("line {}: {}", line_number + 1, message);
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("generation") => {
+                    generation = Some(
+                        words
+                            .next()
+                            .and_then(|word| word.parse().ok())
+                            .ok_or_else(|| error("expected a generation"))?,
+                    )
+                }
+                Some(instruction @ "best") | Some(instruction @ "genome") => {
+                    let fitness = words
+                        .next()
+                        .and_then(|word| word.parse().ok())
+                        .ok_or_else(|| error("expected a fitness"))?;
+                    if instruction == "best" {
+                        best = Some(genomes.len());
+                    }
+                    genomes.push((fitness, String::new()));
+                }
+                Some(_) => match genomes.last_mut() {
+                    Some((_, network)) => {
+                        network.push_str(line);
+                        network.push('\n');
+                    }
+                    None => return Err(error("expected a genome first")),
+                },
+                None => {}
+            }
+        }
+
+        let mut population = genomes
+            .into_iter()
+            .map(|(fitness, network)| {
+                Ok(Genome {
+                    network: Network::parse(&network)?,
+                    fitness,
+                })
+            })
+            .collect::<Result<Vec<Genome>, String>>()?;
+        let best = best.map(|index| population.remove(index));
+        if population.is_empty() {
+            return Err("the checkpoint doesn't have any genomes".to_string());
+        }
+        let generation = generation.ok_or_else(|| "missing generation".to_string())?;
+
+        let layers = &population[0].network.layers;
+        if layers[0] != neural::num_inputs() || layers[layers.len() - 1] != neural::NUM_OUTPUTS {
+            return Err("the checkpoint's networks see something different".to_string());
+        }
+        config.hidden = layers[1..layers.len() - 1].to_vec();
+        config.population = population.len();
+        // carrying on from the same seed shouldn't repeat the games already played
+        let rng = GameRng::seed_from_u64(config.seed.wrapping_add(generation as u64));
+        Ok(Trainer {
+            config,
+            population,
+            generation,
+            best,
+            rng,
+        })
+    }
+
+    // plays every genome's games, spread over however many cores we've got
+    fn evaluate(&mut self) -> f64 {
+        let config = &self.config;
+        let first_seed = config
+            .seed
+            .wrapping_add(self.generation as u64 * config.games);
+        let results: Vec<(f64, f64)> = self
+            .population
+            .par_iter()
+            .map(|genome| {
+                let (fitness, score) = (0..config.games)
+                    .map(|game| play(config, &genome.network, first_seed.wrapping_add(game)))
+                    .fold((0., 0.), |(fitness, score), (game_fitness, game_score)| {
+                        (fitness + game_fitness, score + game_score as f64)
+                    });
+                let games = config.games.max(1) as f64;
+                (fitness / games, score / games)
+            })
+            .collect();
+
+        let mut best_score: f64 = 0.;
+        for (genome, (fitness, score)) in self.population.iter_mut().zip(results) {
+            genome.fitness = fitness;
+            best_score = best_score.max(score);
+        }
+        best_score
+    }
+
+    fn pick_parent(&mut self) -> &Genome {
+        let mut best = self.rng.gen_range(0, self.population.len());
+        for _ in 1..TOURNAMENT_SIZE {
+            let other = self.rng.gen_range(0, self.population.len());
+            if self.population[other].fitness > self.population[best].fitness {
+                best = other;
+            }
+        }
+        &self.population[best]
+    }
+
+    // every weight comes from one parent or the other, and then a few get nudged
+    fn breed(&mut self) -> Genome {
+        let mother = self.pick_parent().network.clone();
+        let father = self.pick_parent().network.clone();
+        let config = &self.config;
+        let rng = &mut self.rng;
+        let weights = mother
+            .weights
+            .iter()
+            .zip(father.weights.iter())
+            .map(|(a, b)| {
+                let mut weight = if rng.gen() { *a } else { *b };
+                if rng.gen::<f64>() < config.mutation_rate {
+                    weight += (gaussian(rng) * config.mutation_strength) as f32;
+                }
+                weight
+            })
+            .collect();
+        Genome {
+            network: Network {
+                layers: mother.layers,
+                weights,
+            },
+            fitness: 0.,
+        }
+    }
+
+    // scores this generation, and replaces it with the next one
+    pub fn step(&mut self) -> GenerationStats {
+        let best_score = self.evaluate();
+        self.population
+            .sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
+
+        let fittest = &self.population[0];
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|best| fittest.fitness > best.fitness)
+        {
+            self.best = Some(fittest.clone());
+        }
+        let stats = GenerationStats {
+            generation: self.generation,
+            best: fittest.fitness,
+            mean: self
+                .population
+                .iter()
+                .map(|genome| genome.fitness)
+                .sum::<f64>()
+                / self.population.len() as f64,
+            best_score,
+        };
+
+        let elite = self.config.elite.min(self.population.len());
+        let mut next: Vec<Genome> = self.population[..elite].to_vec();
+        while next.len() < self.population.len() {
+            let child = self.breed();
+            next.push(child);
+        }
+        self.population = next;
+        self.generation += 1;
+        stats
+    }
+}
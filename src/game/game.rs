@@ -1,9 +1,14 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::{prelude::*, JsCast, JsValue};
-use web_sys::{CanvasRenderingContext2d, Document};
+use web_sys::{CanvasRenderingContext2d, Document, MessageEvent, WebSocket, XmlHttpRequest};
 
-use super::inner::{self, Inner};
+use super::controller::ControllerFactory;
+use super::inner::{self, Inner, Link};
+use super::net::Netcode;
+use super::scoreboard::Request;
+
+const RECONNECT_DELAY: i32 = 1000; // milliseconds between attempts to get back to the server
 
 pub struct Game {
     inner: Rc<RefCell<Inner>>,
@@ -29,6 +34,189 @@ impl Game {
         Ok(game)
     }
 
+    // lets a bot steer the snake in the given slot instead of the keyboard
+    pub fn plug_controller(&self, slot: usize, bot: ControllerFactory) {
+        self.inner.borrow_mut().plug_controller(slot, bot);
+    }
+
+    // plays against whoever else joins the same room on a relay server (src/bin/snake_server.rs),
+    // instead of on this keyboard. The netcode only matters if we end up hosting
+    pub fn go_online(&self, url: &str, room: &str, netcode: Netcode) -> Result<(), JsValue> {
+        self.inner.borrow_mut().go_online(url, room, netcode);
+        Game::connect(self.inner.clone(), Link::Online)
+    }
+
+    // sends this game out on a channel of the relay server, for other browsers to watch
+    pub fn broadcast(&self, url: &str, channel: &str) -> Result<(), JsValue> {
+        self.inner.borrow_mut().go_broadcast(url, channel);
+        Game::connect(self.inner.clone(), Link::Stream)
+    }
+
+    // watches whatever's being broadcast on a channel of the relay server, instead of playing
+    pub fn watch(&self, url: &str, channel: &str) -> Result<(), JsValue> {
+        self.inner.borrow_mut().go_watch(url, channel);
+        Game::connect(self.inner.clone(), Link::Stream)
+    }
+
+    // how many times each game can be rewound after dying, and how many points each one costs
+    pub fn set_rewinds(&self, rewinds_per_game: u32, penalty: u32) {
+        self.inner
+            .borrow_mut()
+            .set_rewinds(rewinds_per_game, penalty);
+    }
+
+    // plays today's daily challenge, the same game everyone else gets today
+    pub fn play_daily(&self) {
+        self.inner.borrow_mut().go_daily();
+    }
+
+    // sends every game played alone to a leaderboard server (src/bin/snake_leaderboard.rs)
+    // when it ends, under the given name, and shows the best scores on the game over screen
+    pub fn use_leaderboard(&self, url: &str, name: &str) {
+        self.inner.borrow_mut().go_leaderboard(url, name);
+    }
+
+    // watches the replay at a URL instead of playing (see replay.rs for the format)
+    pub fn view(&self, url: &str) -> Result<(), JsValue> {
+        log::info!("fetching a replay from {}", url);
+        Game::fetch(self.inner.clone(), url, |inner, text| inner.go_view(text))
+    }
+
+    // plays on from the game written out at a URL, say a puzzle (see state.rs for the format)
+    pub fn load_state(&self, url: &str) -> Result<(), JsValue> {
+        log::info!("fetching a game from {}", url);
+        Game::fetch(self.inner.clone(), url, |inner, text| inner.go_state(text))
+    }
+
+    // gets the text at a URL and hands it to Inner
+    fn fetch<F>(inner: Rc<RefCell<Inner>>, url: &str, on_load: F) -> Result<(), JsValue>
+    where
+        F: FnOnce(&mut Inner, &str) + 'static,
+    {
+        let request = Rc::new(XmlHttpRequest::new()?);
+        request.open_with_async("GET", url, true)?;
+
+        {
+            let request_copy = request.clone();
+            let url = url.to_string();
+            let closure = Closure::once_into_js(move || {
+                let text = request_copy.response_text().ok().flatten();
+                match (request_copy.status().unwrap_or(0), text) {
+                    (200, Some(text)) => on_load(&mut inner.borrow_mut(), &text),
+                    (status, _) => log::warn!("couldn't get {}: {}", url, status),
+                }
+            });
+            request.set_onload(Some(closure.unchecked_ref()));
+        }
+
+        {
+            let url = url.to_string();
+            let closure = Closure::once_into_js(move || {
+                log::warn!("couldn't reach {}", url);
+            });
+            request.set_onerror(Some(closure.unchecked_ref()));
+        }
+
+        request.send()
+    }
+
+    // makes whatever requests the leaderboard's waiting on, handing each answer back to Inner
+    fn send_leaderboard_requests(inner: &Rc<RefCell<Inner>>) -> Result<(), JsValue> {
+        let requests = inner.borrow_mut().take_leaderboard_requests();
+        for Request {
+            kind,
+            method,
+            url,
+            body,
+        } in requests
+        {
+            let request = Rc::new(XmlHttpRequest::new()?);
+            request.open_with_async(method, &url, true)?;
+
+            {
+                let game_copy = inner.clone();
+                let request_copy = request.clone();
+                let kind = kind.clone();
+                let closure = Closure::once_into_js(move || {
+                    let text = request_copy.response_text().ok().flatten();
+                    let status = request_copy.status().unwrap_or(0);
+                    game_copy.borrow_mut().receive_leaderboard(
+                        &kind,
+                        status,
+                        &text.unwrap_or_default(),
+                    );
+                });
+                request.set_onload(Some(closure.unchecked_ref()));
+            }
+
+            {
+                let game_copy = inner.clone();
+                let closure = Closure::once_into_js(move || {
+                    log::warn!("couldn't reach the leaderboard at {}", url);
+                    game_copy.borrow_mut().receive_leaderboard(&kind, 0, "");
+                });
+                request.set_onerror(Some(closure.unchecked_ref()));
+            }
+
+            request.send_with_opt_str(body.as_deref())?;
+        }
+        Ok(())
+    }
+
+    // opens a connection to the server, and opens another whenever it drops
+    fn connect(inner: Rc<RefCell<Inner>>, link: Link) -> Result<(), JsValue> {
+        let url = match inner.borrow().url(link) {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+        let socket = WebSocket::new(&url)?;
+
+        {
+            let game_copy = inner.clone();
+            let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                game_copy.borrow_mut().on_open(link);
+            }) as Box<dyn FnMut(_)>);
+            socket.set_onopen(Some(closure.as_ref().unchecked_ref()));
+            closure.forget();
+        }
+
+        {
+            let game_copy = inner.clone();
+            let closure = Closure::wrap(Box::new(move |event: MessageEvent| {
+                if let Some(text) = event.data().as_string() {
+                    game_copy.borrow_mut().receive_message(link, &text);
+                }
+            }) as Box<dyn FnMut(_)>);
+            socket.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+            closure.forget();
+        }
+
+        {
+            let game_copy = inner.clone();
+            let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                game_copy.borrow_mut().on_close(link);
+                let game_copy = game_copy.clone();
+                let retry = Closure::once_into_js(move || {
+                    if let Err(err) = Game::connect(game_copy, link) {
+                        log::warn!("couldn't reconnect: {:?}", err);
+                    }
+                });
+                web_sys::window()
+                    .unwrap()
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(
+                        retry.unchecked_ref(),
+                        RECONNECT_DELAY,
+                    )
+                    .expect("Something's gone wrong with reconnecting");
+            }) as Box<dyn FnMut(_)>);
+            socket.set_onclose(Some(closure.as_ref().unchecked_ref()));
+            closure.forget();
+        }
+
+        inner.borrow_mut().set_socket(link, Some(socket));
+        Ok(())
+    }
+
     pub fn start(&self) -> Result<(), JsValue> {
         let window = web_sys::window().unwrap();
         let game = self.inner.clone();
@@ -36,6 +224,9 @@ impl Game {
             game.borrow_mut()
                 .tick()
                 .expect("Something's gone wrong with tick");
+            if let Err(err) = Game::send_leaderboard_requests(&game) {
+                log::warn!("couldn't send to the leaderboard: {:?}", err);
+            }
         }) as Box<dyn FnMut()>);
         window.set_interval_with_callback_and_timeout_and_arguments_0(
             closure.as_ref().unchecked_ref(),
@@ -62,6 +253,22 @@ impl Game {
             closure.forget();
         }
 
+        {
+            let game_copy = self.inner.clone();
+            let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+                game_copy
+                    .borrow_mut()
+                    .handle_key_up(event.key())
+                    .expect("Something's gone wrong with handle key up");
+                event.prevent_default();
+            }) as Box<dyn FnMut(_)>);
+            self.inner
+                .borrow_mut()
+                .canvas
+                .add_event_listener_with_callback("keyup", closure.as_ref().unchecked_ref())?;
+            closure.forget();
+        }
+
         {
             let game_copy = self.inner.clone();
             let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
@@ -78,6 +285,23 @@ impl Game {
             closure.forget();
         }
 
+        {
+            let game_copy = self.inner.clone();
+            let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+                // the first bit's the main button
+                let is_pressed = event.buttons() & 1 != 0;
+                game_copy
+                    .borrow_mut()
+                    .handle_mouse_move(event.x(), event.y(), is_pressed)
+                    .expect("Something's gone wrong with mouse move");
+            }) as Box<dyn FnMut(_)>);
+            self.inner
+                .borrow_mut()
+                .canvas
+                .add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
+            closure.forget();
+        }
+
         {
             let game_copy = self.inner.clone();
             let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
@@ -109,6 +333,18 @@ impl Game {
             closure.forget();
         }
 
+        {
+            // the last chance to save the game before the tab's closed or the page reloads
+            let game_copy = self.inner.clone();
+            let closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                game_copy.borrow_mut().autosave();
+            }) as Box<dyn FnMut(_)>);
+            web_sys::window()
+                .unwrap()
+                .add_event_listener_with_callback("pagehide", closure.as_ref().unchecked_ref())?;
+            closure.forget();
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,45 @@
+use super::replay::Replay;
+use super::simulation::Simulation;
+use super::snake::Snake;
+use super::verify::Submission;
+
+// A personal best played back alongside the game in play, one update of it for each of ours,
+// so there's something to race. It plays out its own game, with its own apples, but only its
+// snake gets drawn
+pub struct Ghost {
+    replay: Replay,
+    sim: Simulation,
+    pub best_score: u32,
+}
+
+impl Ghost {
+    pub fn new(best: Submission) -> Ghost {
+        Ghost {
+            sim: best.replay.start(),
+            best_score: best.claimed_score(),
+            replay: best.replay,
+        }
+    }
+
+    // catches up with the game in play, or starts over if that's gone back in time
+    pub fn follow(&mut self, tick: u64) {
+        if tick < self.sim.tick {
+            self.sim = self.replay.start();
+        }
+        while self.sim.tick < tick {
+            match self.replay.ticks.get(self.sim.tick as usize) {
+                Some(commands) => self.sim.step(commands),
+                None => break,
+            }
+        }
+    }
+
+    pub fn snake(&self) -> Option<&Snake> {
+        self.sim.snakes.first()
+    }
+
+    // what the best run had scored by this point
+    pub fn score(&self) -> u32 {
+        self.snake().map(|snake| snake.score).unwrap_or(0)
+    }
+}
@@ -1,14 +1,40 @@
 use rand::rngs::ThreadRng;
-use rand::seq::SliceRandom;
+use rand::Rng;
 use std::{
-    cmp::{max, min},
+    cmp::{max, min, Ordering},
     collections::VecDeque,
     f64,
     rc::Rc,
 };
 use tau::TAU;
 use wasm_bindgen::JsValue;
-use web_sys::CanvasRenderingContext2d;
+use web_sys::{CanvasRenderingContext2d, WebSocket};
+
+use super::ai::{AiPlayer, Difficulty, AI_NAMES};
+use super::autopilot::Autopilot;
+use super::controller::{Command, ControllerFactory, SnakeController};
+use super::daily::{Challenge, Daily};
+use super::ghost::Ghost;
+use super::leaderboard;
+use super::level::Level;
+use super::lockstep::{self, Lockstep};
+use super::net::{Message, Netcode};
+use super::neural::{Network, NeuralBot};
+use super::online::{Online, Session};
+use super::render::{self, Align, Grid, Renderer};
+use super::replay::Replay;
+use super::rewind::{self, Rewind};
+use super::rollback::{self, Rollback};
+use super::save::Save;
+use super::scoreboard::{Request, RequestKind, Scoreboard};
+use super::simulation::{self, Settings, Simulation};
+use super::snake::{self, KeyBindings, Pilot, MAX_KEY_BUFF_LEN};
+use super::spectate::Broadcaster;
+use super::state;
+use super::stream::{Role, Stream, Watching};
+use super::vector::Vector2D;
+use super::verify::Submission;
+use super::viewer::Viewer;
 
 // how many pixels from a side you have to be before we say you clicked there
 const MARGIN: i32 = 50;
@@ -16,17 +42,56 @@ const MARGIN: i32 = 50;
 pub const FPS: i32 = (0.025 * 1000.0) as i32; // 0.025 sec -> 40 fps
 const MIN_SPEED: u32 = 3; // number of frames between updates
 const MAX_SPEED: u32 = 1; // number of frames between updates
-
-const MAX_KEY_BUFF_LEN: usize = 3; // how many keys we'll keep track of before ignoring inputs
-const SNAKE_COLOR: &str = "green"; // how many keys we'll keep track of before ignoring inputs
-const HEAD_COLOR: &str = "yellow"; // how many keys we'll keep track of before ignoring inputs
-const TAIL_COLOR: &str = "yellow"; // how many keys we'll keep track of before ignoring inputs
-const APPLE_COLOR: &str = "red"; // how many keys we'll keep track of before ignoring inputs
-
-#[derive(Debug, Clone, Copy)]
-struct Vector2D {
-    x: i32,
-    y: i32,
+const ATTRACT_RESTART_FRAMES: u32 = 120; // how long the autopilot admires a finished game
+const STALL_BANNER_UPDATES: u32 = 10; // how long we wait on other players before saying so
+
+// keys that don't change the game, so they still work when it's shared with other people
+const ONLINE_KEYS: [&str; 2] = ["s", "f"];
+// keys that change the game in play, so its replay no longer matches how it started
+const RULE_KEYS: [&str; 5] = ["a", "t", "g", "c", "x"];
+// keys that still work in the daily challenge, which picks its own rules
+const DAILY_KEYS: [&str; 5] = ["s", "f", "b", "m", "y"];
+// held down after dying to go back in time
+const REWIND_KEY: &str = "Backspace";
+// watches the game that just ended, and leaves the replay viewer again
+const VIEWER_KEY: &str = "e";
+// prints the game as it stands to the console, as text that can be loaded back in
+const STATE_KEY: &str = "`";
+// pick which slot the save and load keys use
+const SAVE_SLOT_KEYS: [&str; 3] = ["1", "2", "3"];
+const SAVE_KEY: &str = ",";
+const LOAD_KEY: &str = ".";
+// followed by the slot's key, or AUTOSAVE_SLOT, where the browser keeps saved games
+const SAVE_STORAGE_PREFIX: &str = "rusty_snake_save_";
+const AUTOSAVE_SLOT: &str = "auto";
+// milliseconds between saving the game in case the page goes away
+const AUTOSAVE_INTERVAL: f64 = 30_000.;
+// shows what's going on under the hood
+const DEBUG_KEY: &str = "/";
+// moves a paused game on by exactly one update
+const STEP_KEY: &str = "]";
+const DEBUG_GRID_COLOR: &str = "#444";
+const DEBUG_ALPHA: f64 = 0.6; // how dark the overlay's background is
+const DEBUG_WIDTH: f64 = 280.;
+const DEBUG_TEXT_SIZE: f64 = 14.;
+const DEBUG_LINE_SPACING: f64 = 18.;
+const DEBUG_MARGIN: f64 = 8.;
+// where the browser remembers today's attempt at the daily challenge
+const DAILY_STORAGE_KEY: &str = "rusty_snake_daily";
+// followed by the board, where the browser keeps the best run on it
+const BEST_STORAGE_PREFIX: &str = "rusty_snake_best_";
+
+const CAMERA_ZOOM: f64 = 2.; // how much closer the camera gets when following one snake
+const TIMELINE_HEIGHT: f64 = 40.; // the strip under the board in the replay viewer
+const TIMELINE_MARGIN: f64 = 10.;
+
+const GHOST_ALPHA: f64 = 0.35;
+
+// which of the connections to the relay server something's about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Link {
+    Online,
+    Stream,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -35,41 +100,68 @@ struct FVector2D {
     y: f64,
 }
 
-enum ICellContents {
-    Empty,
-    Snake,
-    Apple,
-}
-
 pub struct Inner {
     pub width: f64,
     pub height: f64,
     pub canvas: web_sys::HtmlCanvasElement,
     pub context: Rc<CanvasRenderingContext2d>,
 
-    num_squares_x: i32,
-    num_squares_y: i32,
     rect_size: f64,
 
     should_show_focus_banner: bool,
     is_paused: bool,
-    is_game_over: bool,
-    did_win: bool,
-    score: u32,
+    // the autopilot has the first player's snake, and starts a new game whenever one ends
+    is_autopilot_on: bool,
+    frames_until_restart: u32,
     key_buff: VecDeque<String>,
 
-    apples: VecDeque<Vector2D>,
-    num_apples: usize,
-
-    is_growing: bool,
+    levels: Vec<Level>,
+    level: usize,
 
     frames_between_updates: u32,
     frames_until_update: u32,
 
-    head_direction: Vector2D,
-    head_is_tail: bool,
-    path: VecDeque<Vector2D>,
-
+    num_players: usize,
+    num_opponents: usize,
+    difficulty: Difficulty,
+    // computer opponents and the autopilot use the evolved network instead of searching
+    is_neural: bool,
+    network: Network,
+    // whatever's been plugged into each snake slot in place of the keyboard
+    bots: Vec<Option<ControllerFactory>>,
+    // one per snake, in the same order
+    pilots: Vec<Pilot>,
+    sim: Simulation,
+    // set when playing against people over the network
+    online: Option<Online>,
+    // set when broadcasting this game, or watching someone else's
+    stream: Option<Stream>,
+    // set when games played alone go on a leaderboard
+    scoreboard: Option<Scoreboard>,
+    // the game so far, for as long as it can still be played back
+    replay: Option<Replay>,
+    // whether it could go on the leaderboard or be a personal best: nobody else steered, and
+    // it wasn't rewound
+    is_fair: bool,
+    // set when watching a replay instead of playing
+    viewer: Option<Viewer>,
+    // racing the personal best on the board in play
+    is_ghost_on: bool,
+    ghost: Option<Ghost>,
+    // set while playing the daily challenge
+    daily: Option<Daily>,
+    rewind: Rewind,
+    // index into SAVE_SLOT_KEYS
+    save_slot: usize,
+    // when the game was last autosaved, in milliseconds
+    last_autosave: f64,
+    is_debug_on: bool,
+    // where the mouse last was over the canvas, in pixels
+    cursor: Option<(f64, f64)>,
+    // how long the last frame took to draw, in milliseconds
+    render_time: f64,
+
+    // only used to pick the seed for each new game
     rng: ThreadRng,
 }
 
@@ -83,67 +175,156 @@ impl Inner {
     ) -> Inner {
         let width = block_size * num_cols as f64;
         let height = block_size * num_rows as f64;
+        let levels = Level::builtin();
+        let mut settings = Settings::new(num_cols as i32, num_rows as i32);
+        settings.level = levels[0].clone();
 
         let mut inner = Inner {
-            width: width as f64,
-            height: height as f64,
-            canvas: canvas,
-            context: context,
+            width,
+            height,
+            canvas,
+            context,
 
-            num_squares_x: num_cols as i32,
-            num_squares_y: num_rows as i32,
             rect_size: block_size,
 
             should_show_focus_banner: false,
             is_paused: false,
-            is_game_over: false,
-            did_win: false,
-            score: 0,
+            is_autopilot_on: false,
+            frames_until_restart: ATTRACT_RESTART_FRAMES,
             key_buff: VecDeque::with_capacity(MAX_KEY_BUFF_LEN),
 
-            apples: VecDeque::new(),
-            num_apples: 5,
-            is_growing: false,
+            levels,
+            level: 0,
 
             frames_between_updates: MIN_SPEED,
             frames_until_update: 0,
 
-            head_direction: Vector2D { x: 1, y: 0 },
-            head_is_tail: true,
-            path: VecDeque::new(),
+            num_players: 1,
+            num_opponents: 0,
+            difficulty: Difficulty::Medium,
+            is_neural: false,
+            network: Network::builtin(),
+            bots: snake::players().iter().map(|_| None).collect(),
+            pilots: vec![],
+            sim: Simulation::new(settings, 0),
+            online: None,
+            stream: None,
+            scoreboard: None,
+            replay: None,
+            is_fair: false,
+            viewer: None,
+            is_ghost_on: false,
+            ghost: None,
+            daily: None,
+            rewind: Rewind::new(rewind::DEFAULT_REWINDS, rewind::DEFAULT_PENALTY),
+            save_slot: 0,
+            last_autosave: js_sys::Date::now(),
+            is_debug_on: false,
+            cursor: None,
+            render_time: 0.,
 
             rng: rand::thread_rng(),
         };
 
-        if let Some(space) = inner.get_random_empty_space() {
-            inner.path.push_front(space);
+        inner.reset();
+        // carries on with whatever was being played when the page last went away
+        if let Some(save) = load_save(AUTOSAVE_SLOT) {
+            log::info!("carrying on from where you left off");
+            inner.resume(save);
         }
-
-        return inner;
+        inner
     }
 
     fn reset(&mut self) {
-        self.is_game_over = false;
-        self.did_win = false;
-        self.is_growing = false;
-        self.path.clear();
-        self.path.push_front(Vector2D {
-            x: self.num_squares_x / 2,
-            y: self.num_squares_y / 2,
-        });
+        // starting over gives up on the daily challenge's game that counts
+        self.finish_daily();
+        let (settings, seed) = match self.daily.as_mut() {
+            Some(daily) => {
+                daily.next_game();
+                (daily.challenge.settings.clone(), daily.challenge.seed)
+            }
+            None => {
+                // whatever was changed during the last game carries over into the next one
+                let mut settings = self.sim.settings.clone();
+                settings.num_snakes = self.num_players + self.num_opponents;
+                settings.level = self.levels[self.level].clone();
+                (settings, self.rng.gen())
+            }
+        };
+        self.sim = Simulation::new(settings, seed);
+        self.rewind.reset();
+        self.seat_pilots();
+
+        let names = self
+            .sim
+            .snakes
+            .iter()
+            .map(|snake| snake.player.name.to_string())
+            .collect();
+        self.replay = Some(Replay::new(self.sim.settings.clone(), seed, names));
+        self.is_fair = true;
+        self.load_ghost();
 
-        self.score = 0;
-        self.apples.clear();
         self.frames_between_updates = MIN_SPEED;
         self.frames_until_update = MIN_SPEED;
     }
 
+    // a pilot for every snake in the game, with the computer steering any there aren't enough
+    // players for
+    fn seat_pilots(&mut self) {
+        self.pilots.clear();
+        for index in 0..self.sim.snakes.len() {
+            let mut pilot = Pilot::new(self.sim.snakes[index].player.bindings);
+            if let Some(bot) = &self.bots[index] {
+                pilot.controller = Some(bot());
+            } else if index >= self.num_players {
+                // computer opponents take the slots after the human players
+                self.sim.snakes[index].player.name = AI_NAMES[index - self.num_players];
+                pilot.controller = Some(self.computer_player());
+            }
+            self.pilots.push(pilot);
+        }
+    }
+
+    pub fn plug_controller(&mut self, slot: usize, bot: ControllerFactory) {
+        let controller = bot();
+        log::info!("{} is steering slot {}", controller.name(), slot);
+        if let Some(pilot) = self.pilots.get_mut(slot) {
+            pilot.controller = Some(controller);
+        }
+        self.bots[slot] = Some(bot);
+    }
+
+    // hands the slot back to whoever had it before: the keyboard, or the computer
+    pub fn unplug_controller(&mut self, slot: usize) {
+        self.bots[slot] = None;
+        let controller = if slot >= self.num_players {
+            Some(self.computer_player())
+        } else {
+            None
+        };
+        if let Some(pilot) = self.pilots.get_mut(slot) {
+            pilot.controller = controller;
+        }
+    }
+
+    // whoever plays the computer opponents
+    fn computer_player(&self) -> Box<dyn SnakeController> {
+        if self.is_neural {
+            Box::new(NeuralBot::new(self.network.clone()))
+        } else {
+            Box::new(AiPlayer::new(self.difficulty))
+        }
+    }
+
     pub fn focus(&self) -> Result<(), JsValue> {
         self.canvas.focus()
     }
 
     pub fn show_focus_banner(&mut self) -> Result<(), JsValue> {
         log::info!("Show focus banner");
+        // we won't hear about the rewind key coming back up
+        self.stop_rewind();
         self.should_show_focus_banner = true;
         Ok(())
     }
@@ -156,7 +337,25 @@ impl Inner {
 
     pub fn tick(&mut self) -> Result<(), JsValue> {
         self.pre_process_keys();
-        if !self.effectively_paused() {
+        if self.is_autopilot_on
+            && self.is_game_over()
+            && !self.should_show_focus_banner
+            && self.viewer.is_none()
+        {
+            self.frames_until_restart -= 1;
+            if self.frames_until_restart == 0 {
+                self.frames_until_restart = ATTRACT_RESTART_FRAMES;
+                self.reset();
+            }
+        }
+
+        if let Some(viewer) = self.viewer.as_mut() {
+            if !self.should_show_focus_banner {
+                viewer.advance(&mut self.sim, 1. / MIN_SPEED as f64);
+            }
+        } else if self.rewind.is_scrubbing() {
+            self.scrub();
+        } else if !self.effectively_paused() {
             if self.frames_until_update == 0 {
                 self.process_key();
                 self.update()?;
@@ -164,104 +363,799 @@ impl Inner {
             }
             self.frames_until_update -= 1;
         }
+        self.broadcast();
+        if js_sys::Date::now() - self.last_autosave >= AUTOSAVE_INTERVAL {
+            self.autosave();
+        }
+        let started = now();
         self.draw().expect("Something's gone wrong with draw");
+        self.render_time = now() - started;
         Ok(())
     }
 
     fn update(&mut self) -> Result<(), JsValue> {
-        let mut current_head = {
-            // head will never be null
-            let head = if self.head_is_tail {
-                self.path.back().unwrap()
-            } else {
-                self.path.front().unwrap()
-            };
+        // watched games only move when the broadcast does
+        if self.is_watching() {
+            return Ok(());
+        }
+        if self.online.is_some() {
+            self.update_online();
+            return Ok(());
+        }
+
+        if self.sim.is_game_over {
+            return Ok(());
+        }
+        if self.sim.tick == 0 {
+            self.start_daily();
+        }
+        let commands = self.steer_snakes();
+        // nobody gets onto the leaderboard with help
+        if self.pilots.iter().any(|pilot| pilot.controller.is_some()) {
+            self.is_fair = false;
+        }
+        if let Some(replay) = self.replay.as_mut() {
+            replay.record(&commands);
+        }
+        self.rewind.record(&self.sim);
+        self.sim.step(&commands);
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.follow(self.sim.tick);
+        }
+        if self.sim.is_game_over {
+            self.finish_daily();
+            self.record_score();
+        }
+        Ok(())
+    }
+
+    // which board the game in play is on, for the leaderboard and personal bests
+    fn board(&self) -> String {
+        match &self.daily {
+            Some(daily) => daily.challenge.board_key(),
+            None => leaderboard::board_key(&self.sim.settings),
+        }
+    }
+
+    // races the best run on this board, if there's been one and the ghost's on
+    fn load_ghost(&mut self) {
+        self.ghost = None;
+        if !self.is_ghost_on || self.sim.snakes.len() != 1 || self.online.is_some() {
+            return;
+        }
+        if let Some(best) = load_best(&self.board()) {
+            let mut ghost = Ghost::new(best);
+            ghost.follow(self.sim.tick);
+            self.ghost = Some(ghost);
+        }
+    }
+
+    fn toggle_ghost(&mut self) {
+        self.is_ghost_on = !self.is_ghost_on;
+        log::info!("ghost: {}", self.is_ghost_on);
+        self.load_ghost();
+    }
+
+    // how many times each game can be rewound, and how many points each one costs
+    pub fn set_rewinds(&mut self, rewinds_per_game: u32, penalty: u32) {
+        log::info!(
+            "rewinds: {} per game, costing {}",
+            rewinds_per_game,
+            penalty
+        );
+        self.rewind = Rewind::new(rewinds_per_game, penalty);
+    }
+
+    // only games played here can be rewound, and not the daily challenge, which would make it
+    // too easy
+    fn can_rewind(&self) -> bool {
+        self.is_game_over()
+            && self.viewer.is_none()
+            && self.online.is_none()
+            && !self.is_watching()
+            && self.daily.is_none()
+            && self.rewind.can_rewind()
+    }
+
+    fn start_rewind(&mut self) {
+        if !self.can_rewind() {
+            return;
+        }
+        if self.rewind.start(&mut self.sim) {
+            log::info!("rewinding");
+            // the leaderboard has no way of checking a rewound score
+            self.is_fair = false;
+            self.frames_until_update = self.frames_between_updates;
+        }
+    }
+
+    // goes back another update every time an update's due, for as long as the key's held
+    fn scrub(&mut self) {
+        if self.frames_until_update == 0 {
+            self.rewind.step_back(&mut self.sim);
+            if let Some(ghost) = self.ghost.as_mut() {
+                ghost.follow(self.sim.tick);
+            }
+            self.frames_until_update = self.frames_between_updates;
+        }
+        self.frames_until_update -= 1;
+    }
+
+    fn stop_rewind(&mut self) {
+        if !self.rewind.release() {
+            return;
+        }
+        // what happened after this never happened
+        if let Some(replay) = self.replay.as_mut() {
+            replay.ticks.truncate(self.sim.tick as usize);
+        }
+        let penalty = self.rewind.penalty;
+        for (snake, pilot) in self.sim.snakes.iter_mut().zip(self.pilots.iter_mut()) {
+            if pilot.controller.is_none() {
+                snake.score = snake.score.saturating_sub(penalty);
+            }
+            pilot.key_buff.clear();
+        }
+        log::info!(
+            "rewound to update {}, {} rewinds left",
+            self.sim.tick,
+            self.rewind.rewinds_left
+        );
+        // a moment to get your bearings before it carries on
+        self.is_paused = true;
+    }
+
+    fn rewind_hint(&self) -> Option<String> {
+        if !self.can_rewind() {
+            return None;
+        }
+        Some(format!(
+            "hold backspace to rewind ({} left, {} points each)",
+            self.rewind.rewinds_left, self.rewind.penalty
+        ))
+    }
+
+    // plays the same game as everyone else today, instead of whatever's set up here
+    pub fn go_daily(&mut self) {
+        if self.daily.is_none() && self.online.is_none() && !self.is_watching() {
+            self.toggle_daily();
+        }
+    }
+
+    fn toggle_daily(&mut self) {
+        if self.daily.is_some() {
+            self.finish_daily();
+            if let Some(daily) = self.daily.take() {
+                self.sim.settings = daily.settings;
+            }
+            log::info!("daily challenge: off");
+        } else {
+            if self.is_autopilot_on {
+                self.toggle_autopilot();
+            }
+            let challenge = Challenge::at((js_sys::Date::now() / 1000.) as u64);
+            log::info!("daily challenge: {}", challenge.date());
+            let settings = self.sim.settings.clone();
+            self.daily = Some(Daily::new(challenge, settings, load_daily()));
+        }
+        self.is_paused = false;
+        self.reset();
+    }
 
-            Vector2D {
-                x: head.x + self.head_direction.x,
-                y: head.y + self.head_direction.y,
+    // the day's game that counts starts counting with its first move
+    fn start_daily(&mut self) {
+        if let Some(daily) = self.daily.as_mut() {
+            if daily.start(js_sys::Date::now()) {
+                save_daily(daily.challenge.day, None);
             }
+        }
+    }
+
+    fn finish_daily(&mut self) {
+        if let Some(daily) = self.daily.as_mut() {
+            if let Some(result) = daily.finish(&self.sim, js_sys::Date::now()) {
+                log::info!("{}", result);
+                save_daily(daily.challenge.day, Some(&result));
+            }
+        }
+    }
+
+    // A game played alone that's just ended can be a new personal best, and goes to the
+    // leaderboard, as long as nobody else steered, it wasn't rewound and the rules didn't change
+    // partway through. Either way we show the best scores for its board
+    fn record_score(&mut self) {
+        let submission = match &self.replay {
+            Some(replay) if self.is_fair => Some(Submission::new(replay.clone())),
+            _ => None,
         };
+        if self.sim.snakes.len() != 1 {
+            return;
+        }
+        let board = self.board();
+        if let Some(submission) = &submission {
+            let best = load_best(&board).map(|best| best.claimed_score());
+            if best.is_none_or(|best| submission.claimed_score() > best) {
+                log::info!("new best on {}: {}", board, submission.claimed_score());
+                save_best(&board, submission);
+            }
+        }
 
-        if current_head.x < 0 {
-            current_head.x = self.num_squares_x - 1;
+        // only the game that counts goes on the daily challenge's board
+        let is_practice = self.daily.as_ref().is_some_and(|daily| daily.is_practice);
+        if let Some(scoreboard) = self.scoreboard.as_mut() {
+            match submission.filter(|_| !is_practice) {
+                Some(submission) => scoreboard.submit(board, &submission),
+                None => scoreboard.show(board),
+            }
         }
+    }
 
-        if current_head.x >= self.num_squares_x {
-            current_head.x = 0;
+    // watches a replay instead of playing, from its text
+    pub fn go_view(&mut self, text: &str) {
+        match Replay::parse(text) {
+            Ok(replay) => self.view(replay),
+            Err(err) => log::warn!("couldn't read the replay: {}", err),
         }
+    }
+
+    fn view(&mut self, replay: Replay) {
+        log::info!("viewing a replay of {} updates", replay.ticks.len());
+        self.stop_rewind();
+        self.sim = replay.start();
+        self.viewer = Some(Viewer::new(replay));
+        self.ghost = None;
+        self.is_paused = false;
+    }
 
-        if current_head.y < 0 {
-            current_head.y = self.num_squares_y - 1;
+    // Plays on from a game written out as text, with everyone at the keyboard. It starts
+    // paused, so there's a chance to look at it first
+    pub fn go_state(&mut self, text: &str) {
+        if self.online.is_some() || self.is_watching() {
+            log::warn!("can't load a game while playing online or watching");
+            return;
         }
+        let sim = match state::parse(text) {
+            Ok(sim) => sim,
+            Err(err) => {
+                log::warn!("couldn't read the game: {}", err);
+                return;
+            }
+        };
+        log::info!("playing on from update {}", sim.tick);
+        let num_players = sim.snakes.len();
+        self.play_on(sim, num_players);
+        // it didn't start at the beginning, so there's nothing to play back
+        self.replay = None;
+        self.is_fair = false;
+    }
+
+    // carries on with a game from somewhere else, paused, with the given number of players at
+    // the keyboard and the computer on the rest
+    fn play_on(&mut self, sim: Simulation, num_players: usize) {
+        self.stop_rewind();
+        self.finish_daily();
+        self.daily = None;
+        self.viewer = None;
+
+        self.num_players = num_players;
+        self.num_opponents = sim.snakes.len() - num_players;
+        self.sim = sim;
+        self.rewind.reset();
+        self.seat_pilots();
+        self.load_ghost();
+
+        self.is_paused = true;
+        self.frames_between_updates = MIN_SPEED;
+        self.frames_until_update = MIN_SPEED;
+    }
 
-        if current_head.y >= self.num_squares_y {
-            current_head.y = 0;
+    // Only local games can be put away for later, and not partway through a rewind. The
+    // daily challenge only gets the one go
+    fn can_save(&self) -> bool {
+        self.online.is_none()
+            && !self.is_watching()
+            && self.viewer.is_none()
+            && self.daily.is_none()
+            && !self.rewind.is_scrubbing()
+    }
+
+    fn save(&self) -> Save {
+        Save {
+            sim: self.sim.clone(),
+            replay: self.replay.clone(),
+            num_players: min(self.num_players, self.sim.snakes.len()),
+            rewinds_left: self.rewind.rewinds_left,
+            is_fair: self.is_fair,
         }
+    }
 
-        if !self.new_head_collides_with_snake(&current_head) {
-            // move snake
-            if self.is_growing {
-                self.is_growing = false;
-            } else {
-                if self.head_is_tail {
-                    self.path.pop_front();
-                } else {
-                    self.path.pop_back();
+    fn resume(&mut self, save: Save) {
+        let num_players = save.num_players;
+        self.play_on(save.sim, num_players);
+        self.is_fair = save.is_fair && save.replay.is_some();
+        self.replay = save.replay;
+        self.rewind.rewinds_left = save.rewinds_left;
+    }
+
+    fn save_to_slot(&mut self) {
+        if !self.can_save() {
+            log::info!("this game can't be saved");
+            return;
+        }
+        let slot = SAVE_SLOT_KEYS[self.save_slot];
+        if store_save(slot, Some(&self.save())) {
+            log::info!("saved update {} in slot {}", self.sim.tick, slot);
+        }
+    }
+
+    fn load_from_slot(&mut self) {
+        if !self.can_save() {
+            log::info!("can't load a game now");
+            return;
+        }
+        let slot = SAVE_SLOT_KEYS[self.save_slot];
+        match load_save(slot) {
+            Some(save) => {
+                log::info!("loaded update {} from slot {}", save.sim.tick, slot);
+                self.resume(save);
+            }
+            None => log::info!("nothing saved in slot {}", slot),
+        }
+    }
+
+    // Keeps the game in play where it'll be picked up again if the page closes or crashes.
+    // Games that haven't started yet or are already over aren't worth carrying on with, and
+    // neither is the autopilot showing off
+    pub fn autosave(&mut self) {
+        self.last_autosave = js_sys::Date::now();
+        if !self.can_save() || self.is_autopilot_on {
+            return;
+        }
+        if self.sim.tick == 0 || self.is_game_over() {
+            store_save(AUTOSAVE_SLOT, None);
+        } else {
+            store_save(AUTOSAVE_SLOT, Some(&self.save()));
+        }
+    }
+
+    // only games played here can be watched again, once they're over
+    fn can_view(&self) -> bool {
+        self.is_game_over()
+            && self.replay.is_some()
+            && self.viewer.is_none()
+            && self.online.is_none()
+            && !self.is_watching()
+    }
+
+    fn view_hint(&self) -> Option<String> {
+        if !self.can_view() {
+            return None;
+        }
+        Some(format!("{} to watch the replay", VIEWER_KEY))
+    }
+
+    // in the replay viewer, keys move around the replay instead of playing
+    fn process_viewing_keys(&mut self) {
+        let viewer = match self.viewer.as_mut() {
+            Some(viewer) => viewer,
+            None => return,
+        };
+        let sim = &mut self.sim;
+        let mut should_leave = false;
+        for key in self.key_buff.drain(..) {
+            match key.as_str() {
+                "Enter" => viewer.toggle_playing(sim),
+                "ArrowRight" => viewer.step_forward(sim),
+                "ArrowLeft" => viewer.step_back(sim),
+                "Home" => viewer.seek(sim, 0),
+                "End" => viewer.seek(sim, viewer.end()),
+                "f" => viewer.faster(),
+                "s" => viewer.slower(),
+                STATE_KEY => log_state(sim),
+                VIEWER_KEY => should_leave = true,
+                _ => {}
+            }
+        }
+        if should_leave {
+            log::info!("leaving the replay viewer");
+            self.viewer = None;
+            self.reset();
+        }
+    }
+
+    // which update of the replay a spot on the timeline is, if it's on the timeline
+    fn timeline_tick(&self, x: f64, y: f64) -> Option<u64> {
+        let viewer = self.viewer.as_ref()?;
+        if y < self.height - TIMELINE_HEIGHT {
+            return None;
+        }
+        let fraction = (x - TIMELINE_MARGIN) / (self.width - TIMELINE_MARGIN * 2.);
+        Some((fraction.clamp(0., 1.) * viewer.end() as f64).round() as u64)
+    }
+
+    // sends games to a leaderboard server once they're over. The requests themselves are made
+    // by Game
+    pub fn go_leaderboard(&mut self, url: &str, name: &str) {
+        log::info!("sending scores to {} as {}", url, name);
+        self.scoreboard = Some(Scoreboard::new(url, name));
+        self.reset();
+    }
+
+    pub fn take_leaderboard_requests(&mut self) -> Vec<Request> {
+        self.scoreboard
+            .as_mut()
+            .map(|scoreboard| scoreboard.take_requests())
+            .unwrap_or_default()
+    }
+
+    pub fn receive_leaderboard(&mut self, kind: &RequestKind, status: u16, text: &str) {
+        if let Some(scoreboard) = self.scoreboard.as_mut() {
+            scoreboard.receive(kind, status, text);
+        }
+    }
+
+    // sends off our own command, and moves the game on as far as the netcode in play lets us
+    fn update_online(&mut self) {
+        let online = match self.online.as_mut() {
+            Some(online) => online,
+            None => return,
+        };
+        let sim = &mut self.sim;
+        let pilots = &mut self.pilots;
+        let mut outgoing = vec![];
+
+        match online.session.as_mut() {
+            Some(session) if session.desync().is_some() => return,
+            None => return,
+
+            // nobody moves on until everybody's commands have arrived
+            Some(Session::Lockstep(lockstep)) => {
+                // after rejoining, or if we've fallen behind everyone else, play the backlog
+                // straight away, but hold on to a few ticks so we're not waiting on the next update
+                while lockstep.ready_ticks(sim.tick) > lockstep.input_delay + 1 {
+                    let commands = lockstep.take_commands(sim.tick).unwrap();
+                    sim.step(&commands);
+                    outgoing.extend(lockstep.record_hash(sim.tick, sim.state_hash()));
+                }
+
+                if lockstep.wants_input(sim.tick) {
+                    let command = local_command(sim, pilots, lockstep.me);
+                    outgoing.extend(lockstep.send(sim.tick, command));
+                }
+
+                match lockstep.take_commands(sim.tick) {
+                    Some(commands) => {
+                        sim.step(&commands);
+                        outgoing.extend(lockstep.record_hash(sim.tick, sim.state_hash()));
+                        online.stalled_updates = 0;
+                    }
+                    None => online.stalled_updates += 1,
                 }
             }
 
-            if self.head_is_tail {
-                self.path.push_back(current_head);
-            } else {
-                self.path.push_front(current_head);
+            // we always move on, guessing at anyone we haven't heard from yet
+            Some(Session::Rollback(rollback)) => {
+                if rollback.wants_input(sim.tick) {
+                    let command = local_command(sim, pilots, rollback.me);
+                    rollback.add_local(sim.tick, command);
+                }
+
+                if rollback.advance(sim) || sim.is_game_over {
+                    online.stalled_updates = 0;
+                } else {
+                    online.stalled_updates += 1;
+                }
+                outgoing = rollback.messages();
             }
         }
 
-        // remove apples
-        for apple_index in 0..self.apples.len() {
-            let apple = self.apples[apple_index];
-            if apple.x == current_head.x && apple.y == current_head.y {
-                self.apples.swap_remove_back(apple_index);
-                self.is_growing = true;
-                self.score += 1;
-                break;
+        for message in outgoing.iter() {
+            online.send(message);
+        }
+    }
+
+    // plays against whoever else joins the same room on a relay server. The connection itself
+    // is looked after by Game
+    pub fn go_online(&mut self, url: &str, room: &str, netcode: Netcode) {
+        log::info!("joining room {} on {}", room, url);
+        self.online = Some(Online::new(url, room, netcode));
+        self.is_autopilot_on = false;
+        self.is_paused = false;
+    }
+
+    // sends this game out over a channel on a relay server, for other browsers to watch
+    pub fn go_broadcast(&mut self, url: &str, channel: &str) {
+        log::info!("broadcasting on {} at {}", channel, url);
+        self.stream = Some(Stream::new(
+            url,
+            channel,
+            Role::Broadcast(Box::new(Broadcaster::new())),
+        ));
+    }
+
+    // watches whatever's being broadcast on a channel, instead of playing
+    pub fn go_watch(&mut self, url: &str, channel: &str) {
+        log::info!("watching {} at {}", channel, url);
+        let watching = Watching {
+            has_game: false,
+            follow: None,
+        };
+        self.stream = Some(Stream::new(url, channel, Role::Watch(watching)));
+        self.is_autopilot_on = false;
+        self.is_paused = false;
+    }
+
+    fn is_watching(&self) -> bool {
+        self.stream
+            .as_ref()
+            .is_some_and(|stream| stream.watching().is_some())
+    }
+
+    pub fn url(&self, link: Link) -> Option<String> {
+        match link {
+            Link::Online => self.online.as_ref().map(|online| online.url.clone()),
+            Link::Stream => self.stream.as_ref().map(|stream| stream.url.clone()),
+        }
+    }
+
+    pub fn set_socket(&mut self, link: Link, socket: Option<WebSocket>) {
+        match link {
+            Link::Online => {
+                if let Some(online) = self.online.as_mut() {
+                    online.set_socket(socket);
+                }
+            }
+            Link::Stream => {
+                if let Some(stream) = self.stream.as_mut() {
+                    stream.set_socket(socket);
+                }
             }
         }
+    }
 
-        // add missing apples
-        while self.apples.len() < self.num_apples {
-            match self.get_random_empty_space() {
-                None => {
-                    break;
+    pub fn on_open(&mut self, link: Link) {
+        match link {
+            Link::Online => {
+                if let Some(online) = &self.online {
+                    log::info!("connected to {}", online.url);
+                    online.send(&online.hello());
                 }
-                Some(apple) => {
-                    self.apples.push_back(apple);
+            }
+            Link::Stream => {
+                if let Some(stream) = self.stream.as_mut() {
+                    log::info!("connected to {}", stream.url);
+                    stream.send(&stream.hello());
+                    // the server's forgotten everything we'd sent
+                    if let Role::Broadcast(broadcaster) = &mut stream.role {
+                        broadcaster.restart();
+                    }
                 }
             }
         }
+    }
 
-        if self.apples.len() == 0 {
-            self.is_game_over = true;
-            self.did_win = true;
+    pub fn on_close(&mut self, link: Link) {
+        if let Some(url) = self.url(link) {
+            log::warn!("lost the connection to {}", url);
         }
+        self.set_socket(link, None);
+    }
 
-        Ok(())
+    pub fn receive_message(&mut self, link: Link, text: &str) {
+        let message = match Message::parse(text) {
+            Ok(message) => message,
+            Err(err) => {
+                log::warn!("couldn't understand the server: {}", err);
+                return;
+            }
+        };
+        if link == Link::Stream {
+            self.receive_stream_message(message);
+            return;
+        }
+        let online = match self.online.as_mut() {
+            Some(online) => online,
+            None => return,
+        };
+
+        match message {
+            Message::Welcome { slot, token, size } => {
+                log::info!("got seat {} of {}", slot + 1, size);
+                online.seat = Some((slot, token));
+                online.present = vec![false; size];
+                online.present[slot] = true;
+                online.error = None;
+            }
+            Message::Joined { slot } => {
+                if let Some(present) = online.present.get_mut(slot) {
+                    *present = true;
+                }
+                if online.is_host() && online.session.is_none() && online.is_everyone_here() {
+                    self.host_online_game();
+                }
+            }
+            Message::Left { slot } => {
+                if let Some(present) = online.present.get_mut(slot) {
+                    *present = false;
+                }
+            }
+            Message::Start {
+                input_delay,
+                netcode,
+                replay,
+            } => self.start_online_game(input_delay, netcode, &replay),
+            Message::Error { message } => {
+                log::warn!("the server says: {}", message);
+                online.error = Some(message);
+            }
+            message => {
+                if let Some(session) = online.session.as_mut() {
+                    session.receive(&message);
+                }
+            }
+        }
     }
 
-    fn new_head_collides_with_snake(&mut self, new_head: &Vector2D) -> bool {
-        for pos in self.path.iter() {
-            if pos.x == new_head.x && pos.y == new_head.y {
-                return true;
+    fn receive_stream_message(&mut self, message: Message) {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let watching = match &mut stream.role {
+            Role::Watch(watching) => watching,
+            // nobody sends a broadcaster anything but errors
+            Role::Broadcast(_) => {
+                if let Message::Error { message } = message {
+                    log::warn!("the server says: {}", message);
+                }
+                return;
+            }
+        };
+
+        match message {
+            Message::Snapshot(snapshot) => {
+                self.sim = snapshot.to_simulation();
+                watching.has_game = true;
+                stream.error = None;
             }
+            Message::Delta(delta) if watching.has_game => {
+                if let Err(err) = delta.apply(&mut self.sim) {
+                    log::warn!("couldn't follow the broadcast: {}", err);
+                }
+            }
+            Message::Error { message } => {
+                log::warn!("the server says: {}", message);
+                stream.error = Some(message);
+            }
+            _ => {}
         }
+    }
 
-        return false;
+    // sends anything that's changed to whoever's watching
+    fn broadcast(&mut self) {
+        let is_over = self.is_game_over();
+        if let Some(stream) = self.stream.as_mut() {
+            if let Role::Broadcast(broadcaster) = &mut stream.role {
+                if let Some(message) = broadcaster.update(&self.sim, is_over) {
+                    stream.send(&message);
+                }
+            }
+        }
+    }
+
+    // picks the settings and seed for everyone, using whatever we'd set up locally
+    fn host_online_game(&mut self) {
+        let online = match &self.online {
+            Some(online) if online.is_host() && online.is_everyone_here() => online,
+            _ => return,
+        };
+        let mut settings = self.sim.settings.clone();
+        settings.num_snakes = online.present.len();
+        settings.level = self.levels[self.level].clone();
+        let names = snake::players()
+            .iter()
+            .take(settings.num_snakes)
+            .map(|player| player.name.to_string())
+            .collect();
+        let input_delay = match online.netcode {
+            Netcode::Lockstep => lockstep::DEFAULT_INPUT_DELAY,
+            Netcode::Rollback => rollback::DEFAULT_INPUT_DELAY,
+        };
+        let message = Message::Start {
+            input_delay,
+            netcode: online.netcode,
+            replay: Replay::new(settings, self.rng.gen(), names),
+        };
+        online.send(&message);
+        if let Message::Start {
+            input_delay,
+            netcode,
+            replay,
+        } = message
+        {
+            self.start_online_game(input_delay, netcode, &replay);
+        }
+    }
+
+    fn start_online_game(&mut self, input_delay: u64, netcode: Netcode, replay: &Replay) {
+        let online = match self.online.as_mut() {
+            Some(online) => online,
+            None => return,
+        };
+        let me = match online.slot() {
+            Some(slot) => slot,
+            None => return,
+        };
+        log::info!(
+            "starting a game with {} players, using {}",
+            replay.names.len(),
+            netcode.name()
+        );
+
+        self.sim = replay.start();
+        self.replay = None;
+        // everybody steers their own snake with the arrow keys
+        self.pilots = replay
+            .names
+            .iter()
+            .map(|_| Pilot::new(KeyBindings::arrows()))
+            .collect();
+        let num_players = replay.names.len();
+        online.session = Some(match netcode {
+            Netcode::Lockstep => Session::Lockstep(Lockstep::new(me, num_players, input_delay)),
+            Netcode::Rollback => Session::Rollback(Rollback::new(me, num_players, input_delay)),
+        });
+        online.stalled_updates = 0;
+        self.frames_between_updates = MIN_SPEED;
+        self.frames_until_update = MIN_SPEED;
+    }
+
+    // online games sit still until everyone's here, and stop for good if they desync
+    fn is_waiting_online(&self) -> bool {
+        self.online.as_ref().is_some_and(|online| {
+            online
+                .session
+                .as_ref()
+                .is_none_or(|session| session.desync().is_some())
+        })
+    }
+
+    // the simulation decides when a game is over, except that versus also stops once the
+    // computers are only playing against each other. Online with rollback, it's only over once
+    // nobody's commands could still change how it ended
+    fn is_game_over(&self) -> bool {
+        // the broadcaster already decided
+        if self.is_watching() {
+            return self.sim.is_game_over;
+        }
+        if self.sim.is_game_over {
+            return self
+                .online
+                .as_ref()
+                .and_then(|online| online.session.as_ref())
+                .is_none_or(|session| session.is_settled(&self.sim));
+        }
+
+        let snakes = &self.sim.snakes;
+        let has_humans = self.pilots.iter().any(|pilot| pilot.controller.is_none());
+        let num_humans_alive = snakes
+            .iter()
+            .zip(self.pilots.iter())
+            .filter(|(snake, pilot)| snake.is_alive && pilot.controller.is_none())
+            .count();
+        snakes.len() > 1 && has_humans && num_humans_alive == 0
     }
 
     pub fn handle_click(&mut self, x: i32, y: i32) -> Result<(), JsValue> {
+        if self.is_watching() {
+            return Ok(());
+        }
         let x = x - self.canvas.offset_left();
         let y = y - self.canvas.offset_top();
+        if self.viewer.is_some() {
+            self.seek_timeline(x, y);
+            return Ok(());
+        }
         let width = self.canvas.width() as i32;
         let height = self.canvas.height() as i32;
 
@@ -278,28 +1172,90 @@ impl Inner {
             keys.push("ArrowDown");
         }
 
-        for key in keys {
-            if self.key_buff.len() < MAX_KEY_BUFF_LEN {
-                self.key_buff.push_back(key.to_string());
+        // clicks always steer the first player, or online, us
+        let me = self
+            .online
+            .as_ref()
+            .and_then(|online| online.slot())
+            .unwrap_or(0);
+        if let Some(pilot) = self.pilots.get_mut(me) {
+            for key in keys {
+                pilot.handle_key(key);
             }
         }
         Ok(())
     }
 
+    // dragging along the timeline scrubs through the replay
+    pub fn handle_mouse_move(&mut self, x: i32, y: i32, is_pressed: bool) -> Result<(), JsValue> {
+        let x = x - self.canvas.offset_left();
+        let y = y - self.canvas.offset_top();
+        self.cursor = Some((x as f64, y as f64));
+        if is_pressed && self.viewer.is_some() {
+            self.seek_timeline(x, y);
+        }
+        Ok(())
+    }
+
+    fn seek_timeline(&mut self, x: i32, y: i32) {
+        if let Some(tick) = self.timeline_tick(x as f64, y as f64) {
+            if let Some(viewer) = self.viewer.as_mut() {
+                viewer.is_playing = false;
+                viewer.seek(&mut self.sim, tick);
+            }
+        }
+    }
+
     pub fn handle_key(&mut self, key: String) -> Result<(), JsValue> {
         log::info!("Received {}", key);
+        if key == REWIND_KEY {
+            self.start_rewind();
+            return Ok(());
+        }
+
+        // keys that belong to a snake go straight to it, everything else is for the game itself.
+        // Online, only our own snake is steered from here
+        let me = self.online.as_ref().and_then(|online| online.slot());
+        let is_watching = self.is_watching() || self.viewer.is_some();
+        for (index, pilot) in self.pilots.iter_mut().enumerate() {
+            if !is_watching && me.is_none_or(|me| me == index) && pilot.handle_key(&key) {
+                return Ok(());
+            }
+        }
+
         if self.key_buff.len() < MAX_KEY_BUFF_LEN {
             self.key_buff.push_back(key);
         }
         Ok(())
     }
 
+    pub fn handle_key_up(&mut self, key: String) -> Result<(), JsValue> {
+        if key == REWIND_KEY {
+            self.stop_rewind();
+        }
+        Ok(())
+    }
+
     fn effectively_paused(&self) -> bool {
-        self.should_show_focus_banner || self.is_paused || self.is_game_over
+        self.should_show_focus_banner
+            || self.is_paused
+            || self.rewind.is_scrubbing()
+            || self.is_game_over()
+            || self.is_waiting_online()
     }
 
     pub fn pre_process_keys(&mut self) {
+        if self.is_watching() {
+            self.process_watching_keys();
+            return;
+        }
+        if self.viewer.is_some() {
+            self.process_viewing_keys();
+            return;
+        }
+
         let mut should_reset = false;
+        let mut should_step = false;
         if let Some(key) = self.key_buff.front() {
             match key.as_str() {
                 "r" => {
@@ -308,10 +1264,51 @@ impl Inner {
                     self.key_buff.pop_front();
                 }
 
+                STATE_KEY => {
+                    log_state(&self.sim);
+                    self.key_buff.pop_front();
+                }
+
+                slot if SAVE_SLOT_KEYS.contains(&slot) => {
+                    self.save_slot = SAVE_SLOT_KEYS.iter().position(|key| *key == slot).unwrap();
+                    log::info!("save slot: {}", SAVE_SLOT_KEYS[self.save_slot]);
+                    self.key_buff.pop_front();
+                }
+
+                SAVE_KEY => {
+                    self.save_to_slot();
+                    self.key_buff.pop_front();
+                }
+
+                LOAD_KEY => {
+                    self.load_from_slot();
+                    self.key_buff.pop_front();
+                }
+
+                DEBUG_KEY => {
+                    self.is_debug_on = !self.is_debug_on;
+                    log::info!("debug overlay: {}", self.is_debug_on);
+                    self.key_buff.pop_front();
+                }
+
+                STEP_KEY => {
+                    should_step =
+                        self.is_paused && !self.rewind.is_scrubbing() && !self.is_game_over();
+                    self.key_buff.pop_front();
+                }
+
+                VIEWER_KEY if self.can_view() => {
+                    if let Some(replay) = self.replay.clone() {
+                        self.view(replay);
+                    }
+                    self.key_buff.pop_front();
+                }
+
                 "Enter" => {
-                    if self.is_game_over {
+                    if self.is_game_over() {
                         should_reset = true;
-                    } else {
+                    } else if self.online.is_none() {
+                        // pausing would hold everyone else up too
                         self.is_paused = !self.is_paused;
                     }
                     self.key_buff.pop_front();
@@ -322,35 +1319,140 @@ impl Inner {
 
         // some things we need to do after our immutable borrows up top
         if should_reset {
-            self.reset();
+            if self.online.is_some() {
+                self.host_online_game();
+            } else {
+                self.reset();
+            }
+        }
+        if should_step {
+            self.update().expect("Something's gone wrong with update");
+            self.frames_until_update = self.frames_between_updates;
+            log::info!("stepped to update {}", self.sim.tick);
         }
 
         // eats up any keys that would otherwise clog the buffer.
-        // Also prevents pause-buffering
+        // Also prevents pause-buffering, except when stepping through a game with the debug
+        // overlay, where the snakes' keys wait for the next step so you can see them queue up
         if self.effectively_paused() {
             self.key_buff.clear();
+            if !(self.is_debug_on && self.is_paused) {
+                for pilot in self.pilots.iter_mut() {
+                    pilot.key_buff.clear();
+                }
+            }
+        }
+    }
+
+    // watching, the only thing to do is move the camera
+    fn process_watching_keys(&mut self) {
+        let num_snakes = self.sim.snakes.len();
+        let watching = match self.stream.as_mut().map(|stream| &mut stream.role) {
+            Some(Role::Watch(watching)) => watching,
+            _ => return,
+        };
+        for key in self.key_buff.drain(..) {
+            if key == "z" {
+                watching.follow = match watching.follow {
+                    None if num_snakes > 0 => Some(0),
+                    Some(index) if index + 1 < num_snakes => Some(index + 1),
+                    _ => None,
+                };
+                log::info!("camera following: {:?}", watching.follow);
+            }
         }
     }
 
     pub fn process_key(&mut self) {
+        if self.effectively_paused() {
+            return;
+        }
+
         if let Some(key) = self.key_buff.pop_front() {
-            if self.effectively_paused() {
+            if self.online.is_some() && !ONLINE_KEYS.contains(&key.as_str()) {
+                log::info!("{} doesn't work online", key);
                 return;
             }
 
+            if self.daily.is_some() && !DAILY_KEYS.contains(&key.as_str()) {
+                log::info!("{} doesn't work in the daily challenge", key);
+                return;
+            }
+            if RULE_KEYS.contains(&key.as_str()) {
+                self.replay = None;
+            }
+
+            let settings = &mut self.sim.settings;
             match key.as_str() {
-                // NOTE: y is flipped here since that's the default for rendering, and it's easier
-                // to flip it just here than anytime we draw
-                "ArrowUp" => self.head_direction = Vector2D { y: -1, x: 0 },
-                "ArrowDown" => self.head_direction = Vector2D { y: 1, x: 0 },
+                "a" => settings.num_apples += 1,
 
-                "ArrowRight" => self.head_direction = Vector2D { x: 1, y: 0 },
-                "ArrowLeft" => self.head_direction = Vector2D { x: -1, y: 0 },
+                "t" => self.toggle_timed_apples(),
 
-                "a" => self.num_apples += 1,
+                "g" => {
+                    self.sim.next_spawn_strategy();
+                    log::info!("spawning apples: {}", self.sim.spawn_strategy_name());
+                }
+
+                "l" => {
+                    self.level = (self.level + 1) % self.levels.len();
+                    log::info!("level: {}", self.levels[self.level].name);
+                    self.reset();
+                }
+
+                "v" => {
+                    self.num_players = self.num_players % snake::players().len() + 1;
+                    self.num_opponents = min(
+                        self.num_opponents,
+                        snake::players().len() - self.num_players,
+                    );
+                    log::info!("players: {}", self.num_players);
+                    self.reset();
+                }
+
+                "o" => {
+                    self.num_opponents =
+                        (self.num_opponents + 1) % (snake::players().len() - self.num_players + 1);
+                    log::info!("computer opponents: {}", self.num_opponents);
+                    self.reset();
+                }
 
-                // reverse head
-                " " => self.head_is_tail = !self.head_is_tail,
+                "p" => self.toggle_autopilot(),
+
+                "y" => self.toggle_daily(),
+
+                "m" => self.toggle_ghost(),
+
+                "b" => {
+                    if let Some(scoreboard) = self.scoreboard.as_mut() {
+                        scoreboard.next_period();
+                        log::info!("leaderboard: {}", scoreboard.period.name());
+                    }
+                }
+
+                "h" => {
+                    self.difficulty = self.difficulty.next();
+                    log::info!("difficulty: {:?}", self.difficulty);
+                    self.reset();
+                }
+
+                "n" => {
+                    self.is_neural = !self.is_neural;
+                    log::info!("neural bots: {}", self.is_neural);
+                    if self.is_autopilot_on {
+                        self.plug_controller(0, self.autopilot());
+                    }
+                    self.reset();
+                }
+
+                "c" => {
+                    settings.rules.head_to_head = settings.rules.head_to_head.next();
+                    log::info!("head to head: {:?}", settings.rules.head_to_head);
+                }
+
+                "x" => {
+                    settings.rules.snake_collision = settings.rules.snake_collision.next();
+                    log::info!("snake collisions: {:?}", settings.rules.snake_collision);
+                }
 
                 // slower
                 "s" => {
@@ -367,28 +1469,118 @@ impl Inner {
         }
     }
 
+    // every snake gets a command, from the keyboard or from its controller, and then they all
+    // move exactly the same way
+    fn steer_snakes(&mut self) -> Vec<Command> {
+        let sim = &self.sim;
+        self.pilots
+            .iter_mut()
+            .enumerate()
+            .map(|(index, pilot)| {
+                if sim.snakes[index].is_alive {
+                    pilot.next_command(&sim.board_view(index))
+                } else {
+                    Command::Continue
+                }
+            })
+            .collect()
+    }
+
+    fn toggle_autopilot(&mut self) {
+        self.is_autopilot_on = !self.is_autopilot_on;
+        if self.is_autopilot_on {
+            self.plug_controller(0, self.autopilot());
+        } else {
+            self.unplug_controller(0);
+        }
+    }
+
+    fn autopilot(&self) -> ControllerFactory {
+        if self.is_neural {
+            let network = self.network.clone();
+            Box::new(move || Box::new(NeuralBot::new(network.clone())))
+        } else {
+            Box::new(|| Box::new(Autopilot::new()))
+        }
+    }
+
+    fn toggle_timed_apples(&mut self) {
+        let lifetime = match self.sim.settings.apple_lifetime {
+            Some(_) => None,
+            None => Some(simulation::APPLE_LIFETIME),
+        };
+        self.sim.set_apple_lifetime(lifetime);
+    }
+
     pub fn draw(&mut self) -> Result<(), JsValue> {
         let context = &self.context;
         context.clear_rect(0., 0., self.width, self.height);
 
-        self.draw_circles(self.apples.iter(), APPLE_COLOR);
+        context.save();
+        self.point_camera()?;
+        let grid = Grid::new(&self.sim.settings, self.rect_size);
+        let mut canvas = Canvas(context);
+        grid.draw_walls(&mut canvas, &self.sim.settings.level.walls);
+        grid.draw_apples(&mut canvas, self.sim.apples.iter());
+
+        // the ghost goes underneath, since it's the real snake that matters
+        if let Some(snake) = self.ghost.as_ref().and_then(|ghost| ghost.snake()) {
+            canvas.save();
+            canvas.set_alpha(GHOST_ALPHA);
+            grid.draw_snake(&mut canvas, snake);
+            canvas.restore();
+        }
+
+        for snake in self.sim.snakes.iter() {
+            grid.draw_snake(&mut canvas, snake);
+        }
+        if self.is_debug_on {
+            grid.draw_grid_lines(&mut canvas, DEBUG_GRID_COLOR);
+        }
+        context.restore();
+        self.draw_ghost_delta();
+        if self.is_debug_on {
+            self.draw_debug();
+        }
 
-        self.draw_body(self.path.iter());
-        if self.head_is_tail {
-            self.draw_rect(self.path.front().unwrap(), TAIL_COLOR);
-            self.draw_head(self.path.back().unwrap());
-        } else {
-            self.draw_rect(self.path.back().unwrap(), TAIL_COLOR);
-            self.draw_head(self.path.front().unwrap());
+        if let Some(viewer) = &self.viewer {
+            self.draw_timeline(viewer);
+            if self.should_show_focus_banner {
+                self.draw_banner("LOST FOCUS");
+            }
+            return Ok(());
         }
 
-        if self.is_paused {
+        if let Some(banner) = self.online_banner().or_else(|| self.stream_banner()) {
+            self.draw_banner(&banner);
+        } else if self.is_paused {
             self.draw_banner("PAUSED");
-        } else if self.is_game_over {
-            if self.did_win {
-                self.draw_banner("YOU WON!!!");
+        } else if self.is_game_over() {
+            let title = if self.sim.did_win {
+                "YOU WON!!!"
+            } else {
+                "GAME OVER"
+            };
+            let mut lines: Vec<String> = self
+                .rewind_hint()
+                .into_iter()
+                .chain(self.view_hint())
+                .collect();
+            if let Some(daily) = &self.daily {
+                lines.extend(daily.lines());
+            }
+            if let Some(scoreboard) = self.scoreboard.as_ref().filter(|s| s.board.is_some()) {
+                lines.extend(scoreboard.lines());
+            }
+
+            if self.sim.snakes.len() > 1 {
+                self.draw_match_result();
+            } else if !lines.is_empty() {
+                let lines: Vec<(String, &str)> =
+                    lines.into_iter().map(|line| (line, "white")).collect();
+                self.draw_results(title, &lines);
             } else {
-                self.draw_banner("GAME OVER");
+                self.draw_banner(title);
             }
         } else if self.should_show_focus_banner {
             self.draw_banner("LOST FOCUS");
@@ -396,267 +1588,452 @@ impl Inner {
         Ok(())
     }
 
-    fn draw_body<'a, I>(&self, rects: I)
-    where
-        I: Iterator<Item = &'a Vector2D> + Clone,
-    {
-        // draw the body
-        let context = &self.context;
-        context.save();
-        context.set_fill_style(&JsValue::from(SNAKE_COLOR));
-        context.set_stroke_style(&JsValue::from("black"));
-        context.set_line_width(1.);
-        for pos in rects.clone() {
-            context.begin_path();
-            context.rect(
-                self.rect_size * pos.x as f64,
-                self.rect_size * pos.y as f64,
-                self.rect_size,
-                self.rect_size,
-            );
-            context.fill();
-            context.stroke();
+    // fits the board on the canvas, since a watched game can be any size, and when watching
+    // closes in on whichever snake the camera's following
+    fn point_camera(&self) -> Result<(), JsValue> {
+        let (zoom, corner) = self.camera();
+        self.context.scale(zoom, zoom)?;
+        self.context.translate(-corner.x, -corner.y)
+    }
+
+    // how much the board's scaled up by, and which point on it is in the top left corner
+    fn camera(&self) -> (f64, FVector2D) {
+        let board_width = self.sim.settings.width as f64 * self.rect_size;
+        let board_height = self.sim.settings.height as f64 * self.rect_size;
+        // the replay viewer's timeline goes under the board
+        let height = match self.viewer {
+            Some(_) => self.height - TIMELINE_HEIGHT,
+            None => self.height,
+        };
+        let mut zoom = (self.width / board_width).min(height / board_height);
+        let mut center = FVector2D {
+            x: board_width / 2.,
+            y: board_height / 2.,
+        };
+
+        let follow = self
+            .stream
+            .as_ref()
+            .and_then(|stream| stream.watching())
+            .and_then(|watching| watching.follow)
+            .and_then(|index| self.sim.snakes.get(index));
+        if let Some(snake) = follow {
+            let head = snake.head();
+            zoom *= CAMERA_ZOOM;
+            center = FVector2D {
+                x: (head.x as f64 + 0.5) * self.rect_size,
+                y: (head.y as f64 + 0.5) * self.rect_size,
+            };
         }
-        context.restore();
 
-        context.save();
-        // draw directions on the body
-        let context = &self.context;
-        context.save();
-        context.set_stroke_style(&JsValue::from("white"));
-        context.set_line_width(3.);
-
-        let mut previous_square: Option<Vector2D> = None;
-        for current_square in rects {
-            if let Some(previous_square) = previous_square {
-                let mut p1 = FVector2D {
-                    x: self.rect_size * previous_square.x as f64 + self.rect_size / 2.,
-                    y: self.rect_size * previous_square.y as f64 + self.rect_size / 2.,
-                };
-                let mut p2 = FVector2D {
-                    x: self.rect_size * current_square.x as f64 + self.rect_size / 2.,
-                    y: self.rect_size * current_square.y as f64 + self.rect_size / 2.,
-                };
+        // stay over the board rather than showing what's off the edge
+        let view_width = self.width / zoom;
+        let view_height = height / zoom;
+        let left = (center.x - view_width / 2.)
+            .min(board_width - view_width)
+            .max(0.);
+        let top = (center.y - view_height / 2.)
+            .min(board_height - view_height)
+            .max(0.);
+        (zoom, FVector2D { x: left, y: top })
+    }
 
-                // falls off right side
-                if previous_square.x == self.num_squares_x - 1 && current_square.x == 0 {
-                    let oldx = p2.x;
-                    p2.x = p1.x + self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                    p2.x = oldx;
-                    p1.x = oldx - self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                // falls off left side
-                } else if previous_square.x == 0 && current_square.x == self.num_squares_x - 1 {
-                    // swap x's
-                    let mut oldx = p2.x;
-                    p2.x = p1.x;
-                    p1.x = oldx;
-                    oldx = p2.x;
-
-                    p2.x = p1.x + self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                    p2.x = oldx;
-                    p1.x = oldx - self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                // falls off top
-                } else if previous_square.y == self.num_squares_y - 1 && current_square.y == 0 {
-                    let oldy = p2.y;
-                    p2.y = p1.y + self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                    p2.y = oldy;
-                    p1.y = oldy - self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                // falls off bottom
-                } else if previous_square.y == 0 && current_square.y == self.num_squares_y - 1 {
-                    // swap y's
-                    let mut oldy = p2.y;
-                    p2.y = p1.y;
-                    p1.y = oldy;
-                    oldy = p2.y;
-
-                    p2.y = p1.y + self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                    p2.y = oldy;
-                    p1.y = oldy - self.rect_size;
-                    Inner::draw_line(context, &p1, &p2);
-                } else {
-                    Inner::draw_line(context, &p1, &p2);
-                }
-            }
-            previous_square = Some(*current_square);
+    // the square under the mouse, if it's over the board
+    fn cursor_square(&self) -> Option<Vector2D> {
+        let (x, y) = self.cursor?;
+        let (zoom, corner) = self.camera();
+        let square = Vector2D {
+            x: ((x / zoom + corner.x) / self.rect_size).floor() as i32,
+            y: ((y / zoom + corner.y) / self.rect_size).floor() as i32,
+        };
+        let is_on_board = (0..self.sim.settings.width).contains(&square.x)
+            && (0..self.sim.settings.height).contains(&square.y);
+        Some(square).filter(|_| is_on_board)
+    }
+
+    // what's holding up a watched game, if anything
+    fn stream_banner(&self) -> Option<String> {
+        let stream = self.stream.as_ref()?;
+        let watching = stream.watching()?;
+        if let Some(error) = &stream.error {
+            Some(error.to_uppercase())
+        } else if !stream.is_connected() {
+            Some("CONNECTING".to_string())
+        } else if !watching.has_game {
+            Some("WAITING FOR BROADCAST".to_string())
+        } else {
+            None
         }
-        context.restore();
     }
 
-    fn draw_line(context: &Rc<CanvasRenderingContext2d>, p1: &FVector2D, p2: &FVector2D) {
-        context.begin_path();
-        context.move_to(p1.x, p1.y);
-        context.line_to(p2.x, p2.y);
-        context.stroke();
+    // what's holding up an online game, if anything
+    fn online_banner(&self) -> Option<String> {
+        let online = self.online.as_ref()?;
+        let desync = online.session.as_ref().and_then(|session| session.desync());
+        if let Some(tick) = desync {
+            Some(format!("DESYNCED AT {}", tick))
+        } else if let Some(error) = &online.error {
+            Some(error.to_uppercase())
+        } else if !online.is_connected() {
+            Some("CONNECTING".to_string())
+        } else if online.session.is_none() || online.stalled_updates > STALL_BANNER_UPDATES {
+            Some("WAITING FOR PLAYERS".to_string())
+        } else {
+            None
+        }
     }
 
-    fn draw_rect(&self, rect: &Vector2D, color: &str) {
+    // a bar along the bottom showing how far into the replay we are, which can be clicked or
+    // dragged along to go somewhere else in it
+    fn draw_timeline(&self, viewer: &Viewer) {
         let context = &self.context;
+        let top = self.height - TIMELINE_HEIGHT;
+        let bar_width = self.width - TIMELINE_MARGIN * 2.;
+        let fraction = match viewer.end() {
+            0 => 0.,
+            end => self.sim.tick as f64 / end as f64,
+        };
         context.save();
-        context.set_fill_style(&JsValue::from(color));
-        context.set_stroke_style(&JsValue::from("black"));
-        context.set_line_width(1.);
-        context.begin_path();
-        context.rect(
-            self.rect_size * rect.x as f64,
-            self.rect_size * rect.y as f64,
-            self.rect_size,
-            self.rect_size,
+        context.set_fill_style_str("#222");
+        context.fill_rect(0., top, self.width, TIMELINE_HEIGHT);
+        context.set_fill_style_str("grey");
+        context.fill_rect(TIMELINE_MARGIN, top + 8., bar_width, 6.);
+        context.set_fill_style_str("lime");
+        context.fill_rect(TIMELINE_MARGIN, top + 8., bar_width * fraction, 6.);
+        context.fill_rect(
+            TIMELINE_MARGIN + bar_width * fraction - 2.,
+            top + 4.,
+            4.,
+            14.,
         );
-        context.fill();
-        context.stroke();
+
+        context.set_font("14px Arial");
+        context.set_text_baseline("top");
+        context.set_fill_style_str("white");
+        context
+            .fill_text(
+                &format!("update {} of {}", self.sim.tick, viewer.end()),
+                TIMELINE_MARGIN,
+                top + 22.,
+            )
+            .expect("Something's gone wrong here");
+        let state = if viewer.is_playing { "" } else { "paused    " };
+        context.set_text_align("right");
+        context
+            .fill_text(
+                &format!("{}{}x", state, viewer.speed()),
+                self.width - TIMELINE_MARGIN,
+                top + 22.,
+            )
+            .expect("Something's gone wrong here");
         context.restore();
     }
 
-    fn draw_head(&self, rect: &Vector2D) {
-        let tl = FVector2D {
-            x: self.rect_size * rect.x as f64,
-            y: self.rect_size * rect.y as f64,
+    // how we're doing against the ghost at the same point in its game, in the top right corner
+    fn draw_ghost_delta(&self) {
+        let (ghost, snake) = match (&self.ghost, self.sim.snakes.first()) {
+            (Some(ghost), Some(snake)) => (ghost, snake),
+            _ => return,
+        };
+        let delta = snake.score as i64 - ghost.score() as i64;
+        let (text, color) = match delta.cmp(&0) {
+            Ordering::Greater => (format!("{} ahead of your best", delta), "lime"),
+            Ordering::Less => (format!("{} behind your best", -delta), "tomato"),
+            Ordering::Equal => ("level with your best".to_string(), "white"),
         };
 
         let context = &self.context;
         context.save();
-        context.set_fill_style(&JsValue::from(HEAD_COLOR));
-        context.set_stroke_style(&JsValue::from("black"));
-        context.set_line_width(1.);
-        context.begin_path();
-        context.rect(tl.x, tl.y, self.rect_size, self.rect_size);
-        context.fill();
-        context.stroke();
-        context.restore();
-
-        context.save();
+        context.set_font("16px Arial");
+        context.set_text_align("right");
+        context.set_text_baseline("top");
+        context.set_fill_style_str(color);
         context
-            .translate(tl.x + self.rect_size / 2., tl.y + self.rect_size / 2.)
-            .unwrap();
+            .fill_text(&text, self.width - 10., 10.)
+            .expect("Something's gone wrong here");
+        context.restore();
+    }
 
-        let angle = Inner::get_angle(&self.head_direction);
-        context.rotate(angle * f64::consts::PI / 180.).unwrap();
+    // what's going on under the hood, in the top left corner
+    fn draw_debug(&self) {
+        let lines = self.debug_lines();
+        let mut canvas = Canvas(&self.context);
+        canvas.save();
+        canvas.set_alpha(DEBUG_ALPHA);
+        let height = lines.len() as f64 * DEBUG_LINE_SPACING + DEBUG_MARGIN;
+        canvas.rect(0., 0., DEBUG_WIDTH, height, "black", "transparent");
+        canvas.restore();
+        for (index, line) in lines.iter().enumerate() {
+            let y = DEBUG_MARGIN / 2. + (index as f64 + 0.5) * DEBUG_LINE_SPACING;
+            canvas.text(line, DEBUG_MARGIN, y, DEBUG_TEXT_SIZE, Align::Left, "white");
+        }
+    }
 
-        let x_buffer = -4.;
-        let y_buffer = -4.;
+    fn debug_lines(&self) -> Vec<String> {
+        let next_update = if self.viewer.is_some() {
+            "next update: up to the replay viewer".to_string()
+        } else if self.effectively_paused() {
+            format!("next update: paused, {} steps once", STEP_KEY)
+        } else {
+            // the frame it gets to 0 on is the one it updates on
+            format!(
+                "next update in {} of {} frames",
+                self.frames_until_update + 1,
+                self.frames_between_updates
+            )
+        };
+        let (seed, draws) = self.sim.randomness();
+        let square = match self.cursor_square() {
+            Some(square) => format!("mouse over {}, {}", square.x, square.y),
+            None => "mouse off the board".to_string(),
+        };
 
-        context
-            .translate(-self.rect_size / 2., -self.rect_size / 2.)
-            .unwrap();
+        let mut lines = vec![
+            format!("update {}", self.sim.tick),
+            next_update,
+            format!(
+                "keys {:?} {}/{}",
+                self.key_buff,
+                self.key_buff.len(),
+                MAX_KEY_BUFF_LEN
+            ),
+        ];
+        for (snake, pilot) in self.sim.snakes.iter().zip(self.pilots.iter()) {
+            lines.push(match &pilot.controller {
+                Some(controller) => format!("{}: {}", snake.player.name, controller.name()),
+                None => format!(
+                    "{} keys {:?} {}/{}",
+                    snake.player.name,
+                    pilot.key_buff,
+                    pilot.key_buff.len(),
+                    MAX_KEY_BUFF_LEN
+                ),
+            });
+        }
+        lines.push(format!("seed {} after {} draws", seed, draws));
+        lines.push(square);
+        lines.push(format!("drew the last frame in {:.2} ms", self.render_time));
+        lines
+    }
 
-        context.set_fill_style(&JsValue::from("black"));
-        context.begin_path();
-        context.move_to(-x_buffer, 0.);
-        context.line_to(self.rect_size + x_buffer, 0.);
-        context.line_to(self.rect_size / 2., self.rect_size + y_buffer);
-        context.fill();
-        context.restore();
+    fn draw_banner(&self, text: &str) {
+        render::draw_banner(&mut Canvas(&self.context), self.width, self.height, text);
     }
 
-    fn get_angle(direction: &Vector2D) -> f64 {
-        return match direction {
-            Vector2D { x: 1, y: 0 } => 90.,
-            Vector2D { x: -1, y: 0 } => 270.,
-            Vector2D { x: 0, y: 1 } => 180.,
-            Vector2D { x: 0, y: -1 } => 0.,
-            _ => 0.,
+    fn draw_match_result(&self) {
+        let title = match self.sim.get_winner() {
+            Some(winner) => format!("{} WINS!", self.sim.snakes[winner].player.name),
+            None => "DRAW".to_string(),
         };
+        let lines: Vec<(String, &str)> = self
+            .sim
+            .snakes
+            .iter()
+            .map(|snake| {
+                let line = format!(
+                    "{}    score {}    length {}{}",
+                    snake.player.name,
+                    snake.score,
+                    snake.path.len(),
+                    if snake.is_alive { "" } else { "    (dead)" }
+                );
+                (line, snake.player.colors.head)
+            })
+            .chain(self.rewind_hint().map(|hint| (hint, "white")))
+            .chain(self.view_hint().map(|hint| (hint, "white")))
+            .collect();
+        self.draw_results(&title, &lines);
     }
 
-    fn draw_circles<'a, I>(&self, circles: I, color: &str)
-    where
-        I: Iterator<Item = &'a Vector2D>,
-    {
-        let context = &self.context;
-        let radius = self.rect_size / 2.;
-        let border = 2.;
+    fn draw_results(&self, title: &str, lines: &[(String, &str)]) {
+        render::draw_results(
+            &mut Canvas(&self.context),
+            self.width,
+            self.height,
+            title,
+            lines,
+        );
+    }
+}
+
+// what our own snake does next in an online game
+fn local_command(sim: &Simulation, pilots: &mut [Pilot], me: usize) -> Command {
+    if sim.snakes.get(me).is_some_and(|snake| snake.is_alive) {
+        pilots[me].next_command(&sim.board_view(me))
+    } else {
+        Command::Continue
+    }
+}
+
+// in milliseconds, to a finer grain than Date::now where the browser allows it
+fn now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or_else(js_sys::Date::now, |performance| performance.now())
+}
+
+fn log_state(sim: &Simulation) {
+    log::info!("the game as it stands:\n{}", state::to_text(sim));
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// the day of the last attempt at the daily challenge in this browser, and how it went if it
+// finished
+fn load_daily() -> Option<(u64, Option<String>)> {
+    let text = storage()?.get_item(DAILY_STORAGE_KEY).ok()??;
+    let mut lines = text.lines();
+    let day = lines.next()?.parse().ok()?;
+    Some((day, lines.next().map(str::to_string)))
+}
+
+fn save_daily(day: u64, result: Option<&str>) {
+    let storage = match storage() {
+        Some(storage) => storage,
+        None => return log::warn!("nowhere to remember the daily challenge"),
+    };
+    let text = match result {
+        Some(result) => format!("{}\n{}", day, result),
+        None => day.to_string(),
+    };
+    if let Err(err) = storage.set_item(DAILY_STORAGE_KEY, &text) {
+        log::warn!("couldn't remember the daily challenge: {:?}", err);
+    }
+}
+
+fn load_save(slot: &str) -> Option<Save> {
+    let key = format!("{}{}", SAVE_STORAGE_PREFIX, slot);
+    let text = storage()?.get_item(&key).ok()??;
+    Save::parse(&text)
+        .map_err(|err| log::warn!("couldn't read the game saved in {}: {}", slot, err))
+        .ok()
+}
+
+// None clears the slot. Returns whether it worked
+fn store_save(slot: &str, save: Option<&Save>) -> bool {
+    let key = format!("{}{}", SAVE_STORAGE_PREFIX, slot);
+    let stored = storage().map(|storage| match save {
+        Some(save) => storage.set_item(&key, &save.to_text()),
+        None => storage.remove_item(&key),
+    });
+    if !matches!(stored, Some(Ok(()))) {
+        log::warn!("couldn't save the game in {}", slot);
+        return false;
+    }
+    true
+}
+
+// the best run on a board in this browser
+fn load_best(board: &str) -> Option<Submission> {
+    let key = format!("{}{}", BEST_STORAGE_PREFIX, board);
+    let text = storage()?.get_item(&key).ok()??;
+    Submission::parse(&text)
+        .map_err(|err| log::warn!("couldn't read the best run on {}: {}", board, err))
+        .ok()
+}
+
+fn save_best(board: &str, best: &Submission) {
+    let key = format!("{}{}", BEST_STORAGE_PREFIX, board);
+    let saved = storage().map(|storage| storage.set_item(&key, &best.to_text()));
+    if !matches!(saved, Some(Ok(()))) {
+        log::warn!("couldn't remember the best run on {}", board);
+    }
+}
+
+// the browser's canvas, for the drawing that's shared with the exporters
+struct Canvas<'a>(&'a CanvasRenderingContext2d);
+
+impl Renderer for Canvas<'_> {
+    fn save(&mut self) {
+        self.0.save();
+    }
+
+    fn restore(&mut self) {
+        self.0.restore();
+    }
+
+    fn set_alpha(&mut self, alpha: f64) {
+        self.0.set_global_alpha(alpha);
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.0.translate(x, y).unwrap();
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        self.0.rotate(angle).unwrap();
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str) {
+        let context = self.0;
         context.save();
-        context.set_fill_style(&JsValue::from(color));
-        context.set_stroke_style(&JsValue::from("black"));
+        context.set_fill_style_str(fill);
+        context.set_stroke_style_str(stroke);
         context.set_line_width(1.);
-        for pos in circles {
-            context.begin_path();
-            context
-                .arc(
-                    self.rect_size * pos.x as f64 + radius,
-                    self.rect_size * pos.y as f64 + radius,
-                    radius - border,
-                    0.,
-                    TAU,
-                )
-                .unwrap();
-            context.fill();
-            context.stroke();
-        }
+        context.begin_path();
+        context.rect(x, y, width, height);
+        context.fill();
+        context.stroke();
         context.restore();
     }
 
-    fn draw_banner(&self, text: &str) {
-        let context = &self.context;
+    fn circle(&mut self, x: f64, y: f64, radius: f64, fill: &str, stroke: &str) {
+        let context = self.0;
         context.save();
-        context.set_fill_style(&JsValue::from("white"));
-        context.set_global_alpha(0.5);
-        let quarter_height = self.height / 4.;
-        context.fill_rect(
-            0.,
-            quarter_height,
-            self.width,
-            self.height - quarter_height * 2.,
-        );
+        context.set_fill_style_str(fill);
+        context.set_stroke_style_str(stroke);
+        context.set_line_width(1.);
+        context.begin_path();
+        context.arc(x, y, radius, 0., TAU).unwrap();
+        context.fill();
+        context.stroke();
         context.restore();
+    }
 
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str, width: f64) {
+        let context = self.0;
         context.save();
+        context.set_stroke_style_str(color);
+        context.set_line_width(width);
         context.begin_path();
-        context.set_font("60px Arial");
-        context.set_stroke_style(&JsValue::from("white"));
-        context.set_font("60px Arial");
-        context.set_text_align("center");
-        context.set_text_baseline("middle");
-        context.set_fill_style(&JsValue::from("white"));
-        context
-            .fill_text_with_max_width(text, self.width / 2., self.height / 2., self.width)
-            .expect("Something's gone wrong here");
+        context.move_to(from.0, from.1);
+        context.line_to(to.0, to.1);
+        context.stroke();
         context.restore();
     }
 
-    fn get_random_empty_space(&mut self) -> Option<Vector2D> {
-        let empty_squares = self.get_empty_squares();
-        if let Some(space) = empty_squares.choose(&mut self.rng) {
-            return Some(Vector2D {
-                x: space.x,
-                y: space.y,
-            });
-        }
-        return None;
-    }
-
-    fn get_empty_squares(&mut self) -> Vec<Vector2D> {
-        let mut rv = vec![];
-        for x in 0..self.num_squares_x {
-            for y in 0..self.num_squares_y {
-                if let ICellContents::Empty = self.contents_of_square(x, y) {
-                    rv.push(Vector2D { x: x, y: y });
-                }
+    fn polygon(&mut self, points: &[(f64, f64)], fill: &str) {
+        let context = self.0;
+        context.save();
+        context.set_fill_style_str(fill);
+        context.begin_path();
+        for (index, &(x, y)) in points.iter().enumerate() {
+            if index == 0 {
+                context.move_to(x, y);
+            } else {
+                context.line_to(x, y);
             }
         }
-        return rv;
+        context.fill();
+        context.restore();
     }
 
-    fn contents_of_square(&self, x: i32, y: i32) -> ICellContents {
-        for pos in self.path.iter() {
-            if pos.x == x && pos.y == y {
-                return ICellContents::Snake;
-            }
-        }
-
-        for pos in self.apples.iter() {
-            if pos.x == x && pos.y == y {
-                return ICellContents::Apple;
-            }
-        }
-
-        return ICellContents::Empty;
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: Align, color: &str) {
+        let context = self.0;
+        context.save();
+        context.set_font(&format!("{}px Arial", size));
+        context.set_text_align(align.name());
+        context.set_text_baseline("middle");
+        context.set_fill_style_str(color);
+        // squeezed rather than running off the side of the canvas
+        let max_width = context
+            .canvas()
+            .map_or(f64::INFINITY, |canvas| canvas.width() as f64);
+        context
+            .fill_text_with_max_width(text, x, y, max_width)
+            .expect("Something's gone wrong here");
+        context.restore();
     }
 }
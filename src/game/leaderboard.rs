@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+
+use super::daily::{self, Challenge};
+use super::level::Level;
+use super::replay::Replay;
+use super::simulation::Settings;
+use super::spawn;
+
+// The leaderboard server (src/bin/snake_leaderboard.rs) talks plain HTTP:
+//
+//   POST /submit?name=NAME     the body's a submission (see verify.rs). Answers 200 with
+//                              "accepted RANK", where RANK is where NAME's best now comes on
+//                              the board's all-time list, or 400 with why it was rejected
+//   GET /top?board=KEY&period=PERIOD&limit=N
+//                              the best scores on a board, one entry line each, best first
+//
+// Every entry's stored as a line of text, which is also how they're sent back:
+//
+//   entry TIME BOARD SCORE TICKS NAME
+//
+// where TIME is seconds since 1970 and NAME is everything left on the line.
+
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+pub const MAX_NAME_LEN: usize = 16;
+
+// one accepted score
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub time: u64,
+    pub board: String,
+    pub score: u32,
+    pub ticks: u64,
+    pub name: String,
+}
+
+impl Entry {
+    pub fn to_text(&self) -> String {
+        format!(
+            "entry {} {} {} {} {}",
+            self.time, self.board, self.score, self.ticks, self.name
+        )
+    }
+
+    pub fn parse(line: &str) -> Result<Entry, String> {
+        let words: Vec<&str> = line.trim().splitn(6, ' ').collect();
+        let number_error = |word: &str| format!("expected a number, not {}", word);
+        match words.as_slice() {
+            ["entry", time, board, score, ticks, name] => Ok(Entry {
+                time: time.parse().map_err(|_| number_error(time))?,
+                board: board.to_string(),
+                score: score.parse().map_err(|_| number_error(score))?,
+                ticks: ticks.parse().map_err(|_| number_error(ticks))?,
+                name: name.to_string(),
+            }),
+            _ => Err(format!("not an entry: {}", line)),
+        }
+    }
+}
+
+// how far back a view of the leaderboard looks. Days and weeks are in UTC, and weeks start
+// on Monday
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Daily,
+    Weekly,
+    AllTime,
+}
+
+impl Period {
+    pub fn next(self) -> Period {
+        match self {
+            Period::Daily => Period::Weekly,
+            Period::Weekly => Period::AllTime,
+            Period::AllTime => Period::Daily,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+            Period::AllTime => "all-time",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Period, String> {
+        match text {
+            "daily" => Ok(Period::Daily),
+            "weekly" => Ok(Period::Weekly),
+            "all-time" => Ok(Period::AllTime),
+            _ => Err(format!("unknown period: {}", text)),
+        }
+    }
+
+    // the first second that counts, for a view taken at the given time
+    pub fn start(self, now: u64) -> u64 {
+        let day = now / SECONDS_PER_DAY;
+        match self {
+            Period::Daily => day * SECONDS_PER_DAY,
+            // the 1st of January 1970 was a Thursday
+            Period::Weekly => (day - (day + 3) % 7) * SECONDS_PER_DAY,
+            Period::AllTime => 0,
+        }
+    }
+}
+
+// Which board a game's score goes on. Scores are only compared between games with the same
+// size, apples, level and rules, so the key is made of all of those, e.g.
+// 30x20-5-untimed-near-walls-empty-block
+pub fn board_key(settings: &Settings) -> String {
+    let lifetime = match settings.apple_lifetime {
+        Some(lifetime) => format!("timed{}", lifetime),
+        None => "untimed".to_string(),
+    };
+    let strategies = spawn::builtin_strategies(&settings.level);
+    let spawn = strategies
+        .get(settings.spawn_strategy)
+        .map(|strategy| strategy.name())
+        .unwrap_or("unknown");
+    // keys go in URLs and lines of text, so they're one word
+    format!(
+        "{}x{}-{}-{}-{}-{}-{}",
+        settings.width,
+        settings.height,
+        settings.num_apples,
+        lifetime,
+        spawn,
+        settings.level.name,
+        settings.rules.self_collision.name()
+    )
+    .replace(' ', "-")
+}
+
+// The board a replay belongs on, if it can go on one at all. Only games played alone count,
+// since in versus nothing stops a submission making up its opponents' moves, and only on the
+// built-in levels and board sizes, so nobody gets to knock walls out of one or have the server
+// play out a game on an enormous board. Daily challenges from around the
+// given time go on their own board, with a day's grace either side for games over midnight
+pub fn board_for(replay: &Replay, now: u64) -> Result<String, String> {
+    let settings = &replay.settings;
+    if settings.num_snakes != 1 {
+        return Err("only games played alone go on the leaderboard".to_string());
+    }
+    if !daily::SIZES.contains(&(settings.width, settings.height)) {
+        return Err(format!(
+            "the game isn't played on {}x{} boards",
+            settings.width, settings.height
+        ));
+    }
+    if settings.spawn_strategy >= spawn::builtin_strategies(&settings.level).len() {
+        return Err(format!(
+            "unknown spawn strategy {}",
+            settings.spawn_strategy
+        ));
+    }
+    match Level::find_builtin(&settings.level.name) {
+        Some(level) if level.to_text() == settings.level.to_text() => {}
+        Some(_) => {
+            return Err(format!(
+                "that's not what {} looks like",
+                settings.level.name
+            ))
+        }
+        None => return Err(format!("unknown level {}", settings.level.name)),
+    }
+
+    let today = now / SECONDS_PER_DAY;
+    let challenge = (today.saturating_sub(1)..=today + 1)
+        .map(Challenge::for_day)
+        .find(|challenge| challenge.matches(replay));
+    Ok(match challenge {
+        Some(challenge) => challenge.board_key(),
+        None => board_key(settings),
+    })
+}
+
+// names go in the middle of a line of text, so they're kept short and on one line
+pub fn clean_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LEN)
+        .collect();
+    match name.trim() {
+        "" => "anonymous".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// every score anyone's had accepted
+#[derive(Debug, Clone, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<Entry>,
+}
+
+impl Leaderboard {
+    pub fn parse(text: &str) -> Result<Leaderboard, String> {
+        let entries = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_number, line)| {
+                Entry::parse(line).map_err(|err| format!("line {}: {}", line_number + 1, err))
+            })
+            .collect::<Result<Vec<Entry>, String>>()?;
+        Ok(Leaderboard { entries })
+    }
+
+    // The best scores on a board over a period, best first. Everybody's only listed once, with
+    // their best, and ties go to whoever got there in fewer updates, then whoever got there first
+    pub fn top(&self, board: &str, period: Period, now: u64, limit: usize) -> Vec<&Entry> {
+        let start = period.start(now);
+        let mut entries: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.board == board && entry.time >= start)
+            .collect();
+        entries.sort_by_key(|entry| (std::cmp::Reverse(entry.score), entry.ticks, entry.time));
+
+        let mut names = HashSet::new();
+        entries
+            .into_iter()
+            .filter(|entry| names.insert(entry.name.as_str()))
+            .take(limit)
+            .collect()
+    }
+
+    // where an entry comes on its board's all-time list, counting from 1
+    pub fn rank(&self, entry: &Entry) -> usize {
+        let top = self.top(&entry.board, Period::AllTime, entry.time, usize::MAX);
+        top.iter()
+            .position(|other| other.name == entry.name)
+            .map(|index| index + 1)
+            .unwrap_or(top.len() + 1)
+    }
+}
@@ -0,0 +1,107 @@
+use super::vector::Vector2D;
+
+// A level is a plain text file, one instruction per line:
+//
+//   # comments start with a hash
+//   wall 3 4          a single wall square
+//   wall 0 0 29 0     a filled rectangle of wall, corners inclusive
+//   apple 10 5        scripted apple positions, used in order by the scripted spawner
+#[derive(Debug, Clone, Default)]
+pub struct Level {
+    pub name: String,
+    pub walls: Vec<Vector2D>,
+    pub apples: Vec<Vector2D>,
+}
+
+impl Level {
+    pub fn parse(name: &str, text: &str) -> Result<Level, String> {
+        let mut level = Level {
+            name: name.to_string(),
+            ..Level::default()
+        };
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let instruction = words.next().unwrap();
+            let numbers = words
+                .map(|word| word.parse::<i32>())
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|err| format!("line {}: {}", line_number + 1, err))?;
+
+            match (instruction, numbers.as_slice()) {
+                ("wall", &[x, y]) => level.walls.push(Vector2D { x, y }),
+                ("wall", &[x1, y1, x2, y2]) => {
+                    for x in x1.min(x2)..=x1.max(x2) {
+                        for y in y1.min(y2)..=y1.max(y2) {
+                            level.walls.push(Vector2D { x, y });
+                        }
+                    }
+                }
+                ("apple", &[x, y]) => level.apples.push(Vector2D { x, y }),
+                _ => {
+                    return Err(format!(
+                        "line {}: don't know what to do with \"{}\"",
+                        line_number + 1,
+                        line
+                    ))
+                }
+            }
+        }
+
+        Ok(level)
+    }
+
+    // every wall and scripted apple has to be on a board this size, rather than quietly going
+    // missing
+    pub fn check_fits(&self, width: i32, height: i32) -> Result<(), String> {
+        let is_on_board =
+            |square: &Vector2D| (0..width).contains(&square.x) && (0..height).contains(&square.y);
+        let off_board = self
+            .walls
+            .iter()
+            .map(|wall| ("wall", wall))
+            .chain(self.apples.iter().map(|apple| ("apple", apple)))
+            .find(|(_, square)| !is_on_board(square));
+        match off_board {
+            Some((kind, square)) => Err(format!(
+                "level {} has {} {} {}, off the edge of a {}x{} board",
+                self.name, kind, square.x, square.y, width, height
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // the level written back out in the same format, one square per line
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for wall in self.walls.iter() {
+            text.push_str(&format!("wall {} {}\n", wall.x, wall.y));
+        }
+        for apple in self.apples.iter() {
+            text.push_str(&format!("apple {} {}\n", apple.x, apple.y));
+        }
+        text
+    }
+
+    pub fn find_builtin(name: &str) -> Option<Level> {
+        Level::builtin()
+            .into_iter()
+            .find(|level| level.name == name)
+    }
+
+    pub fn builtin() -> Vec<Level> {
+        [
+            ("empty", include_str!("levels/empty.txt")),
+            ("pillars", include_str!("levels/pillars.txt")),
+            ("arena", include_str!("levels/arena.txt")),
+        ]
+        .iter()
+        .map(|(name, text)| Level::parse(name, text).expect("built-in levels should parse"))
+        .collect()
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use super::controller::Command;
+use super::net::Message;
+
+pub const DEFAULT_INPUT_DELAY: u64 = 3; // how many updates ahead our own commands are scheduled
+const HASH_INTERVAL: u64 = 10; // how often players compare notes on what the game looks like
+const HASHES_KEPT: u64 = 1000; // how many updates back we remember our own hashes for
+
+// Keeps a networked game in step. Every player's simulation only moves on once it has everyone's
+// command for the next tick, so they all play out exactly the same game. Commands get scheduled
+// input_delay ticks ahead of where we are, so they've usually arrived by the time they're needed
+// and nobody has to wait.
+pub struct Lockstep {
+    pub me: usize,
+    pub num_players: usize,
+    pub input_delay: u64,
+    // everyone's commands for ticks that haven't been played yet
+    inputs: BTreeMap<u64, Vec<Option<Command>>>,
+    // the first tick we haven't sent our own command for
+    next_input: u64,
+    pub hashes: HashLog,
+}
+
+impl Lockstep {
+    pub fn new(me: usize, num_players: usize, input_delay: u64) -> Lockstep {
+        let mut lockstep = Lockstep {
+            me,
+            num_players,
+            input_delay,
+            inputs: BTreeMap::new(),
+            next_input: input_delay,
+            hashes: HashLog::new(me),
+        };
+        // nobody's had a chance to say anything about the first few ticks, so they're free
+        for tick in 0..input_delay {
+            lockstep
+                .inputs
+                .insert(tick, vec![Some(Command::Continue); num_players]);
+        }
+        lockstep
+    }
+
+    // whether there's a tick waiting on our command, as of having played tick ticks
+    pub fn wants_input(&self, tick: u64) -> bool {
+        self.next_input <= tick + self.input_delay
+    }
+
+    // schedules our command, and gives back the messages to send everyone. If we've fallen
+    // behind, the ticks we skipped just keep going the way they were
+    pub fn send(&mut self, tick: u64, command: Command) -> Vec<Message> {
+        let mut messages = vec![];
+        let last = tick + self.input_delay;
+        while self.next_input <= last {
+            let command = if self.next_input == last {
+                command
+            } else {
+                Command::Continue
+            };
+            let input = Message::Input {
+                slot: self.me,
+                tick: self.next_input,
+                command,
+            };
+            self.receive(&input);
+            messages.push(input);
+        }
+        messages
+    }
+
+    // takes in the messages that matter to the game, and ignores the rest
+    pub fn receive(&mut self, message: &Message) {
+        match *message {
+            Message::Input {
+                slot,
+                tick,
+                command,
+            } if slot < self.num_players => {
+                let num_players = self.num_players;
+                let commands = self
+                    .inputs
+                    .entry(tick)
+                    .or_insert_with(|| vec![None; num_players]);
+                commands[slot] = Some(command);
+                // after rejoining, the server reminds us what we'd already sent
+                if slot == self.me {
+                    self.next_input = self.next_input.max(tick + 1);
+                }
+            }
+            Message::Hash { .. } => self.hashes.receive(message),
+            _ => {}
+        }
+    }
+
+    // how many ticks in a row, starting at tick, we have every command for
+    pub fn ready_ticks(&self, tick: u64) -> u64 {
+        let mut ready = 0;
+        while self
+            .inputs
+            .get(&(tick + ready))
+            .is_some_and(|commands| commands.iter().all(|command| command.is_some()))
+        {
+            ready += 1;
+        }
+        ready
+    }
+
+    // everyone's commands for tick, if they've all arrived
+    pub fn take_commands(&mut self, tick: u64) -> Option<Vec<Command>> {
+        if self.ready_ticks(tick) == 0 {
+            return None;
+        }
+        let commands = self.inputs.remove(&tick)?;
+        Some(
+            commands
+                .into_iter()
+                .map(|command| command.unwrap())
+                .collect(),
+        )
+    }
+
+    // hands over our hash for the tick we just played, if it's time to send one
+    pub fn record_hash(&mut self, tick: u64, hash: u64) -> Option<Message> {
+        self.hashes.record(tick, hash)
+    }
+}
+
+// Every so often everybody sends a hash of their game, and if one doesn't match ours something's
+// gone wrong and the games have desynced
+pub struct HashLog {
+    me: usize,
+    hashes: BTreeMap<u64, u64>,
+    // other players' hashes for ticks we haven't got to yet
+    remote_hashes: Vec<(u64, u64)>,
+    // the first tick we saw disagreeing with someone else
+    pub desync: Option<u64>,
+}
+
+impl HashLog {
+    pub fn new(me: usize) -> HashLog {
+        HashLog {
+            me,
+            hashes: BTreeMap::new(),
+            remote_hashes: vec![],
+            desync: None,
+        }
+    }
+
+    // hands over our hash for a tick that's settled for good, if it's time to send one
+    pub fn record(&mut self, tick: u64, hash: u64) -> Option<Message> {
+        if !tick.is_multiple_of(HASH_INTERVAL) {
+            return None;
+        }
+
+        self.hashes.insert(tick, hash);
+        self.hashes = self.hashes.split_off(&tick.saturating_sub(HASHES_KEPT));
+        let (arrived, waiting) = self
+            .remote_hashes
+            .drain(..)
+            .partition(|(remote_tick, _)| *remote_tick <= tick);
+        self.remote_hashes = waiting;
+        for (remote_tick, remote_hash) in arrived {
+            if remote_tick == tick {
+                self.check(tick, hash, remote_hash);
+            }
+        }
+
+        Some(Message::Hash {
+            slot: self.me,
+            tick,
+            hash,
+        })
+    }
+
+    pub fn receive(&mut self, message: &Message) {
+        if let Message::Hash { slot, tick, hash } = *message {
+            if slot == self.me {
+                return;
+            }
+            match self.hashes.get(&tick) {
+                Some(ours) => self.check(tick, *ours, hash),
+                None => self.remote_hashes.push((tick, hash)),
+            }
+        }
+    }
+
+    fn check(&mut self, tick: u64, ours: u64, theirs: u64) {
+        if ours != theirs && self.desync.is_none_or(|desync| tick < desync) {
+            log::warn!("desynced at tick {}", tick);
+            self.desync = Some(tick);
+        }
+    }
+}
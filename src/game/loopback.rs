@@ -0,0 +1,167 @@
+use rand::{Rng, SeedableRng};
+
+use super::controller::{Command, SnakeController};
+use super::net::Message;
+use super::rollback::{Rollback, RollbackStats};
+use super::simulation::{GameRng, Settings, Simulation};
+use super::tournament::Bot;
+
+// how bad the pretend network is, and what's played over it
+#[derive(Clone)]
+pub struct LoopbackConfig {
+    pub settings: Settings,
+    pub seed: u64,
+    pub input_delay: u64,
+    // how many updates every message takes to arrive
+    pub latency: u64,
+    // up to how many more updates on top of that, picked at random for each message
+    pub jitter: u64,
+    // the chance of any one message never arriving at all
+    pub loss: f64,
+    // where the game gets cut off if nobody's died by then
+    pub max_ticks: u64,
+}
+
+// how one player's game went
+pub struct PeerReport {
+    pub stats: RollbackStats,
+    pub confirmed: u64,
+    pub desync: Option<u64>,
+    // whether a fresh game played with the commands this player settled on ends up in exactly
+    // the same place
+    pub matches_replay: bool,
+}
+
+pub struct LoopbackReport {
+    pub peers: Vec<PeerReport>,
+    // how many updates it took for everybody to settle on the end of the game
+    pub updates: u64,
+    // whether everybody got there before we gave up on them
+    pub finished: bool,
+    // whether everybody settled on the same commands for every tick
+    pub histories_agree: bool,
+}
+
+impl LoopbackReport {
+    pub fn is_ok(&self) -> bool {
+        self.finished
+            && self.histories_agree
+            && self
+                .peers
+                .iter()
+                .all(|peer| peer.desync.is_none() && peer.matches_replay)
+    }
+}
+
+struct Peer {
+    rollback: Rollback,
+    sim: Simulation,
+    controller: Box<dyn SnakeController>,
+}
+
+impl Peer {
+    fn is_done(&self, max_ticks: u64) -> bool {
+        self.rollback.is_settled(&self.sim) && (self.sim.is_game_over || self.sim.tick >= max_ticks)
+    }
+}
+
+// a message on its way to somebody
+struct InFlight {
+    arrival: u64,
+    to: usize,
+    text: String,
+}
+
+// Plays an online game with rollback between one player per bot, all in this process, with
+// every message going through a pretend relay server that delays and loses them as configured.
+// Messages go over as text, the same as they would over a WebSocket.
+pub fn run(config: &LoopbackConfig, bots: &[&Bot]) -> LoopbackReport {
+    let mut settings = config.settings.clone();
+    settings.num_snakes = bots.len();
+    let num_players = bots.len();
+    let mut peers: Vec<Peer> = bots
+        .iter()
+        .enumerate()
+        .map(|(me, bot)| Peer {
+            rollback: Rollback::new(me, num_players, config.input_delay),
+            sim: Simulation::new(settings.clone(), config.seed),
+            controller: (bot.factory)(),
+        })
+        .collect();
+    let mut network_rng = GameRng::seed_from_u64(config.seed);
+    let mut in_flight: Vec<InFlight> = vec![];
+    // with enough packet loss a game can take forever, so give up eventually
+    let give_up = config.max_ticks * 10 + 1000;
+
+    let mut updates = 0;
+    while updates < give_up && !peers.iter().all(|peer| peer.is_done(config.max_ticks)) {
+        let (arrived, waiting) = in_flight
+            .drain(..)
+            .partition(|message| message.arrival <= updates);
+        in_flight = waiting;
+        for InFlight { to, text, .. } in arrived {
+            let message = Message::parse(&text).expect("sent a message we can't read");
+            peers[to].rollback.receive(&message);
+        }
+
+        for (me, peer) in peers.iter_mut().enumerate() {
+            let sim = &mut peer.sim;
+            if sim.tick < config.max_ticks {
+                if peer.rollback.wants_input(sim.tick) {
+                    let command = if sim.snakes.get(me).is_some_and(|snake| snake.is_alive) {
+                        peer.controller.next_command(&sim.board_view(me))
+                    } else {
+                        Command::Continue
+                    };
+                    peer.rollback.add_local(sim.tick, command);
+                }
+                peer.rollback.advance(sim);
+            } else {
+                peer.rollback.settle(sim);
+            }
+
+            for message in peer.rollback.messages() {
+                let text = message.to_text();
+                for to in (0..num_players).filter(|to| *to != me) {
+                    if network_rng.gen::<f64>() < config.loss {
+                        continue;
+                    }
+                    let delay = config.latency + network_rng.gen_range(0, config.jitter + 1);
+                    in_flight.push(InFlight {
+                        arrival: updates + delay,
+                        to,
+                        text: text.clone(),
+                    });
+                }
+            }
+        }
+        updates += 1;
+    }
+
+    let histories_agree = peers
+        .windows(2)
+        .all(|pair| pair[0].rollback.history == pair[1].rollback.history);
+    let peers = peers
+        .iter()
+        .map(|peer| {
+            let mut replay = Simulation::new(settings.clone(), config.seed);
+            for commands in peer.rollback.history.iter() {
+                replay.step(commands);
+            }
+            let confirmed = peer.rollback.confirmed_state(&peer.sim);
+            PeerReport {
+                stats: peer.rollback.stats,
+                confirmed: peer.rollback.confirmed,
+                desync: peer.rollback.hashes.desync,
+                matches_replay: replay.state_hash() == confirmed.state_hash(),
+            }
+        })
+        .collect();
+
+    LoopbackReport {
+        peers,
+        updates,
+        finished: updates < give_up,
+        histories_agree,
+    }
+}
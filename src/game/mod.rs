@@ -1,2 +1,42 @@
+pub mod ai;
+pub mod autopilot;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+pub mod controller;
+pub mod daily;
+pub mod env;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod evolve;
+#[allow(clippy::module_inception)]
 pub mod game;
+pub mod ghost;
 pub mod inner;
+pub mod leaderboard;
+pub mod level;
+pub mod lockstep;
+pub mod loopback;
+pub mod net;
+pub mod neural;
+pub mod online;
+pub mod raster;
+pub mod record;
+pub mod replay;
+pub mod rollback;
+pub mod render;
+pub mod rewind;
+pub mod rules;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sandbox;
+pub mod save;
+pub mod scoreboard;
+pub mod simulation;
+pub mod snake;
+pub mod spawn;
+pub mod spectate;
+pub mod state;
+pub mod stream;
+pub mod svg;
+pub mod tournament;
+pub mod vector;
+pub mod verify;
+pub mod viewer;
@@ -0,0 +1,242 @@
+use super::controller::Command;
+use super::replay::{self, Replay};
+use super::spectate::{Delta, Snapshot};
+
+// How the browser and the relay server (src/bin/snake_server.rs) talk over a WebSocket. Every
+// message is text, with the first word saying what it is:
+//
+//   join <room>                        asks for a seat in a room
+//   rejoin <room> <slot> <token>       takes a seat back after losing the connection
+//   welcome <slot> <token> <size>      your seat, the token to get it back, and the room's size
+//   joined <slot>                      somebody took a seat, or took theirs back
+//   left <slot>                        somebody's connection dropped
+//   start <input delay> <netcode>      the host starting a game, followed on the next lines by
+//   <replay>                           a replay with no ticks, giving the settings and seed
+//   input <slot> <tick> <command>      what a snake does on a tick, as a replay character
+//   inputs <slot> <ack> <tick> <commands>
+//                                      with rollback, every command of ours from tick on that
+//                                      somebody might not have, and the first tick we don't
+//                                      have everyone's commands for
+//   hash <slot> <tick> <hash>          what the game looked like to someone after a tick
+//   error <message>                    the server saying no
+//
+// The server doesn't run the game, it just passes on whatever players send to everyone else in
+// the room, and remembers it all so a player who drops out can catch back up.
+//
+// Games can also be watched. Watching is separate from playing, and uses channels rather than
+// rooms, with any number of watchers but only one game broadcasting on each:
+//
+//   broadcast <channel>                a game offering itself up to be watched
+//   watch <channel>                    asks to watch whatever's on a channel
+//   snapshot ...                       the whole game, as described in src/game/spectate.rs
+//   delta ...                          what's changed over a tick, likewise
+//
+// The server remembers the last snapshot and every delta since, so anyone who starts watching
+// partway through gets caught up.
+#[derive(Debug, Clone)]
+pub enum Message {
+    Join {
+        room: String,
+    },
+    Rejoin {
+        room: String,
+        slot: usize,
+        token: u64,
+    },
+    Welcome {
+        slot: usize,
+        token: u64,
+        size: usize,
+    },
+    Joined {
+        slot: usize,
+    },
+    Left {
+        slot: usize,
+    },
+    Start {
+        input_delay: u64,
+        netcode: Netcode,
+        replay: Replay,
+    },
+    Input {
+        slot: usize,
+        tick: u64,
+        command: Command,
+    },
+    Inputs {
+        slot: usize,
+        ack: u64,
+        first_tick: u64,
+        commands: Vec<Command>,
+    },
+    Hash {
+        slot: usize,
+        tick: u64,
+        hash: u64,
+    },
+    Error {
+        message: String,
+    },
+    Broadcast {
+        channel: String,
+    },
+    Watch {
+        channel: String,
+    },
+    Snapshot(Snapshot),
+    Delta(Delta),
+}
+
+impl Message {
+    pub fn to_text(&self) -> String {
+        match self {
+            Message::Join { room } => format!("join {}", room),
+            Message::Rejoin { room, slot, token } => format!("rejoin {} {} {}", room, slot, token),
+            Message::Welcome { slot, token, size } => {
+                format!("welcome {} {} {}", slot, token, size)
+            }
+            Message::Joined { slot } => format!("joined {}", slot),
+            Message::Left { slot } => format!("left {}", slot),
+            Message::Start {
+                input_delay,
+                netcode,
+                replay,
+            } => format!(
+                "start {} {}\n{}",
+                input_delay,
+                netcode.name(),
+                replay.to_text()
+            ),
+            Message::Input {
+                slot,
+                tick,
+                command,
+            } => format!(
+                "input {} {} {}",
+                slot,
+                tick,
+                replay::command_to_char(*command)
+            ),
+            Message::Inputs {
+                slot,
+                ack,
+                first_tick,
+                commands,
+            } => {
+                let commands: String = commands
+                    .iter()
+                    .map(|command| replay::command_to_char(*command))
+                    .collect();
+                format!("inputs {} {} {} {}", slot, ack, first_tick, commands)
+            }
+            Message::Hash { slot, tick, hash } => format!("hash {} {} {:x}", slot, tick, hash),
+            Message::Error { message } => format!("error {}", message),
+            Message::Broadcast { channel } => format!("broadcast {}", channel),
+            Message::Watch { channel } => format!("watch {}", channel),
+            Message::Snapshot(snapshot) => snapshot.to_text(),
+            Message::Delta(delta) => delta.to_text(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Message, String> {
+        let (first_line, rest) = match text.find('\n') {
+            Some(index) => (&text[..index], &text[index + 1..]),
+            None => (text, ""),
+        };
+        let words: Vec<&str> = first_line.split_whitespace().collect();
+        let error = || format!("don't know what to do with \"{}\"", first_line);
+        let number = |index: usize| -> Result<u64, String> {
+            words
+                .get(index)
+                .and_then(|word| word.parse().ok())
+                .ok_or_else(error)
+        };
+        let slot = |index: usize| number(index).map(|slot| slot as usize);
+        // rooms and channels are a single word, so they fit on the line with everything else
+        let room = || words.get(1).map(|room| room.to_string()).ok_or_else(error);
+
+        let message = match words.first().copied() {
+            Some("join") if words.len() == 2 => Message::Join { room: room()? },
+            Some("rejoin") if words.len() == 4 => Message::Rejoin {
+                room: room()?,
+                slot: slot(2)?,
+                token: number(3)?,
+            },
+            Some("welcome") if words.len() == 4 => Message::Welcome {
+                slot: slot(1)?,
+                token: number(2)?,
+                size: slot(3)?,
+            },
+            Some("joined") if words.len() == 2 => Message::Joined { slot: slot(1)? },
+            Some("left") if words.len() == 2 => Message::Left { slot: slot(1)? },
+            Some("start") if words.len() == 3 => Message::Start {
+                input_delay: number(1)?,
+                netcode: Netcode::parse(words[2]).ok_or_else(error)?,
+                replay: Replay::parse(rest)?,
+            },
+            Some("input") if words.len() == 4 => {
+                let mut chars = words[3].chars();
+                let command = match (chars.next(), chars.next()) {
+                    (Some(c), None) => replay::char_to_command(c).ok_or_else(error)?,
+                    _ => return Err(error()),
+                };
+                Message::Input {
+                    slot: slot(1)?,
+                    tick: number(2)?,
+                    command,
+                }
+            }
+            Some("inputs") if words.len() == 5 => Message::Inputs {
+                slot: slot(1)?,
+                ack: number(2)?,
+                first_tick: number(3)?,
+                commands: words[4]
+                    .chars()
+                    .map(replay::char_to_command)
+                    .collect::<Option<Vec<Command>>>()
+                    .ok_or_else(error)?,
+            },
+            Some("hash") if words.len() == 4 => Message::Hash {
+                slot: slot(1)?,
+                tick: number(2)?,
+                hash: u64::from_str_radix(words[3], 16).map_err(|_| error())?,
+            },
+            Some("error") => Message::Error {
+                message: first_line["error".len()..].trim().to_string(),
+            },
+            Some("broadcast") if words.len() == 2 => Message::Broadcast { channel: room()? },
+            Some("watch") if words.len() == 2 => Message::Watch { channel: room()? },
+            Some("snapshot") => Message::Snapshot(Snapshot::parse(text)?),
+            Some("delta") => Message::Delta(Delta::parse(text)?),
+            _ => return Err(error()),
+        };
+        Ok(message)
+    }
+}
+
+// how a game keeps everyone's copy the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Netcode {
+    // nobody moves on until everyone's commands are in (src/game/lockstep.rs)
+    Lockstep,
+    // guess at everyone else's commands, and replay if the guess was wrong (src/game/rollback.rs)
+    Rollback,
+}
+
+impl Netcode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Netcode::Lockstep => "lockstep",
+            Netcode::Rollback => "rollback",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Netcode> {
+        match name {
+            "lockstep" => Some(Netcode::Lockstep),
+            "rollback" => Some(Netcode::Rollback),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use rand::Rng;
+
+use super::controller::{BoardView, Command, SnakeController};
+use super::env::ObservationKind;
+use super::vector::Vector2D;
+
+// what the network sees: the eight rays from the training environment, plus which way the
+// nearest apple is, forward and to the right
+const SENSES: ObservationKind = ObservationKind::Rays;
+const COMPASS_INPUTS: usize = 2;
+// turn left, keep going, or turn right
+pub const NUM_OUTPUTS: usize = 3;
+
+// the genome the browser plays with, from src/bin/snake_train.rs
+const BUILTIN_GENOME: &str = include_str!("genomes/neural.genome");
+
+pub fn num_inputs() -> usize {
+    // rays don't depend on the size of the board
+    SENSES.len(0, 0) + COMPASS_INPUTS
+}
+
+// a small fully connected network. Hidden layers squash with tanh, and the last one is left as
+// is since we only care which output is biggest
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    // how many neurons are in each layer, inputs first
+    pub layers: Vec<usize>,
+    // layer by layer, each neuron's bias followed by its weights
+    pub weights: Vec<f32>,
+}
+
+fn num_weights(layers: &[usize]) -> usize {
+    layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+}
+
+impl Network {
+    pub fn random<R: Rng>(layers: Vec<usize>, rng: &mut R) -> Network {
+        let weights = (0..num_weights(&layers))
+            .map(|_| rng.gen_range(-1., 1.))
+            .collect();
+        Network { layers, weights }
+    }
+
+    // inputs, then however many hidden layers, then the outputs a bot needs
+    pub fn bot_layers(hidden: &[usize]) -> Vec<usize> {
+        let mut layers = vec![num_inputs()];
+        layers.extend_from_slice(hidden);
+        layers.push(NUM_OUTPUTS);
+        layers
+    }
+
+    pub fn builtin() -> Network {
+        Network::parse(BUILTIN_GENOME).expect("the built-in genome should parse")
+    }
+
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut values = inputs.to_vec();
+        let mut weights = self.weights.iter();
+        let last = self.layers.len() - 2;
+        for (index, pair) in self.layers.windows(2).enumerate() {
+            values = (0..pair[1])
+                .map(|_| {
+                    let bias = *weights.next().unwrap();
+                    let sum = values
+                        .iter()
+                        .fold(bias, |sum, value| sum + value * weights.next().unwrap());
+                    if index == last {
+                        sum
+                    } else {
+                        sum.tanh()
+                    }
+                })
+                .collect();
+        }
+        values
+    }
+
+    // like a level, one instruction per line:
+    //
+    //   layers 26 16 3
+    //   weights 0.25 -1.5 ...   any number of these lines, read one after the other
+    pub fn to_text(&self) -> String {
+        let layers: Vec<String> = self.layers.iter().map(|size| size.to_string()).collect();
+        let mut text = format!("layers {}\n", layers.join(" "));
+        let mut start = 0;
+        for pair in self.layers.windows(2) {
+            let end = start + (pair[0] + 1) * pair[1];
+            let weights: Vec<String> = self.weights[start..end]
+                .iter()
+                .map(|weight| weight.to_string())
+                .collect();
+            text.push_str(&format!("weights {}\n", weights.join(" ")));
+            start = end;
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Network, String> {
+        let mut layers = vec![];
+        let mut weights = vec![];
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("line {}: {}", line_number + 1, message);
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("layers") => {
+                    layers = words
+                        .map(|word| word.parse::<usize>())
+                        .collect::<Result<Vec<usize>, _>>()
+                        .map_err(|err| error(&err.to_string()))?
+                }
+                Some("weights") => {
+                    for word in words {
+                        weights.push(word.parse::<f32>().map_err(|err| error(&err.to_string()))?);
+                    }
+                }
+                _ => return Err(error(&format!("don't know what to do with \"{}\"", line))),
+            }
+        }
+
+        if layers.len() < 2 || layers.contains(&0) {
+            return Err("expected at least an input and an output layer".to_string());
+        }
+        if weights.len() != num_weights(&layers) {
+            return Err(format!(
+                "expected {} weights, found {}",
+                num_weights(&layers),
+                weights.len()
+            ));
+        }
+        Ok(Network { layers, weights })
+    }
+}
+
+// which way a step goes on a board that wraps, taking whichever way round is shorter
+fn wrapped(delta: i32, size: i32) -> i32 {
+    let delta = delta.rem_euclid(size);
+    if delta * 2 > size {
+        delta - size
+    } else {
+        delta
+    }
+}
+
+// everything the network gets to look at, from board.me's point of view
+pub fn sense(board: &BoardView, inputs: &mut Vec<f32>) {
+    let rays = SENSES.len(board.width, board.height);
+    inputs.clear();
+    inputs.resize(rays + COMPASS_INPUTS, 0.);
+    SENSES.write(board, &mut inputs[..rays]);
+
+    let me = board.me();
+    let heading = me.head_direction;
+    let nearest = board
+        .apples
+        .iter()
+        .map(|apple| Vector2D {
+            x: wrapped(apple.x - me.head.x, board.width),
+            y: wrapped(apple.y - me.head.y, board.height),
+        })
+        .min_by_key(|delta| delta.x.abs() + delta.y.abs());
+    if let Some(delta) = nearest {
+        let scale = board.width.max(board.height) as f32;
+        // see env::relative_square for which way right is
+        let forward = delta.x * heading.x + delta.y * heading.y;
+        let right = delta.x * -heading.y + delta.y * heading.x;
+        inputs[rays] = forward as f32 / scale;
+        inputs[rays + 1] = right as f32 / scale;
+    }
+}
+
+pub struct NeuralBot {
+    network: Network,
+    // kept between moves so we're not allocating all the time
+    inputs: Vec<f32>,
+}
+
+impl NeuralBot {
+    pub fn new(network: Network) -> NeuralBot {
+        NeuralBot {
+            network,
+            inputs: vec![],
+        }
+    }
+}
+
+impl SnakeController for NeuralBot {
+    fn name(&self) -> &str {
+        "neural"
+    }
+
+    fn next_command(&mut self, board: &BoardView) -> Command {
+        sense(board, &mut self.inputs);
+        let outputs = self.network.forward(&self.inputs);
+        let choice = outputs
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map_or(1, |(index, _)| index);
+
+        let heading = board.me().head_direction;
+        match choice {
+            0 => Command::towards(Vector2D {
+                x: heading.y,
+                y: -heading.x,
+            }),
+            2 => Command::towards(Vector2D {
+                x: -heading.y,
+                y: heading.x,
+            }),
+            _ => Command::Continue,
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use web_sys::WebSocket;
+
+use super::lockstep::Lockstep;
+use super::net::{Message, Netcode};
+use super::rollback::Rollback;
+use super::simulation::Simulation;
+
+// whatever's keeping the game in play in sync with everyone else's
+pub enum Session {
+    Lockstep(Lockstep),
+    Rollback(Rollback),
+}
+
+impl Session {
+    pub fn receive(&mut self, message: &Message) {
+        match self {
+            Session::Lockstep(lockstep) => lockstep.receive(message),
+            Session::Rollback(rollback) => rollback.receive(message),
+        }
+    }
+
+    pub fn desync(&self) -> Option<u64> {
+        match self {
+            Session::Lockstep(lockstep) => lockstep.hashes.desync,
+            Session::Rollback(rollback) => rollback.hashes.desync,
+        }
+    }
+
+    // with rollback, the end of the game might still be a wrong guess
+    pub fn is_settled(&self, sim: &Simulation) -> bool {
+        match self {
+            Session::Lockstep(_) => true,
+            Session::Rollback(rollback) => rollback.is_settled(sim),
+        }
+    }
+}
+
+// the browser's end of a networked game: the connection to the relay server, our seat in the
+// room, and once a game's going, the session keeping it in sync with everyone else's
+pub struct Online {
+    pub url: String,
+    pub room: String,
+    // how the games we host are kept in sync
+    pub netcode: Netcode,
+    socket: Option<WebSocket>,
+    // our slot, and the token that gets it back if the connection drops
+    pub seat: Option<(usize, u64)>,
+    // who's connected, by slot
+    pub present: Vec<bool>,
+    pub session: Option<Session>,
+    // how many updates in a row we've spent waiting on somebody else's commands
+    pub stalled_updates: u32,
+    // the last thing the server turned us down for
+    pub error: Option<String>,
+}
+
+impl Online {
+    pub fn new(url: &str, room: &str, netcode: Netcode) -> Online {
+        Online {
+            url: url.to_string(),
+            room: room.to_string(),
+            netcode,
+            socket: None,
+            seat: None,
+            present: vec![],
+            session: None,
+            stalled_updates: 0,
+            error: None,
+        }
+    }
+
+    pub fn set_socket(&mut self, socket: Option<WebSocket>) {
+        self.socket = socket;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.socket
+            .as_ref()
+            .is_some_and(|socket| socket.ready_state() == WebSocket::OPEN)
+    }
+
+    pub fn slot(&self) -> Option<usize> {
+        self.seat.map(|(slot, _)| slot)
+    }
+
+    // the first player gets to pick the settings and start every game
+    pub fn is_host(&self) -> bool {
+        self.slot() == Some(0)
+    }
+
+    pub fn is_everyone_here(&self) -> bool {
+        !self.present.is_empty() && self.present.iter().all(|present| *present)
+    }
+
+    // what to say once connected: either asking for a seat, or for ours back
+    pub fn hello(&self) -> Message {
+        match self.seat {
+            Some((slot, token)) => Message::Rejoin {
+                room: self.room.clone(),
+                slot,
+                token,
+            },
+            None => Message::Join {
+                room: self.room.clone(),
+            },
+        }
+    }
+
+    // anything sent while the connection's down is lost, but the server fills us back in on
+    // everything when we rejoin
+    pub fn send(&self, message: &Message) {
+        if !self.is_connected() {
+            return;
+        }
+        if let Some(socket) = &self.socket {
+            if let Err(err) = socket.send_with_str(&message.to_text()) {
+                log::warn!("couldn't send to {}: {:?}", self.url, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::render::{self, Align, Renderer};
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+// a 2D affine transform, [a, b, c, d, e, f] the same way the canvas has it
+type Transform = [f64; 6];
+const IDENTITY: Transform = [1., 0., 0., 1., 0., 0.];
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    transform: Transform,
+    alpha: f64,
+}
+
+// A software renderer, for drawing the game without a browser. Shapes are filled wherever they
+// cover the middle of a pixel, with no antialiasing, which keeps the pictures the same
+// everywhere and is close enough to the canvas at the sizes the game's drawn at
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    // RGB, a row at a time from the top
+    pub pixels: Vec<u8>,
+    state: State,
+    saved: Vec<State>,
+}
+
+impl Pixmap {
+    pub fn new(width: u32, height: u32) -> Pixmap {
+        Pixmap {
+            width,
+            height,
+            pixels: vec![0; (width * height * 3) as usize],
+            state: State {
+                transform: IDENTITY,
+                alpha: 1.,
+            },
+            saved: vec![],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = ((y * self.width + x) * 3) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+        ]
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.state.transform;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    // fills a polygon given in pixels, counting the pixels whose middles are inside it
+    fn fill(&mut self, points: &[(f64, f64)], color: &str) {
+        let rgb = match render::parse_color(color) {
+            Some(rgb) => rgb,
+            None => return,
+        };
+        let alpha = self.state.alpha.clamp(0., 1.);
+        let top = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let bottom = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let first_row = (top - 0.5).ceil().max(0.) as u32;
+        let last_row = ((bottom - 0.5).ceil().min(self.height as f64)).max(0.) as u32;
+
+        let mut crossings = vec![];
+        for row in first_row..last_row {
+            let y = row as f64 + 0.5;
+            crossings.clear();
+            for (index, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(index + 1) % points.len()];
+                // counts each edge from its top end but not its bottom, so corners aren't
+                // counted twice
+                if (y1 <= y && y < y2) || (y2 <= y && y < y1) {
+                    crossings.push(x1 + (y - y1) / (y2 - y1) * (x2 - x1));
+                }
+            }
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil().max(0.) as u32;
+                let end = ((span[1] - 0.5).ceil().min(self.width as f64)).max(0.) as u32;
+                for column in start..end {
+                    self.blend(column, row, rgb, alpha);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: u32, y: u32, rgb: [u8; 3], alpha: f64) {
+        let index = ((y * self.width + x) * 3) as usize;
+        for (channel, &value) in rgb.iter().enumerate() {
+            let old = self.pixels[index + channel] as f64;
+            self.pixels[index + channel] = (old + (value as f64 - old) * alpha).round() as u8;
+        }
+    }
+
+    // a line as the four corners of the rectangle it covers
+    fn line_polygon(from: (f64, f64), to: (f64, f64), width: f64) -> Option<[(f64, f64); 4]> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0. {
+            return None;
+        }
+        let (nx, ny) = (-dy / length * width / 2., dx / length * width / 2.);
+        Some([
+            (from.0 + nx, from.1 + ny),
+            (to.0 + nx, to.1 + ny),
+            (to.0 - nx, to.1 - ny),
+            (from.0 - nx, from.1 - ny),
+        ])
+    }
+}
+
+impl Renderer for Pixmap {
+    fn save(&mut self) {
+        self.saved.push(self.state);
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+        }
+    }
+
+    fn set_alpha(&mut self, alpha: f64) {
+        self.state.alpha = alpha;
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        let [a, b, c, d, e, f] = self.state.transform;
+        self.state.transform = [a, b, c, d, a * x + c * y + e, b * x + d * y + f];
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        let (sin, cos) = angle.sin_cos();
+        let [a, b, c, d, e, f] = self.state.transform;
+        self.state.transform = [
+            a * cos + c * sin,
+            b * cos + d * sin,
+            c * cos - a * sin,
+            d * cos - b * sin,
+            e,
+            f,
+        ];
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str) {
+        let corners = [
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ];
+        let points: Vec<(f64, f64)> = corners.iter().map(|&p| self.apply(p)).collect();
+        self.fill(&points, fill);
+        for (index, &corner) in corners.iter().enumerate() {
+            self.line(corner, corners[(index + 1) % 4], stroke, 1.);
+        }
+    }
+
+    fn circle(&mut self, x: f64, y: f64, radius: f64, fill: &str, stroke: &str) {
+        let points: Vec<(f64, f64)> = (0..CIRCLE_SEGMENTS)
+            .map(|index| {
+                let angle = index as f64 / CIRCLE_SEGMENTS as f64 * std::f64::consts::TAU;
+                (x + radius * angle.cos(), y + radius * angle.sin())
+            })
+            .collect();
+        let transformed: Vec<(f64, f64)> = points.iter().map(|&p| self.apply(p)).collect();
+        self.fill(&transformed, fill);
+        for (index, &point) in points.iter().enumerate() {
+            self.line(point, points[(index + 1) % points.len()], stroke, 1.);
+        }
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str, width: f64) {
+        if let Some(corners) = Pixmap::line_polygon(from, to, width) {
+            let points: Vec<(f64, f64)> = corners.iter().map(|&p| self.apply(p)).collect();
+            self.fill(&points, color);
+        }
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], fill: &str) {
+        let points: Vec<(f64, f64)> = points.iter().map(|&p| self.apply(p)).collect();
+        self.fill(&points, fill);
+    }
+
+    // there's no font to draw it with, so text is left out
+    fn text(&mut self, _text: &str, _x: f64, _y: f64, _size: f64, _align: Align, _color: &str) {}
+}
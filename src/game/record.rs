@@ -0,0 +1,224 @@
+use std::f64::consts::PI;
+
+use super::render::{Align, Renderer};
+
+// one call to a renderer
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    Save,
+    Restore,
+    Alpha(f64),
+    Translate(f64, f64),
+    Rotate(f64),
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        fill: String,
+        stroke: String,
+    },
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+        fill: String,
+        stroke: String,
+    },
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+        color: String,
+        width: f64,
+    },
+    Polygon {
+        points: Vec<(f64, f64)>,
+        fill: String,
+    },
+    Text {
+        text: String,
+        x: f64,
+        y: f64,
+        size: f64,
+        align: Align,
+        color: String,
+    },
+}
+
+impl DrawCall {
+    // one line, with the numbers rounded and angles in degrees so it's easy to read
+    pub fn to_text(&self) -> String {
+        match self {
+            DrawCall::Save => "save".to_string(),
+            DrawCall::Restore => "restore".to_string(),
+            DrawCall::Alpha(alpha) => format!("alpha {}", number(*alpha)),
+            DrawCall::Translate(x, y) => format!("translate {} {}", number(*x), number(*y)),
+            DrawCall::Rotate(angle) => format!("rotate {}", number(angle * 180. / PI)),
+            DrawCall::Rect {
+                x,
+                y,
+                width,
+                height,
+                fill,
+                stroke,
+            } => format!(
+                "rect {} {} {} {} {} {}",
+                number(*x),
+                number(*y),
+                number(*width),
+                number(*height),
+                fill,
+                stroke
+            ),
+            DrawCall::Circle {
+                x,
+                y,
+                radius,
+                fill,
+                stroke,
+            } => format!(
+                "circle {} {} {} {} {}",
+                number(*x),
+                number(*y),
+                number(*radius),
+                fill,
+                stroke
+            ),
+            DrawCall::Line {
+                from,
+                to,
+                color,
+                width,
+            } => format!(
+                "line {} {} {} {} {} {}",
+                number(from.0),
+                number(from.1),
+                number(to.0),
+                number(to.1),
+                color,
+                number(*width)
+            ),
+            DrawCall::Polygon { points, fill } => {
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", number(*x), number(*y)))
+                    .collect();
+                format!("polygon {} {}", fill, points.join(" "))
+            }
+            DrawCall::Text {
+                text,
+                x,
+                y,
+                size,
+                align,
+                color,
+            } => format!(
+                "text {} {} {} {} {} {:?}",
+                number(*x),
+                number(*y),
+                number(*size),
+                align.name(),
+                color,
+                text
+            ),
+        }
+    }
+}
+
+// to three places, and never -0
+fn number(value: f64) -> f64 {
+    (value * 1000.).round() / 1000. + 0.
+}
+
+// A renderer that writes down what it's asked to draw instead of drawing anything, so what the
+// drawing code does can be checked without a browser or comparing pictures
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    pub calls: Vec<DrawCall>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    // a call a line
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for call in self.calls.iter() {
+            text.push_str(&call.to_text());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Renderer for Recorder {
+    fn save(&mut self) {
+        self.calls.push(DrawCall::Save);
+    }
+
+    fn restore(&mut self) {
+        self.calls.push(DrawCall::Restore);
+    }
+
+    fn set_alpha(&mut self, alpha: f64) {
+        self.calls.push(DrawCall::Alpha(alpha));
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.calls.push(DrawCall::Translate(x, y));
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        self.calls.push(DrawCall::Rotate(angle));
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str) {
+        self.calls.push(DrawCall::Rect {
+            x,
+            y,
+            width,
+            height,
+            fill: fill.to_string(),
+            stroke: stroke.to_string(),
+        });
+    }
+
+    fn circle(&mut self, x: f64, y: f64, radius: f64, fill: &str, stroke: &str) {
+        self.calls.push(DrawCall::Circle {
+            x,
+            y,
+            radius,
+            fill: fill.to_string(),
+            stroke: stroke.to_string(),
+        });
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str, width: f64) {
+        self.calls.push(DrawCall::Line {
+            from,
+            to,
+            color: color.to_string(),
+            width,
+        });
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], fill: &str) {
+        self.calls.push(DrawCall::Polygon {
+            points: points.to_vec(),
+            fill: fill.to_string(),
+        });
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: Align, color: &str) {
+        self.calls.push(DrawCall::Text {
+            text: text.to_string(),
+            x,
+            y,
+            size,
+            align,
+            color: color.to_string(),
+        });
+    }
+}
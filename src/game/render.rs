@@ -0,0 +1,368 @@
+use std::f64::consts::PI;
+
+use super::simulation::{Apple, Settings, Simulation};
+use super::snake::{Snake, SnakeColors};
+use super::vector::Vector2D;
+
+pub const APPLE_COLOR: &str = "red";
+pub const WALL_COLOR: &str = "grey";
+pub const BACKGROUND_COLOR: &str = "black";
+pub const DEAD_SNAKE_ALPHA: f64 = 0.3;
+
+const APPLE_FADE_START: f64 = 0.5; // fraction of its lifetime an apple has left when it starts fading
+const MIN_APPLE_ALPHA: f64 = 0.2; // how faded an apple gets right before it expires
+
+const BANNER_ALPHA: f64 = 0.5;
+const TITLE_SIZE: f64 = 60.;
+const LINE_SIZE: f64 = 18.;
+const LINE_SPACING: f64 = 22.;
+
+// which end of a line of text is at the spot it's drawn at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    // what the canvas calls it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Align::Left => "left",
+            Align::Center => "center",
+            Align::Right => "right",
+        }
+    }
+}
+
+// Whatever the board gets drawn onto: the canvas in the browser, or an image or SVG file when
+// exporting replays. Colors are CSS colors, with "transparent" for no color at all, and alpha
+// replaces whatever was set before rather than multiplying, the same as the canvas's globalAlpha
+pub trait Renderer {
+    fn save(&mut self);
+    fn restore(&mut self);
+    fn set_alpha(&mut self, alpha: f64);
+    fn translate(&mut self, x: f64, y: f64);
+    // in radians, clockwise since y points down
+    fn rotate(&mut self, angle: f64);
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str);
+    fn circle(&mut self, x: f64, y: f64, radius: f64, fill: &str, stroke: &str);
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str, width: f64);
+    fn polygon(&mut self, points: &[(f64, f64)], fill: &str);
+    // a line of text in Arial, size pixels high and centered on y
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: Align, color: &str);
+}
+
+pub fn apple_alpha(apple: &Apple) -> f64 {
+    let freshness = apple.freshness();
+    if freshness >= APPLE_FADE_START {
+        1.
+    } else {
+        MIN_APPLE_ALPHA + (1. - MIN_APPLE_ALPHA) * freshness / APPLE_FADE_START
+    }
+}
+
+// how a board's drawn, with each square rect_size across
+#[derive(Debug, Clone, Copy)]
+pub struct Grid {
+    pub rect_size: f64,
+    pub num_squares_x: i32,
+    pub num_squares_y: i32,
+}
+
+impl Grid {
+    pub fn new(settings: &Settings, rect_size: f64) -> Grid {
+        Grid {
+            rect_size,
+            num_squares_x: settings.width,
+            num_squares_y: settings.height,
+        }
+    }
+
+    // in pixels
+    pub fn size(&self) -> (f64, f64) {
+        (
+            self.rect_size * self.num_squares_x as f64,
+            self.rect_size * self.num_squares_y as f64,
+        )
+    }
+
+    // everything on the board, on top of whatever's already there
+    pub fn draw_board(&self, renderer: &mut dyn Renderer, sim: &Simulation) {
+        self.draw_walls(renderer, &sim.settings.level.walls);
+        self.draw_apples(renderer, sim.apples.iter());
+        for snake in sim.snakes.iter() {
+            self.draw_snake(renderer, snake);
+        }
+    }
+
+    pub fn draw_walls(&self, renderer: &mut dyn Renderer, walls: &[Vector2D]) {
+        for wall in walls {
+            self.draw_rect(renderer, wall, WALL_COLOR);
+        }
+    }
+
+    // a line along every edge between squares, and around the outside
+    pub fn draw_grid_lines(&self, renderer: &mut dyn Renderer, color: &str) {
+        let (width, height) = self.size();
+        for x in 0..=self.num_squares_x {
+            let x = x as f64 * self.rect_size;
+            renderer.line((x, 0.), (x, height), color, 1.);
+        }
+        for y in 0..=self.num_squares_y {
+            let y = y as f64 * self.rect_size;
+            renderer.line((0., y), (width, y), color, 1.);
+        }
+    }
+
+    pub fn draw_snake(&self, renderer: &mut dyn Renderer, snake: &Snake) {
+        renderer.save();
+        if !snake.is_alive {
+            renderer.set_alpha(DEAD_SNAKE_ALPHA);
+        }
+
+        let colors: &SnakeColors = &snake.player.colors;
+        self.draw_body(renderer, snake.path.iter(), colors.body);
+        self.draw_rect(renderer, &snake.tail(), colors.tail);
+        self.draw_head(renderer, &snake.head(), &snake.head_direction, colors.head);
+        renderer.restore();
+    }
+
+    fn draw_body<'a, I>(&self, renderer: &mut dyn Renderer, rects: I, color: &str)
+    where
+        I: Iterator<Item = &'a Vector2D> + Clone,
+    {
+        // draw the body
+        for pos in rects.clone() {
+            self.draw_rect(renderer, pos, color);
+        }
+
+        // draw directions on the body
+        let mut draw_line = |p1: (f64, f64), p2: (f64, f64)| renderer.line(p1, p2, "white", 3.);
+        let rect_size = self.rect_size;
+        let mut previous_square: Option<Vector2D> = None;
+        for current_square in rects {
+            if let Some(previous_square) = previous_square {
+                let mut p1 = (
+                    rect_size * previous_square.x as f64 + rect_size / 2.,
+                    rect_size * previous_square.y as f64 + rect_size / 2.,
+                );
+                let mut p2 = (
+                    rect_size * current_square.x as f64 + rect_size / 2.,
+                    rect_size * current_square.y as f64 + rect_size / 2.,
+                );
+
+                // falls off right side
+                if previous_square.x == self.num_squares_x - 1 && current_square.x == 0 {
+                    let oldx = p2.0;
+                    p2.0 = p1.0 + rect_size;
+                    draw_line(p1, p2);
+                    p2.0 = oldx;
+                    p1.0 = oldx - rect_size;
+                    draw_line(p1, p2);
+                // falls off left side
+                } else if previous_square.x == 0 && current_square.x == self.num_squares_x - 1 {
+                    // swap x's
+                    std::mem::swap(&mut p1.0, &mut p2.0);
+                    let oldx = p2.0;
+
+                    p2.0 = p1.0 + rect_size;
+                    draw_line(p1, p2);
+                    p2.0 = oldx;
+                    p1.0 = oldx - rect_size;
+                    draw_line(p1, p2);
+                // falls off top
+                } else if previous_square.y == self.num_squares_y - 1 && current_square.y == 0 {
+                    let oldy = p2.1;
+                    p2.1 = p1.1 + rect_size;
+                    draw_line(p1, p2);
+                    p2.1 = oldy;
+                    p1.1 = oldy - rect_size;
+                    draw_line(p1, p2);
+                // falls off bottom
+                } else if previous_square.y == 0 && current_square.y == self.num_squares_y - 1 {
+                    // swap y's
+                    std::mem::swap(&mut p1.1, &mut p2.1);
+                    let oldy = p2.1;
+
+                    p2.1 = p1.1 + rect_size;
+                    draw_line(p1, p2);
+                    p2.1 = oldy;
+                    p1.1 = oldy - rect_size;
+                    draw_line(p1, p2);
+                } else {
+                    draw_line(p1, p2);
+                }
+            }
+            previous_square = Some(*current_square);
+        }
+    }
+
+    pub fn draw_rect(&self, renderer: &mut dyn Renderer, rect: &Vector2D, color: &str) {
+        renderer.rect(
+            self.rect_size * rect.x as f64,
+            self.rect_size * rect.y as f64,
+            self.rect_size,
+            self.rect_size,
+            color,
+            "black",
+        );
+    }
+
+    fn draw_head(
+        &self,
+        renderer: &mut dyn Renderer,
+        rect: &Vector2D,
+        direction: &Vector2D,
+        color: &str,
+    ) {
+        let rect_size = self.rect_size;
+        self.draw_rect(renderer, rect, color);
+
+        renderer.save();
+        renderer.translate(
+            rect_size * rect.x as f64 + rect_size / 2.,
+            rect_size * rect.y as f64 + rect_size / 2.,
+        );
+        renderer.rotate(get_angle(direction) * PI / 180.);
+        renderer.translate(-rect_size / 2., -rect_size / 2.);
+
+        let x_buffer = -4.;
+        let y_buffer = -4.;
+        renderer.polygon(
+            &[
+                (-x_buffer, 0.),
+                (rect_size + x_buffer, 0.),
+                (rect_size / 2., rect_size + y_buffer),
+            ],
+            "black",
+        );
+        renderer.restore();
+    }
+
+    pub fn draw_apples<'a, I>(&self, renderer: &mut dyn Renderer, apples: I)
+    where
+        I: Iterator<Item = &'a Apple>,
+    {
+        let radius = self.rect_size / 2.;
+        let border = 2.;
+        renderer.save();
+        for apple in apples {
+            let pos = &apple.pos;
+            renderer.set_alpha(apple_alpha(apple));
+            renderer.circle(
+                self.rect_size * pos.x as f64 + radius,
+                self.rect_size * pos.y as f64 + radius,
+                radius - border,
+                APPLE_COLOR,
+                "black",
+            );
+        }
+        renderer.restore();
+    }
+}
+
+fn get_angle(direction: &Vector2D) -> f64 {
+    match direction {
+        Vector2D { x: 1, y: 0 } => 90.,
+        Vector2D { x: -1, y: 0 } => 270.,
+        Vector2D { x: 0, y: 1 } => 180.,
+        Vector2D { x: 0, y: -1 } => 0.,
+        _ => 0.,
+    }
+}
+
+// a whole picture of the game, background and all, for exporting
+pub fn draw_frame(renderer: &mut dyn Renderer, sim: &Simulation, rect_size: f64) {
+    let grid = Grid::new(&sim.settings, rect_size);
+    let (width, height) = grid.size();
+    renderer.rect(0., 0., width, height, BACKGROUND_COLOR, BACKGROUND_COLOR);
+    grid.draw_board(renderer, sim);
+}
+
+// a see-through band across the middle of the picture, with a message on it
+pub fn draw_banner(renderer: &mut dyn Renderer, width: f64, height: f64, text: &str) {
+    let quarter_height = height / 4.;
+    draw_band(renderer, width, quarter_height, height - quarter_height);
+    renderer.text(
+        text,
+        width / 2.,
+        height / 2.,
+        TITLE_SIZE,
+        Align::Center,
+        "white",
+    );
+}
+
+// a banner with a title, and lines of text in their own colors under it. It grows to fit
+pub fn draw_results(
+    renderer: &mut dyn Renderer,
+    width: f64,
+    height: f64,
+    title: &str,
+    lines: &[(String, &str)],
+) {
+    let quarter_height = height / 4.;
+    let first_line = quarter_height + 95.;
+    let bottom = (height - quarter_height).max(first_line + LINE_SPACING * lines.len() as f64);
+    draw_band(renderer, width, quarter_height, bottom);
+    renderer.text(
+        title,
+        width / 2.,
+        quarter_height + 45.,
+        TITLE_SIZE,
+        Align::Center,
+        "white",
+    );
+    for (index, (line, color)) in lines.iter().enumerate() {
+        renderer.text(
+            line,
+            width / 2.,
+            first_line + LINE_SPACING * index as f64,
+            LINE_SIZE,
+            Align::Center,
+            color,
+        );
+    }
+}
+
+fn draw_band(renderer: &mut dyn Renderer, width: f64, top: f64, bottom: f64) {
+    renderer.save();
+    renderer.set_alpha(BANNER_ALPHA);
+    renderer.rect(0., top, width, bottom - top, "white", "transparent");
+    renderer.restore();
+}
+
+// the RGB values for the colors the game uses, or any #rgb or #rrggbb color
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<Vec<u8>>>()?;
+        return match *digits.as_slice() {
+            [r, g, b] => Some([r * 17, g * 17, b * 17]),
+            [r1, r2, g1, g2, b1, b2] => Some([r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2]),
+            _ => None,
+        };
+    }
+    Some(match color {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "grey" | "gray" => [128, 128, 128],
+        "green" => [0, 128, 0],
+        "lime" => [0, 255, 0],
+        "yellow" => [255, 255, 0],
+        "royalblue" => [65, 105, 225],
+        "lightskyblue" => [135, 206, 250],
+        "darkorchid" => [153, 50, 204],
+        "violet" => [238, 130, 238],
+        "darkorange" => [255, 140, 0],
+        "gold" => [255, 215, 0],
+        "tomato" => [255, 99, 71],
+        _ => return None,
+    })
+}
@@ -0,0 +1,229 @@
+use super::controller::Command;
+use super::level::Level;
+use super::rules::{CollisionRule, HeadToHeadRule};
+use super::simulation::{Settings, Simulation};
+use super::spawn;
+
+// A replay is plain text, like a level: the settings and seed a game started from, followed
+// by every command that went into it. Playing those commands back through the simulation
+// gives the exact same game.
+//
+//   # comments start with a hash
+//   board 30 20
+//   apples 5
+//   lifetime none        or a number of updates
+//   spawn 0              index into spawn::builtin_strategies
+//   rules block die both-die
+//   level arena
+//   wall 3 4             the level's walls and scripted apples, same as in a level file
+//   apple 10 5
+//   seed 1234
+//   snake hard ai        one line per snake, in order
+//   tick R.              one line per update, with a command per snake out of . U D L R S
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub settings: Settings,
+    pub seed: u64,
+    pub names: Vec<String>,
+    pub ticks: Vec<Vec<Command>>,
+}
+
+pub fn command_to_char(command: Command) -> char {
+    match command {
+        Command::Continue => '.',
+        Command::Up => 'U',
+        Command::Down => 'D',
+        Command::Left => 'L',
+        Command::Right => 'R',
+        Command::SwapHead => 'S',
+    }
+}
+
+pub fn char_to_command(c: char) -> Option<Command> {
+    match c {
+        '.' => Some(Command::Continue),
+        'U' => Some(Command::Up),
+        'D' => Some(Command::Down),
+        'L' => Some(Command::Left),
+        'R' => Some(Command::Right),
+        'S' => Some(Command::SwapHead),
+        _ => None,
+    }
+}
+
+impl Replay {
+    pub fn new(settings: Settings, seed: u64, names: Vec<String>) -> Replay {
+        Replay {
+            settings,
+            seed,
+            names,
+            ticks: vec![],
+        }
+    }
+
+    pub fn record(&mut self, commands: &[Command]) {
+        self.ticks.push(commands.to_vec());
+    }
+
+    // a fresh simulation, before any of the commands have gone into it
+    pub fn start(&self) -> Simulation {
+        Simulation::new(self.settings.clone(), self.seed)
+    }
+
+    // plays the whole thing back
+    pub fn play(&self) -> Simulation {
+        let mut sim = self.start();
+        for commands in self.ticks.iter() {
+            sim.step(commands);
+        }
+        sim
+    }
+
+    pub fn to_text(&self) -> String {
+        let settings = &self.settings;
+        let rules = &settings.rules;
+        let mut text = String::new();
+        text.push_str(&format!("board {} {}\n", settings.width, settings.height));
+        text.push_str(&format!("apples {}\n", settings.num_apples));
+        match settings.apple_lifetime {
+            Some(lifetime) => text.push_str(&format!("lifetime {}\n", lifetime)),
+            None => text.push_str("lifetime none\n"),
+        }
+        text.push_str(&format!("spawn {}\n", settings.spawn_strategy));
+        text.push_str(&format!(
+            "rules {} {} {}\n",
+            rules.self_collision.name(),
+            rules.snake_collision.name(),
+            rules.head_to_head.name()
+        ));
+        text.push_str(&format!("level {}\n", settings.level.name));
+        text.push_str(&settings.level.to_text());
+        text.push_str(&format!("seed {}\n", self.seed));
+        for name in self.names.iter() {
+            text.push_str(&format!("snake {}\n", name));
+        }
+        for commands in self.ticks.iter() {
+            let line: String = commands.iter().map(|c| command_to_char(*c)).collect();
+            text.push_str(&format!("tick {}\n", line));
+        }
+        text
+    }
+
+    // Replays come from anywhere, so anything that couldn't have come from a real game is turned
+    // down here rather than left to trip up whatever plays it back
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let replay = Replay::parse_header(text)?;
+        if replay.names.is_empty() {
+            return Err("there has to be at least one snake".to_string());
+        }
+        Ok(replay)
+    }
+
+    // the same, but happy with no snakes, for a game written out by state.rs that brings its own
+    pub fn parse_header(text: &str) -> Result<Replay, String> {
+        let mut settings = Settings::new(0, 0);
+        let mut level_text = String::new();
+        let mut seed = None;
+        let mut names = vec![];
+        let mut ticks = vec![];
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("line {}: {}", line_number + 1, message);
+            let (instruction, rest) = match line.find(' ') {
+                Some(index) => (&line[..index], line[index + 1..].trim()),
+                None => (line, ""),
+            };
+            let numbers = || {
+                rest.split_whitespace()
+                    .map(|word| word.parse::<i64>())
+                    .collect::<Result<Vec<i64>, _>>()
+                    .map_err(|err| error(&err.to_string()))
+            };
+
+            match instruction {
+                "board" => match numbers()?.as_slice() {
+                    // anything too big for an i32 is too big for the board anyway
+                    &[width, height] if width > 0 && height > 0 => {
+                        settings.width = width.min(i32::MAX as i64) as i32;
+                        settings.height = height.min(i32::MAX as i64) as i32;
+                    }
+                    _ => return Err(error("expected a width and a height")),
+                },
+                "apples" => match numbers()?.as_slice() {
+                    &[num_apples] if num_apples >= 0 => settings.num_apples = num_apples as usize,
+                    _ => return Err(error("expected a number of apples")),
+                },
+                "lifetime" => {
+                    settings.apple_lifetime = match rest {
+                        "none" => None,
+                        _ => Some(rest.parse().map_err(|_| error("expected a lifetime"))?),
+                    }
+                }
+                "spawn" => {
+                    settings.spawn_strategy = rest
+                        .parse()
+                        .map_err(|_| error("expected a spawn strategy"))?
+                }
+                "rules" => {
+                    let words: Vec<&str> = rest.split_whitespace().collect();
+                    match words.as_slice() {
+                        &[self_collision, snake_collision, head_to_head] => {
+                            settings.rules.self_collision =
+                                CollisionRule::parse(self_collision).map_err(|e| error(&e))?;
+                            settings.rules.snake_collision =
+                                CollisionRule::parse(snake_collision).map_err(|e| error(&e))?;
+                            settings.rules.head_to_head =
+                                HeadToHeadRule::parse(head_to_head).map_err(|e| error(&e))?;
+                        }
+                        _ => return Err(error("expected three rules")),
+                    }
+                }
+                "level" => settings.level.name = rest.to_string(),
+                "wall" | "apple" => {
+                    level_text.push_str(line);
+                    level_text.push('\n');
+                }
+                "seed" => seed = Some(rest.parse().map_err(|_| error("expected a seed"))?),
+                "snake" => names.push(rest.to_string()),
+                "tick" => {
+                    let commands = rest
+                        .chars()
+                        .map(char_to_command)
+                        .collect::<Option<Vec<Command>>>()
+                        .ok_or_else(|| error("unknown command"))?;
+                    if commands.len() != names.len() {
+                        return Err(error("expected one command per snake"));
+                    }
+                    ticks.push(commands);
+                }
+                _ => return Err(error(&format!("don't know what to do with \"{}\"", line))),
+            }
+        }
+
+        if settings.width == 0 {
+            return Err("missing board size".to_string());
+        }
+        let level_name = settings.level.name.clone();
+        settings.level = Level::parse(&level_name, &level_text)?;
+        settings.num_snakes = names.len();
+        settings.check()?;
+        if settings.spawn_strategy >= spawn::builtin_strategies(&settings.level).len() {
+            return Err(format!(
+                "unknown spawn strategy {}",
+                settings.spawn_strategy
+            ));
+        }
+
+        Ok(Replay {
+            settings,
+            seed: seed.ok_or_else(|| "missing seed".to_string())?,
+            names,
+            ticks,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+use super::simulation::{Moment, Simulation};
+
+pub const DEFAULT_REWINDS: u32 = 3; // times a game can be rewound
+pub const DEFAULT_PENALTY: u32 = 3; // points every rewind costs
+const MAX_MOMENTS: usize = 100; // how many updates back a rewind can go
+
+// The last few updates of a game, so that after dying you can go back and try again. Rewinding
+// is scrubbing back one update at a time for as long as the key's held, and letting go carries
+// on from there. Every game only gets so many, and each one costs points
+pub struct Rewind {
+    moments: VecDeque<Moment>,
+    pub rewinds_per_game: u32,
+    pub rewinds_left: u32,
+    pub penalty: u32,
+    is_scrubbing: bool,
+}
+
+impl Rewind {
+    pub fn new(rewinds_per_game: u32, penalty: u32) -> Rewind {
+        Rewind {
+            moments: VecDeque::with_capacity(MAX_MOMENTS),
+            rewinds_per_game,
+            rewinds_left: rewinds_per_game,
+            penalty,
+            is_scrubbing: false,
+        }
+    }
+
+    // for a new game
+    pub fn reset(&mut self) {
+        self.moments.clear();
+        self.rewinds_left = self.rewinds_per_game;
+        self.is_scrubbing = false;
+    }
+
+    // call before every update, with the game as it was before it
+    pub fn record(&mut self, sim: &Simulation) {
+        if self.moments.len() == MAX_MOMENTS {
+            self.moments.pop_front();
+        }
+        self.moments.push_back(sim.moment());
+    }
+
+    pub fn can_rewind(&self) -> bool {
+        self.rewinds_left > 0 && !self.moments.is_empty()
+    }
+
+    pub fn is_scrubbing(&self) -> bool {
+        self.is_scrubbing
+    }
+
+    // starts going back, straight away by one update. Returns false if there's nothing left to
+    // rewind with
+    pub fn start(&mut self, sim: &mut Simulation) -> bool {
+        if self.is_scrubbing || !self.can_rewind() {
+            return false;
+        }
+        self.is_scrubbing = true;
+        self.step_back(sim);
+        true
+    }
+
+    // goes back another update, unless we're already as far back as we can go
+    pub fn step_back(&mut self, sim: &mut Simulation) {
+        if let Some(moment) = self.moments.pop_back() {
+            sim.restore(&moment);
+        }
+    }
+
+    // stops going back. Returns whether that used up a rewind, in which case the penalty's due
+    pub fn release(&mut self) -> bool {
+        if !self.is_scrubbing {
+            return false;
+        }
+        self.is_scrubbing = false;
+        self.rewinds_left -= 1;
+        true
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+
+use super::controller::Command;
+use super::lockstep::HashLog;
+use super::net::Message;
+use super::simulation::Simulation;
+
+pub const DEFAULT_INPUT_DELAY: u64 = 1; // how many updates ahead our own commands are scheduled
+pub const MAX_PREDICTION: u64 = 8; // how far we'll guess ahead of the commands we actually have
+const SNAPSHOTS: usize = MAX_PREDICTION as usize + 1; // enough to go back to any unsettled tick
+
+// how much guessing wrong has cost us
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RollbackStats {
+    // how many times we've had to go back
+    pub rollbacks: u64,
+    // how many ticks we've played over again doing it
+    pub resimulated: u64,
+    // how many updates we've sat out because we'd guessed as far ahead as we're allowed
+    pub stalls: u64,
+}
+
+// Keeps a networked game in step without waiting on anyone. Our own commands are played straight
+// away, and anyone else's we don't have yet are guessed at (they usually keep doing the same). When
+// their real commands arrive and the guess was wrong, the game goes back to a snapshot from before
+// that tick and plays forward again with the right ones, so a slow connection only shows up as
+// the other snakes occasionally jumping.
+//
+// A tick is confirmed once everyone's real commands for it are in, and it can't change after
+// that. Only confirmed ticks get hashed and compared, and we stop and wait if we'd be guessing
+// more than MAX_PREDICTION ticks ahead of them.
+pub struct Rollback {
+    pub me: usize,
+    pub num_players: usize,
+    pub input_delay: u64,
+    // everyone's real commands for ticks that aren't confirmed yet, as they arrive
+    inputs: BTreeMap<u64, Vec<Option<Command>>>,
+    // what each played but unconfirmed tick was played with, guesses included
+    played: BTreeMap<u64, Vec<Command>>,
+    // the game as it was before each of those ticks, by tick
+    snapshots: Vec<Option<Simulation>>,
+    // the earliest tick we guessed wrong about
+    rollback_to: Option<u64>,
+    // every tick before this one is settled for good
+    pub confirmed: u64,
+    // everyone's commands for every confirmed tick, which is all it takes to replay the game
+    pub history: Vec<Vec<Command>>,
+    // our own commands that somebody might still be missing
+    local: BTreeMap<u64, Command>,
+    // the first tick we haven't sent our own command for
+    next_input: u64,
+    // the first tick each player doesn't have everyone's commands for, as far as we know
+    acks: Vec<u64>,
+    pub hashes: HashLog,
+    // hashes waiting to go out with the next messages
+    outgoing: Vec<Message>,
+    pub stats: RollbackStats,
+}
+
+impl Rollback {
+    pub fn new(me: usize, num_players: usize, input_delay: u64) -> Rollback {
+        let mut rollback = Rollback {
+            me,
+            num_players,
+            input_delay,
+            inputs: BTreeMap::new(),
+            played: BTreeMap::new(),
+            snapshots: vec![None; SNAPSHOTS],
+            rollback_to: None,
+            confirmed: 0,
+            history: vec![],
+            local: BTreeMap::new(),
+            next_input: input_delay,
+            acks: vec![0; num_players],
+            hashes: HashLog::new(me),
+            outgoing: vec![],
+            stats: RollbackStats::default(),
+        };
+        // nobody's had a chance to say anything about the first few ticks, so they're free
+        for tick in 0..input_delay {
+            rollback
+                .inputs
+                .insert(tick, vec![Some(Command::Continue); num_players]);
+        }
+        rollback
+    }
+
+    // whether there's a tick waiting on our command, as of having played tick ticks
+    pub fn wants_input(&self, tick: u64) -> bool {
+        self.next_input <= tick + self.input_delay
+    }
+
+    // schedules our command. If we've fallen behind, the ticks we skipped just keep going the
+    // way they were
+    pub fn add_local(&mut self, tick: u64, command: Command) {
+        let last = tick + self.input_delay;
+        while self.next_input <= last {
+            let command = if self.next_input == last {
+                command
+            } else {
+                Command::Continue
+            };
+            self.set_input(self.me, self.next_input, command);
+            self.local.insert(self.next_input, command);
+            self.next_input += 1;
+        }
+    }
+
+    // what to send everyone: all our commands that somebody hasn't confirmed yet, since some
+    // might have gone missing, and any hashes that are due
+    pub fn messages(&mut self) -> Vec<Message> {
+        let me = self.me;
+        let oldest_ack = (0..self.num_players)
+            .filter(|slot| *slot != me)
+            .map(|slot| self.acks[slot])
+            .min()
+            .unwrap_or(self.next_input);
+        self.local = self.local.split_off(&oldest_ack);
+
+        let mut messages = vec![];
+        if let Some(first_tick) = self.local.keys().next().copied() {
+            messages.push(Message::Inputs {
+                slot: me,
+                ack: self.confirmed,
+                first_tick,
+                commands: self.local.values().copied().collect(),
+            });
+        }
+        messages.append(&mut self.outgoing);
+        messages
+    }
+
+    // takes in the messages that matter to the game, and ignores the rest
+    pub fn receive(&mut self, message: &Message) {
+        match message {
+            Message::Inputs {
+                slot,
+                ack,
+                first_tick,
+                commands,
+            } if *slot < self.num_players => {
+                self.acks[*slot] = self.acks[*slot].max(*ack);
+                for (tick, command) in (*first_tick..).zip(commands.iter()) {
+                    self.set_input(*slot, tick, *command);
+                    // after rejoining, the server reminds us what we'd already sent
+                    if *slot == self.me && tick >= self.next_input {
+                        self.local.insert(tick, *command);
+                        self.next_input = tick + 1;
+                    }
+                }
+            }
+            Message::Hash { .. } => self.hashes.receive(message),
+            _ => {}
+        }
+    }
+
+    fn set_input(&mut self, slot: usize, tick: u64, command: Command) {
+        if tick < self.confirmed {
+            return;
+        }
+        let num_players = self.num_players;
+        let commands = self
+            .inputs
+            .entry(tick)
+            .or_insert_with(|| vec![None; num_players]);
+        if commands[slot].is_some() {
+            return;
+        }
+        commands[slot] = Some(command);
+
+        let guessed_wrong = self
+            .played
+            .get(&tick)
+            .is_some_and(|played| played[slot] != command);
+        if guessed_wrong && self.rollback_to.is_none_or(|earliest| tick < earliest) {
+            self.rollback_to = Some(tick);
+        }
+    }
+
+    // moves sim on a tick, after putting right anything we guessed wrong. Returns false if we
+    // couldn't, because we're too far ahead of what everyone's actually done, or it's over
+    pub fn advance(&mut self, sim: &mut Simulation) -> bool {
+        self.settle(sim);
+        if sim.is_game_over {
+            return false;
+        }
+        if sim.tick >= self.confirmed + MAX_PREDICTION {
+            self.stats.stalls += 1;
+            return false;
+        }
+
+        self.play(sim);
+        self.confirm(sim);
+        // after rejoining, or if we've fallen behind everyone else, play the backlog straight
+        // away, but hold on to a few ticks so we're not waiting on the next update
+        while self.confirmed == sim.tick
+            && !sim.is_game_over
+            && self.ready_ticks(sim.tick) > self.input_delay + 1
+        {
+            self.play(sim);
+            self.confirm(sim);
+        }
+        true
+    }
+
+    // puts right anything we guessed wrong, and confirms whatever we can, without moving on
+    pub fn settle(&mut self, sim: &mut Simulation) {
+        self.resimulate(sim);
+        self.confirm(sim);
+    }
+
+    // whether sim has nothing left that could still change
+    pub fn is_settled(&self, sim: &Simulation) -> bool {
+        self.confirmed == sim.tick
+    }
+
+    // the game as of the last confirmed tick, given where sim has got to
+    pub fn confirmed_state<'a>(&'a self, sim: &'a Simulation) -> &'a Simulation {
+        if self.confirmed == sim.tick {
+            sim
+        } else {
+            self.snapshot(self.confirmed)
+        }
+    }
+
+    // how many ticks in a row, starting at tick, we have every command for
+    fn ready_ticks(&self, tick: u64) -> u64 {
+        let mut ready = 0;
+        while self.known_commands(tick + ready).is_some() {
+            ready += 1;
+        }
+        ready
+    }
+
+    fn known_commands(&self, tick: u64) -> Option<Vec<Command>> {
+        self.inputs.get(&tick)?.iter().copied().collect()
+    }
+
+    fn snapshot(&self, tick: u64) -> &Simulation {
+        self.snapshots[tick as usize % SNAPSHOTS]
+            .as_ref()
+            .expect("no snapshot of an unconfirmed tick")
+    }
+
+    // goes back to before the first tick we got wrong, and plays back up to where we were
+    fn resimulate(&mut self, sim: &mut Simulation) {
+        let tick = match self.rollback_to.take() {
+            Some(tick) => tick,
+            None => return,
+        };
+        let target = sim.tick;
+        *sim = self.snapshot(tick).clone();
+        self.stats.rollbacks += 1;
+        while sim.tick < target && !sim.is_game_over {
+            self.play(sim);
+            self.stats.resimulated += 1;
+        }
+        // if the game's over sooner this time, the rest never happened
+        self.played.split_off(&sim.tick);
+    }
+
+    // plays one tick with what we know, and our best guess at the rest
+    fn play(&mut self, sim: &mut Simulation) {
+        let tick = sim.tick;
+        let commands: Vec<Command> = (0..self.num_players)
+            .map(|slot| {
+                self.inputs
+                    .get(&tick)
+                    .and_then(|commands| commands[slot])
+                    .unwrap_or_else(|| self.predict(slot, tick))
+            })
+            .collect();
+        self.snapshots[tick as usize % SNAPSHOTS] = Some(sim.clone());
+        sim.step(&commands);
+        self.played.insert(tick, commands);
+    }
+
+    // whoever we haven't heard from yet probably wants to keep going the way they last said,
+    // which bots in particular say on every tick
+    fn predict(&self, slot: usize, tick: u64) -> Command {
+        let last = self
+            .inputs
+            .range(..tick)
+            .rev()
+            .find_map(|(_, commands)| commands[slot])
+            .or_else(|| self.history.last().map(|commands| commands[slot]));
+        match last {
+            // doing that twice would just undo it
+            Some(Command::SwapHead) | None => Command::Continue,
+            Some(command) => command,
+        }
+    }
+
+    // settles every tick we've played that everyone's commands are in for
+    fn confirm(&mut self, sim: &Simulation) {
+        // any wrong guesses have to be put right first
+        if self.rollback_to.is_some() {
+            return;
+        }
+        while self.confirmed < sim.tick && self.known_commands(self.confirmed).is_some() {
+            let commands = self.played.remove(&self.confirmed).unwrap();
+            self.inputs.remove(&self.confirmed);
+            self.history.push(commands);
+            self.confirmed += 1;
+
+            let hash = self.confirmed_state(sim).state_hash();
+            let message = self.hashes.record(self.confirmed, hash);
+            self.outgoing.extend(message);
+        }
+    }
+}
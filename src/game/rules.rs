@@ -0,0 +1,86 @@
+// what happens when a snake runs into something
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionRule {
+    // the snake just doesn't move
+    Block,
+    Die,
+}
+
+// what happens when two snakes try to move into the same square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadToHeadRule {
+    Block,
+    BothDie,
+    // the shorter snake dies, or both of them if they're the same length
+    LongerWins,
+}
+
+impl CollisionRule {
+    pub fn next(self) -> CollisionRule {
+        match self {
+            CollisionRule::Block => CollisionRule::Die,
+            CollisionRule::Die => CollisionRule::Block,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CollisionRule::Block => "block",
+            CollisionRule::Die => "die",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<CollisionRule, String> {
+        match text {
+            "block" => Ok(CollisionRule::Block),
+            "die" => Ok(CollisionRule::Die),
+            _ => Err(format!("unknown collision rule: {}", text)),
+        }
+    }
+}
+
+impl HeadToHeadRule {
+    pub fn next(self) -> HeadToHeadRule {
+        match self {
+            HeadToHeadRule::Block => HeadToHeadRule::BothDie,
+            HeadToHeadRule::BothDie => HeadToHeadRule::LongerWins,
+            HeadToHeadRule::LongerWins => HeadToHeadRule::Block,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HeadToHeadRule::Block => "block",
+            HeadToHeadRule::BothDie => "both-die",
+            HeadToHeadRule::LongerWins => "longer-wins",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<HeadToHeadRule, String> {
+        match text {
+            "block" => Ok(HeadToHeadRule::Block),
+            "both-die" => Ok(HeadToHeadRule::BothDie),
+            "longer-wins" => Ok(HeadToHeadRule::LongerWins),
+            _ => Err(format!("unknown head to head rule: {}", text)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    // running into yourself or a wall
+    pub self_collision: CollisionRule,
+    // running into some other snake's body
+    pub snake_collision: CollisionRule,
+    pub head_to_head: HeadToHeadRule,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            self_collision: CollisionRule::Block,
+            snake_collision: CollisionRule::Die,
+            head_to_head: HeadToHeadRule::BothDie,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::{cell::Cell, fs, rc::Rc};
+use wasmi::{Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use super::controller::{BoardView, Command, SnakeController};
+use super::env::action_to_command;
+use super::tournament::Bot;
+use super::vector::Vector2D;
+
+pub const DEFAULT_FUEL: u64 = 1_000_000; // roughly how many instructions a bot gets per move
+pub const DEFAULT_MEMORY: usize = 16 * 1024 * 1024; // how many bytes of memory a bot can have
+pub const MAX_TABLE_ELEMENTS: u32 = 10_000; // how many functions a bot's table can point to
+
+// what each square of the board looks like to a bot
+const CELL_EMPTY: u8 = 0;
+const CELL_WALL: u8 = 1;
+const CELL_BODY: u8 = 2;
+const CELL_HEAD: u8 = 3;
+const CELL_OTHER_SNAKE: u8 = 4;
+const CELL_APPLE: u8 = 5;
+
+// Bots are WebAssembly modules that can't import anything, so all they can do is think. Each
+// one exports:
+//
+//   memory                                      its linear memory
+//   init(width: i32, height: i32) -> i32        called before the first move of every game, and
+//                                               returns where in memory the board should go
+//   next_move(head_x: i32, head_y: i32, direction: i32) -> i32
+//                                               called every move, after the board's been
+//                                               written to that spot
+//
+// The board is width * height bytes, row by row from the top left: 0 empty, 1 wall, 2 our body,
+// 3 our head, 4 some other snake, 5 apple. Directions are 0 up, 1 down, 2 left, 3 right, and
+// next_move answers with 0 keep going, 1 up, 2 down, 3 left, 4 right or 5 swap head.
+//
+// Every call gets the same amount of fuel, which stands in for time since it's used up one
+// instruction at a time. A bot that runs out, traps, or asks for more memory or a bigger table
+// than it's allowed forfeits the move and just keeps going the way it was.
+pub struct WasmModule {
+    name: String,
+    engine: Engine,
+    module: Module,
+    pub fuel: u64,
+    pub memory: usize,
+    // moves forfeited by every bot running this module
+    forfeits: Cell<u32>,
+}
+
+impl WasmModule {
+    // accepts both compiled modules and the text format
+    pub fn load(name: &str, bytes: &[u8]) -> Result<WasmModule, String> {
+        let wasm = wat::parse_bytes(bytes).map_err(|e| format!("{}: {}", name, e))?;
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm).map_err(|e| format!("{}: {}", name, e))?;
+        Ok(WasmModule {
+            name: name.to_string(),
+            engine,
+            module,
+            fuel: DEFAULT_FUEL,
+            memory: DEFAULT_MEMORY,
+            forfeits: Cell::new(0),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn forfeits(&self) -> u32 {
+        self.forfeits.get()
+    }
+
+    fn forfeit(&self) -> Command {
+        self.forfeits.set(self.forfeits.get() + 1);
+        Command::Continue
+    }
+}
+
+// a bot out of a .wasm or .wat file, ready for the tournament runner. The module comes back too
+// so you can see how many moves it forfeited
+pub fn load_bot(path: &str, fuel: u64, memory: usize) -> Result<(Bot, Rc<WasmModule>), String> {
+    let bytes = fs::read(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+    let mut module = WasmModule::load(path, &bytes)?;
+    module.fuel = fuel;
+    module.memory = memory;
+    let module = Rc::new(module);
+    let shared = module.clone();
+    let bot = Bot::new(
+        path,
+        Box::new(move || Box::new(WasmBot::new(shared.clone()))),
+    );
+    Ok((bot, module))
+}
+
+// the running instance, kept around for a whole game so bots can remember things between moves
+struct Running {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+    board_offset: usize,
+    board: Vec<u8>,
+}
+
+pub struct WasmBot {
+    module: Rc<WasmModule>,
+    running: Option<Running>,
+    // set once the bot can't even start, after which every move is forfeit
+    is_broken: bool,
+}
+
+impl WasmBot {
+    pub fn new(module: Rc<WasmModule>) -> WasmBot {
+        WasmBot {
+            module,
+            running: None,
+            is_broken: false,
+        }
+    }
+
+    fn start(&self, width: i32, height: i32) -> Result<Running, String> {
+        let module = &self.module;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(module.memory)
+            .table_elements(MAX_TABLE_ELEMENTS)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&module.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(module.fuel).map_err(|e| e.to_string())?;
+
+        // nothing to link, so modules that want to import anything fail right here
+        let linker = Linker::<StoreLimits>::new(&module.engine);
+        let instance = linker
+            .instantiate(&mut store, &module.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("doesn't export its memory")?;
+
+        store.set_fuel(module.fuel).map_err(|e| e.to_string())?;
+        let init = instance
+            .get_typed_func::<(i32, i32), i32>(&store, "init")
+            .map_err(|e| e.to_string())?;
+        let board_offset = init
+            .call(&mut store, (width, height))
+            .map_err(|e| e.to_string())?;
+
+        let board = vec![CELL_EMPTY; (width * height) as usize];
+        if board_offset < 0 || board_offset as usize + board.len() > memory.data(&store).len() {
+            return Err("asked for the board somewhere outside its memory".to_string());
+        }
+
+        Ok(Running {
+            store,
+            instance,
+            memory,
+            board_offset: board_offset as usize,
+            board,
+        })
+    }
+
+    fn ask(running: &mut Running, fuel: u64, board: &BoardView) -> Result<i32, String> {
+        let width = board.width;
+        let cell = |pos: &Vector2D| (pos.y * width + pos.x) as usize;
+        for square in running.board.iter_mut() {
+            *square = CELL_EMPTY;
+        }
+        for wall in board.walls.iter() {
+            running.board[cell(wall)] = CELL_WALL;
+        }
+        for apple in board.apples.iter() {
+            running.board[cell(apple)] = CELL_APPLE;
+        }
+        for (index, snake) in board.snakes.iter().enumerate() {
+            if !snake.is_alive {
+                continue;
+            }
+            let contents = if index == board.me {
+                CELL_BODY
+            } else {
+                CELL_OTHER_SNAKE
+            };
+            for pos in snake.path.iter() {
+                running.board[cell(pos)] = contents;
+            }
+        }
+        let me = board.me();
+        running.board[cell(&me.head)] = CELL_HEAD;
+
+        let store = &mut running.store;
+        running
+            .memory
+            .write(&mut *store, running.board_offset, &running.board)
+            .map_err(|e| e.to_string())?;
+        store.set_fuel(fuel).map_err(|e| e.to_string())?;
+        let next_move = running
+            .instance
+            .get_typed_func::<(i32, i32, i32), i32>(&*store, "next_move")
+            .map_err(|e| e.to_string())?;
+        let direction = match me.head_direction {
+            Vector2D { x: 0, y: -1 } => 0,
+            Vector2D { x: 0, y: 1 } => 1,
+            Vector2D { x: -1, y: 0 } => 2,
+            _ => 3,
+        };
+        next_move
+            .call(store, (me.head.x, me.head.y, direction))
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl SnakeController for WasmBot {
+    fn name(&self) -> &str {
+        &self.module.name
+    }
+
+    fn next_command(&mut self, board: &BoardView) -> Command {
+        if self.is_broken {
+            return self.module.forfeit();
+        }
+
+        if self.running.is_none() {
+            match self.start(board.width, board.height) {
+                Ok(running) => self.running = Some(running),
+                Err(err) => {
+                    log::warn!("{} couldn't start: {}", self.module.name, err);
+                    self.is_broken = true;
+                    return self.module.forfeit();
+                }
+            }
+        }
+
+        let fuel = self.module.fuel;
+        let running = self.running.as_mut().unwrap();
+        match WasmBot::ask(running, fuel, board) {
+            Ok(action) if (0..6).contains(&action) => action_to_command(action as usize),
+            Ok(action) => {
+                log::warn!("{} answered with {}", self.module.name, action);
+                self.module.forfeit()
+            }
+            Err(err) => {
+                log::warn!("{} forfeits the move: {}", self.module.name, err);
+                self.module.forfeit()
+            }
+        }
+    }
+}
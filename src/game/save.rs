@@ -0,0 +1,185 @@
+use super::ai::AI_NAMES;
+use super::replay::Replay;
+use super::rewind;
+use super::simulation::Simulation;
+use super::state;
+
+// the version saves are written in. Bump it whenever the format changes, add a migration from
+// the version before, and put a save from the new version in tests/saves
+pub const VERSION: u32 = 2;
+
+// Each one brings a save up a version, starting from version 1, so saves from any version can
+// be brought up to date one step at a time
+type Migration = fn(&str) -> Result<String, String>;
+const MIGRATIONS: [Migration; 1] = [from_bare_game];
+
+// A game put away to carry on with later, along with how it was being played:
+//
+//   version 2
+//   players 1            how many snakes are at the keyboard, with the computer on the rest
+//   rewinds 2            how many times it can still be rewound
+//   fair 1               whether it can still go on the leaderboard
+//   game                 then the game as it stands, the way state.rs writes it
+//   board 30 20
+//   ...
+//   replay               and the replay so far if there is one, the way replay.rs writes it
+//   board 30 20
+//   ...
+//
+// A save with no version line is version 1, which was just the game
+#[derive(Clone)]
+pub struct Save {
+    pub sim: Simulation,
+    pub replay: Option<Replay>,
+    pub num_players: usize,
+    pub rewinds_left: u32,
+    pub is_fair: bool,
+}
+
+impl Save {
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "version {}\nplayers {}\nrewinds {}\nfair {}\n",
+            VERSION, self.num_players, self.rewinds_left, self.is_fair as u8
+        );
+        text.push_str("game\n");
+        text.push_str(&state::to_text(&self.sim));
+        if let Some(replay) = &self.replay {
+            // anything after a rewind went back to never happened
+            let mut replay = replay.clone();
+            replay.ticks.truncate(self.sim.tick as usize);
+            text.push_str("replay\n");
+            text.push_str(&replay.to_text());
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Save, String> {
+        let mut version = version_of(text)?;
+        if version > VERSION {
+            return Err(format!(
+                "this save is version {}, from a newer version of the game",
+                version
+            ));
+        }
+        let mut text = text.to_string();
+        while version < VERSION {
+            text = MIGRATIONS[version as usize - 1](&text)
+                .map_err(|err| format!("couldn't bring version {} up to date: {}", version, err))?;
+            version += 1;
+        }
+
+        let mut header = vec![];
+        let mut game = String::new();
+        let mut replay: Option<String> = None;
+        let mut section = Section::Header;
+        for line in text.lines() {
+            match (section, line.trim()) {
+                (Section::Header, "game") => section = Section::Game,
+                (Section::Game, "replay") => {
+                    section = Section::Replay;
+                    replay = Some(String::new());
+                }
+                (Section::Header, _) => header.push(line),
+                (Section::Game, _) => {
+                    game.push_str(line);
+                    game.push('\n');
+                }
+                (Section::Replay, _) => {
+                    if let Some(replay) = replay.as_mut() {
+                        replay.push_str(line);
+                        replay.push('\n');
+                    }
+                }
+            }
+        }
+        if section == Section::Header {
+            return Err("the save has no game in it".to_string());
+        }
+
+        let mut num_players = None;
+        let mut rewinds_left = None;
+        let mut is_fair = None;
+        for line in header.iter() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = |word: &str| {
+                word.parse::<u32>()
+                    .map_err(|_| format!("expected a number in \"{}\"", line))
+            };
+            match words.as_slice() {
+                [] | ["version", _] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["players", count] => num_players = Some(number(count)?),
+                ["rewinds", count] => rewinds_left = Some(number(count)?),
+                ["fair", "0"] => is_fair = Some(false),
+                ["fair", "1"] => is_fair = Some(true),
+                _ => return Err(format!("don't know what to do with \"{}\"", line)),
+            }
+        }
+
+        let sim = state::parse(&game).map_err(|err| format!("in the game: {}", err))?;
+        let replay = replay
+            .map(|text| Replay::parse(&text).map_err(|err| format!("in the replay: {}", err)))
+            .transpose()?;
+        let num_players = num_players.ok_or("missing players")? as usize;
+        if num_players > sim.snakes.len() {
+            return Err(format!(
+                "{} players but only {} snakes",
+                num_players,
+                sim.snakes.len()
+            ));
+        }
+        // the computer takes the rest, and it only has so many names to go by
+        let num_computers = sim.snakes.len() - num_players;
+        if num_computers > AI_NAMES.len() {
+            return Err(format!(
+                "{} computer players but there can only be {}",
+                num_computers,
+                AI_NAMES.len()
+            ));
+        }
+        Ok(Save {
+            sim,
+            replay,
+            num_players,
+            rewinds_left: rewinds_left.ok_or("missing rewinds")?,
+            is_fair: is_fair.ok_or("missing fair")?,
+        })
+    }
+}
+
+// which part of a save is being read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Header,
+    Game,
+    Replay,
+}
+
+fn version_of(text: &str) -> Result<u32, String> {
+    let first = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'));
+    match first.and_then(|line| line.strip_prefix("version ")) {
+        Some(version) => match version.trim().parse() {
+            Ok(version) if version > 0 => Ok(version),
+            _ => Err(format!("unknown version \"{}\"", version)),
+        },
+        None => Ok(1),
+    }
+}
+
+// version 1 was the bare game, with everyone at the keyboard and all of the rewinds left
+fn from_bare_game(text: &str) -> Result<String, String> {
+    let num_snakes = text
+        .lines()
+        .filter(|line| line.split_whitespace().next() == Some("snake"))
+        .count();
+    Ok(format!(
+        "version 2\nplayers {}\nrewinds {}\nfair 0\ngame\n{}",
+        num_snakes,
+        rewind::DEFAULT_REWINDS,
+        text
+    ))
+}
@@ -0,0 +1,171 @@
+use super::leaderboard::{Entry, Period};
+use super::verify::Submission;
+
+const SHOWN_ENTRIES: usize = 5; // how many of the best scores fit on the game over screen
+
+// what a request to the leaderboard server was for, and which board it was about
+#[derive(Debug, Clone)]
+pub enum RequestKind {
+    Submit(String),
+    Top(String),
+}
+
+// something for Game to send, since the callbacks for the answer have to come from there
+pub struct Request {
+    pub kind: RequestKind,
+    pub method: &'static str,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+// the browser's end of the leaderboard (src/bin/snake_leaderboard.rs): it sends off every game
+// played alone once it's over, and keeps the best scores for that game's board to show
+pub struct Scoreboard {
+    pub url: String,
+    pub name: String,
+    pub period: Period,
+    // the board the last game was on, and its best scores once they've arrived
+    pub board: Option<String>,
+    pub top: Option<Vec<Entry>>,
+    // how the last game went down with the server
+    pub status: Option<String>,
+    requests: Vec<Request>,
+}
+
+fn encode(text: &str) -> String {
+    String::from(js_sys::encode_uri_component(text))
+}
+
+impl Scoreboard {
+    pub fn new(url: &str, name: &str) -> Scoreboard {
+        Scoreboard {
+            url: url.trim_end_matches('/').to_string(),
+            name: name.to_string(),
+            period: Period::Daily,
+            board: None,
+            top: None,
+            status: None,
+            requests: vec![],
+        }
+    }
+
+    // the best scores get fetched once the server's had its say on this one
+    pub fn submit(&mut self, board: String, submission: &Submission) {
+        self.start(&board);
+        self.status = Some("checking your score...".to_string());
+        self.requests.push(Request {
+            kind: RequestKind::Submit(board),
+            method: "POST",
+            url: format!("{}/submit?name={}", self.url, encode(&self.name)),
+            body: Some(submission.to_text()),
+        });
+    }
+
+    // for games that can't go on the leaderboard, but still have one worth seeing
+    pub fn show(&mut self, board: String) {
+        self.start(&board);
+        self.fetch_top();
+    }
+
+    fn start(&mut self, board: &str) {
+        self.board = Some(board.to_string());
+        self.top = None;
+        self.status = None;
+    }
+
+    pub fn next_period(&mut self) {
+        self.period = self.period.next();
+        self.top = None;
+        self.fetch_top();
+    }
+
+    fn fetch_top(&mut self) {
+        let board = match &self.board {
+            Some(board) => board.clone(),
+            None => return,
+        };
+        self.requests.push(Request {
+            url: format!(
+                "{}/top?board={}&period={}&limit={}",
+                self.url,
+                encode(&board),
+                self.period.name(),
+                SHOWN_ENTRIES
+            ),
+            kind: RequestKind::Top(board),
+            method: "GET",
+            body: None,
+        });
+    }
+
+    pub fn take_requests(&mut self) -> Vec<Request> {
+        self.requests.drain(..).collect()
+    }
+
+    // a status of 0 means the server couldn't be reached at all. Answers about a board we've
+    // since moved on from are ignored
+    pub fn receive(&mut self, kind: &RequestKind, status: u16, text: &str) {
+        let board = match kind {
+            RequestKind::Submit(board) | RequestKind::Top(board) => board,
+        };
+        if self.board.as_ref() != Some(board) {
+            return;
+        }
+
+        match kind {
+            RequestKind::Submit(_) => {
+                self.status = Some(match (status, text.strip_prefix("accepted ")) {
+                    (200, Some(rank)) => format!("your best is number {} of all time", rank),
+                    (0, _) => "couldn't reach the leaderboard".to_string(),
+                    _ => format!("not listed: {}", text.trim()),
+                });
+                self.fetch_top();
+            }
+            RequestKind::Top(_) => {
+                if status != 200 {
+                    log::warn!("couldn't get the leaderboard: {} {}", status, text);
+                    return;
+                }
+                let entries = text
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(Entry::parse)
+                    .collect::<Result<Vec<Entry>, String>>();
+                match entries {
+                    Ok(entries) => self.top = Some(entries),
+                    Err(err) => log::warn!("couldn't read the leaderboard: {}", err),
+                }
+            }
+        }
+    }
+
+    // what goes under the game over banner
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+        if let Some(status) = &self.status {
+            lines.push(status.clone());
+        }
+        let title = match self.period {
+            Period::Daily => "TODAY",
+            Period::Weekly => "THIS WEEK",
+            Period::AllTime => "ALL TIME",
+        };
+        lines.push(format!("{}    (b for more)", title));
+        match &self.top {
+            None => lines.push("...".to_string()),
+            Some(top) if top.is_empty() => lines.push("nobody yet".to_string()),
+            Some(top) => {
+                for (index, entry) in top.iter().enumerate() {
+                    lines.push(format!(
+                        "{}. {}    score {}    {} updates",
+                        index + 1,
+                        entry.name,
+                        entry.score,
+                        entry.ticks
+                    ));
+                }
+            }
+        }
+        lines
+    }
+}
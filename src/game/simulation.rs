@@ -0,0 +1,668 @@
+use rand::seq::SliceRandom;
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use std::{
+    cmp::{max, min},
+    collections::{HashSet, VecDeque},
+    convert::TryInto,
+    hash::{Hash, Hasher},
+};
+
+use super::controller::{BoardView, Command};
+use super::level::Level;
+use super::rules::{CollisionRule, HeadToHeadRule, Rules};
+use super::snake::{self, Snake};
+use super::spawn::{self, SpawnBoard, SpawnStrategy};
+use super::vector::Vector2D;
+
+pub const APPLE_LIFETIME: u32 = 150; // number of updates a timed apple sticks around for
+const MAX_APPLE_BONUS: u32 = 5; // extra points for eating a timed apple the moment it appears
+
+// FNV-1a, which unlike the standard library's hasher is guaranteed to give the same answer
+// everywhere, so two machines can compare games
+struct StateHasher(u64);
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+// how many squares across or down a board can go, so settings from outside can't ask for more
+// memory than there is
+pub const MAX_BOARD_SIZE: i32 = 200;
+
+// the one source of randomness in a game. It's seeded, so the same seed and the same commands
+// always play out the same way
+pub type GameRng = Pcg32;
+
+// A game's randomness keeps count of how much of it has been used, since there's no getting
+// the generator's insides back out. The seed and the count are enough to get back to the same
+// point. Everything is drawn 32 bits at a time, which gives exactly the same numbers as using
+// the generator directly
+#[derive(Clone)]
+struct CountingRng {
+    rng: GameRng,
+    seed: u64,
+    draws: u64,
+}
+
+impl CountingRng {
+    fn new(seed: u64) -> CountingRng {
+        CountingRng {
+            rng: GameRng::seed_from_u64(seed),
+            seed,
+            draws: 0,
+        }
+    }
+}
+
+// Pcg32's own
+const PCG_MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+impl CountingRng {
+    // Where the generator would be after drawing that many more numbers, worked out in a few
+    // dozen steps rather than drawing them all, since a game read in from outside can claim
+    // any number. Pcg32 doesn't let us at its state, so this rebuilds it from the seed: the
+    // state's a linear congruential generator, which can be jumped ahead by squaring
+    fn skip(&mut self, draws: u64) {
+        let seed = seed_bytes(self.seed);
+        let start = u64::from_le_bytes(seed[..8].try_into().unwrap());
+        let increment = u64::from_le_bytes(seed[8..].try_into().unwrap()) | 1;
+        self.draws = self.draws.wrapping_add(draws);
+
+        // from_seed adds the increment then takes a step, before the first number's drawn
+        let (mut multiplier, mut plus) = (1u64, 0u64);
+        let (mut step_multiplier, mut step_plus) = (PCG_MULTIPLIER, increment);
+        let mut steps = self.draws;
+        while steps > 0 {
+            if steps & 1 == 1 {
+                multiplier = multiplier.wrapping_mul(step_multiplier);
+                plus = plus.wrapping_mul(step_multiplier).wrapping_add(step_plus);
+            }
+            step_plus = step_multiplier.wrapping_add(1).wrapping_mul(step_plus);
+            step_multiplier = step_multiplier.wrapping_mul(step_multiplier);
+            steps >>= 1;
+        }
+        let before_last_step = multiplier
+            .wrapping_mul(start.wrapping_add(increment))
+            .wrapping_add(plus);
+
+        let mut seed = [0u8; 16];
+        seed[..8].copy_from_slice(&before_last_step.wrapping_sub(increment).to_le_bytes());
+        seed[8..].copy_from_slice(&increment.to_le_bytes());
+        self.rng = GameRng::from_seed(seed);
+    }
+}
+
+// what seed_from_u64 hands to from_seed, the same way rand does it
+fn seed_bytes(mut state: u64) -> [u8; 16] {
+    const INCREMENT: u64 = 11_634_580_027_462_260_723;
+    let mut seed = [0u8; 16];
+    for chunk in seed.chunks_mut(4) {
+        state = state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        chunk.copy_from_slice(&xorshifted.rotate_right(rotation).to_le_bytes());
+    }
+    seed
+}
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        let high = self.next_u32() as u64;
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Apple {
+    pub pos: Vector2D,
+    // how many updates this apple lasts before moving elsewhere. None means forever
+    pub lifetime: Option<u32>,
+    pub age: u32,
+}
+
+impl Apple {
+    fn new(pos: Vector2D, lifetime: Option<u32>) -> Apple {
+        Apple {
+            pos,
+            lifetime,
+            age: 0,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.lifetime.is_some_and(|lifetime| self.age >= lifetime)
+    }
+
+    // fraction of this apple's lifetime that's left, from 1.0 (fresh) to 0.0 (expired)
+    pub fn freshness(&self) -> f64 {
+        match self.lifetime {
+            Some(lifetime) if lifetime > 0 => 1. - min(self.age, lifetime) as f64 / lifetime as f64,
+            _ => 1.,
+        }
+    }
+
+    // the earlier you eat a timed apple the more it's worth
+    fn bonus(&self) -> u32 {
+        match self.lifetime {
+            Some(_) => (MAX_APPLE_BONUS as f64 * self.freshness()).round() as u32,
+            None => 0,
+        }
+    }
+}
+
+// what a snake does this update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Move {
+    Advance,
+    Stay,
+    Die,
+}
+
+impl From<CollisionRule> for Move {
+    fn from(rule: CollisionRule) -> Move {
+        match rule {
+            CollisionRule::Block => Move::Stay,
+            CollisionRule::Die => Move::Die,
+        }
+    }
+}
+
+enum ICellContents {
+    Empty,
+    Snake,
+    Apple,
+    Wall,
+}
+
+// everything it takes to set up a game
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub width: i32,
+    pub height: i32,
+    pub num_snakes: usize,
+    pub num_apples: usize,
+    pub apple_lifetime: Option<u32>,
+    // which of spawn::builtin_strategies places the apples
+    pub spawn_strategy: usize,
+    pub level: Level,
+    pub rules: Rules,
+}
+
+impl Settings {
+    pub fn new(width: i32, height: i32) -> Settings {
+        Settings {
+            width,
+            height,
+            num_snakes: 1,
+            num_apples: 5,
+            apple_lifetime: None,
+            spawn_strategy: 0,
+            level: Level::default(),
+            rules: Rules::default(),
+        }
+    }
+
+    // whether a game can be played with these, for settings that came from outside
+    pub fn check(&self) -> Result<(), String> {
+        let sizes = 1..=MAX_BOARD_SIZE;
+        if !sizes.contains(&self.width) || !sizes.contains(&self.height) {
+            return Err(format!(
+                "the board has to be from 1x1 up to {0}x{0}, not {1}x{2}",
+                MAX_BOARD_SIZE, self.width, self.height
+            ));
+        }
+        self.level.check_fits(self.width, self.height)?;
+        if self.num_snakes > snake::players().len() {
+            return Err(format!(
+                "there can't be more than {} snakes",
+                snake::players().len()
+            ));
+        }
+        // every snake needs a square of its own to start on
+        let walls: HashSet<&Vector2D> = self.level.walls.iter().collect();
+        let free_squares = (self.width * self.height) as usize - walls.len();
+        if free_squares < self.num_snakes {
+            return Err(format!(
+                "there's only room for {} snakes on that board, not {}",
+                free_squares, self.num_snakes
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, square: &Vector2D) -> bool {
+        (0..self.width).contains(&square.x) && (0..self.height).contains(&square.y)
+    }
+}
+
+// Everything about a game that changes as it's played, without the settings and level that
+// don't. Putting one back into the game it came from carries on exactly as it did the first time
+#[derive(Clone)]
+pub struct Moment {
+    snakes: Vec<Snake>,
+    apples: VecDeque<Apple>,
+    tick: u64,
+    is_game_over: bool,
+    did_win: bool,
+    // the scripted spawner remembers where it's up to
+    spawn_strategies: Vec<Box<dyn SpawnStrategy>>,
+    rng: CountingRng,
+}
+
+// the game itself, with no idea how it's being drawn or who's playing it. Cloning one gives a
+// snapshot that plays on exactly the same way
+#[derive(Clone)]
+pub struct Simulation {
+    pub settings: Settings,
+    pub snakes: Vec<Snake>,
+    pub apples: VecDeque<Apple>,
+    // how many updates have gone by
+    pub tick: u64,
+    pub is_game_over: bool,
+    pub did_win: bool,
+
+    spawn_strategies: Vec<Box<dyn SpawnStrategy>>,
+    rng: CountingRng,
+}
+
+impl Simulation {
+    pub fn new(settings: Settings, seed: u64) -> Simulation {
+        let mut sim = Simulation {
+            spawn_strategies: spawn::builtin_strategies(&settings.level),
+            settings,
+            snakes: vec![],
+            apples: VecDeque::new(),
+            tick: 0,
+            is_game_over: false,
+            did_win: false,
+            rng: CountingRng::new(seed),
+        };
+
+        // spread the snakes out along a diagonal, facing each other. With just one player
+        // that's the middle of the board
+        let num_snakes = sim.settings.num_snakes;
+        for (index, player) in snake::players().iter().take(num_snakes).enumerate() {
+            let step = index as i32 + 1;
+            let spot = Vector2D {
+                x: sim.settings.width * step / (num_snakes as i32 + 1),
+                y: sim.settings.height * step / (num_snakes as i32 + 1),
+            };
+            // unless the level put something there
+            let start = match sim.contents_of_square(spot.x, spot.y) {
+                ICellContents::Empty => Some(spot),
+                _ => sim.get_random_empty_space(),
+            };
+            let direction = if index % 2 == 0 {
+                Vector2D { x: 1, y: 0 }
+            } else {
+                Vector2D { x: -1, y: 0 }
+            };
+            if let Some(start) = start {
+                sim.snakes.push(Snake::new(*player, start, direction));
+            }
+        }
+
+        sim.add_missing_apples();
+        sim
+    }
+
+    pub fn spawn_strategy_name(&self) -> &str {
+        self.spawn_strategies[self.settings.spawn_strategy].name()
+    }
+
+    // how far the spawn strategy in use has got through any list of its own
+    pub fn spawn_progress(&self) -> usize {
+        self.spawn_strategies[self.settings.spawn_strategy].progress()
+    }
+
+    pub fn set_spawn_progress(&mut self, progress: usize) {
+        self.spawn_strategies[self.settings.spawn_strategy].set_progress(progress);
+    }
+
+    pub fn next_spawn_strategy(&mut self) {
+        self.settings.spawn_strategy =
+            (self.settings.spawn_strategy + 1) % self.spawn_strategies.len();
+    }
+
+    pub fn moment(&self) -> Moment {
+        Moment {
+            snakes: self.snakes.clone(),
+            apples: self.apples.clone(),
+            tick: self.tick,
+            is_game_over: self.is_game_over,
+            did_win: self.did_win,
+            spawn_strategies: self.spawn_strategies.clone(),
+            rng: self.rng.clone(),
+        }
+    }
+
+    // goes back to a moment taken from this same game
+    pub fn restore(&mut self, moment: &Moment) {
+        self.snakes = moment.snakes.clone();
+        self.apples = moment.apples.clone();
+        self.tick = moment.tick;
+        self.is_game_over = moment.is_game_over;
+        self.did_win = moment.did_win;
+        self.spawn_strategies = moment.spawn_strategies.clone();
+        self.rng = moment.rng.clone();
+    }
+
+    // where the game's randomness started, and how many numbers it's handed out since
+    pub fn randomness(&self) -> (u64, u64) {
+        (self.rng.seed, self.rng.draws)
+    }
+
+    // carries on from randomness that's already been used up to a point, as if this game had
+    // been the one to use it
+    pub fn skip_randomness(&mut self, draws: u64) {
+        self.rng.skip(draws);
+    }
+
+    pub fn set_apple_lifetime(&mut self, lifetime: Option<u32>) {
+        self.settings.apple_lifetime = lifetime;
+        for apple in self.apples.iter_mut() {
+            apple.lifetime = lifetime;
+            apple.age = 0;
+        }
+    }
+
+    // moves the game along by one update. Each snake gets the command at its own index
+    pub fn step(&mut self, commands: &[Command]) {
+        if self.is_game_over {
+            return;
+        }
+
+        for (snake, command) in self.snakes.iter_mut().zip(commands.iter()) {
+            if snake.is_alive {
+                snake.apply(*command);
+            }
+        }
+
+        let next_heads: Vec<Vector2D> = self
+            .snakes
+            .iter()
+            .map(|snake| snake.next_head(self.settings.width, self.settings.height))
+            .collect();
+        let moves = self.get_moves(&next_heads);
+
+        for (snake_index, snake_move) in moves.into_iter().enumerate() {
+            match snake_move {
+                Move::Die => {
+                    self.snakes[snake_index].is_alive = false;
+                }
+                Move::Stay => {}
+                Move::Advance => {
+                    let current_head = next_heads[snake_index];
+                    let snake = &mut self.snakes[snake_index];
+                    snake.advance(current_head);
+
+                    // remove apples
+                    for apple_index in 0..self.apples.len() {
+                        let apple = self.apples[apple_index];
+                        if apple.pos.x == current_head.x && apple.pos.y == current_head.y {
+                            self.apples.swap_remove_back(apple_index);
+                            snake.is_growing = true;
+                            snake.score += 1 + apple.bonus();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        for apple in self.apples.iter_mut() {
+            apple.age += 1;
+        }
+
+        self.add_missing_apples();
+
+        let num_alive = self.snakes.iter().filter(|snake| snake.is_alive).count();
+        if self.snakes.len() > 1 {
+            // versus is over once there's only one snake left standing
+            if num_alive <= 1 || self.apples.is_empty() {
+                self.is_game_over = true;
+            }
+        } else if num_alive == 0 {
+            self.is_game_over = true;
+        } else if self.apples.is_empty() {
+            self.is_game_over = true;
+            self.did_win = true;
+        }
+
+        self.tick += 1;
+    }
+
+    // tops the board back up to the right number of apples. Expired apples keep their squares
+    // until now so that their replacements end up somewhere else
+    fn add_missing_apples(&mut self) {
+        let mut num_live_apples = self
+            .apples
+            .iter()
+            .filter(|apple| !apple.is_expired())
+            .count();
+        while num_live_apples < self.settings.num_apples {
+            match self.get_apple_space() {
+                None => {
+                    break;
+                }
+                Some(pos) => {
+                    self.apples
+                        .push_back(Apple::new(pos, self.settings.apple_lifetime));
+                    num_live_apples += 1;
+                }
+            }
+        }
+
+        if num_live_apples >= self.settings.num_apples {
+            self.apples.retain(|apple| !apple.is_expired());
+        } else {
+            // nowhere to move them to, so let them stick around a bit longer
+            for apple in self.apples.iter_mut().filter(|apple| apple.is_expired()) {
+                apple.age = 0;
+            }
+        }
+    }
+
+    pub fn board_view(&self, me: usize) -> BoardView<'_> {
+        BoardView {
+            width: self.settings.width,
+            height: self.settings.height,
+            me,
+            snakes: self.snakes.iter().map(|snake| snake.view()).collect(),
+            apples: self.apples.iter().map(|apple| apple.pos).collect(),
+            walls: &self.settings.level.walls,
+            seed: self.rng.seed,
+        }
+    }
+
+    // works out what every snake does this update, before any of them actually move
+    fn get_moves(&self, next_heads: &[Vector2D]) -> Vec<Move> {
+        let rules = &self.settings.rules;
+        let mut moves = vec![];
+        for (index, snake) in self.snakes.iter().enumerate() {
+            if !snake.is_alive {
+                moves.push(Move::Stay);
+                continue;
+            }
+
+            let next_head = &next_heads[index];
+            let mut snake_move = Move::Advance;
+            if self.is_wall(next_head) || snake.contains(next_head) {
+                snake_move = rules.self_collision.into();
+            } else if self.snakes.iter().enumerate().any(|(other_index, other)| {
+                other_index != index && other.is_alive && other.contains(next_head)
+            }) {
+                snake_move = rules.snake_collision.into();
+            }
+
+            for (other_index, other) in self.snakes.iter().enumerate() {
+                if other_index == index || !other.is_alive || next_heads[other_index] != *next_head
+                {
+                    continue;
+                }
+
+                let head_to_head_move = match rules.head_to_head {
+                    HeadToHeadRule::Block => Move::Stay,
+                    HeadToHeadRule::BothDie => Move::Die,
+                    HeadToHeadRule::LongerWins => {
+                        if snake.path.len() > other.path.len() {
+                            Move::Advance
+                        } else {
+                            Move::Die
+                        }
+                    }
+                };
+                snake_move = max(snake_move, head_to_head_move);
+            }
+
+            moves.push(snake_move);
+        }
+        moves
+    }
+
+    // a fingerprint of everything that can change during a game. Two simulations that started
+    // the same way and got the same commands always agree on it
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher(0xcbf2_9ce4_8422_2325);
+        self.tick.hash(&mut hasher);
+        for snake in self.snakes.iter() {
+            snake.path.hash(&mut hasher);
+            snake.head_direction.hash(&mut hasher);
+            snake.head_is_tail.hash(&mut hasher);
+            snake.is_growing.hash(&mut hasher);
+            snake.score.hash(&mut hasher);
+            snake.is_alive.hash(&mut hasher);
+        }
+        for apple in self.apples.iter() {
+            apple.pos.hash(&mut hasher);
+            apple.age.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // the snake that won a versus match, or None if it was a draw
+    pub fn get_winner(&self) -> Option<usize> {
+        let alive: Vec<usize> = (0..self.snakes.len())
+            .filter(|index| self.snakes[*index].is_alive)
+            .collect();
+        if alive.len() == 1 {
+            return Some(alive[0]);
+        }
+
+        // nobody outlasted everyone else, so it comes down to score
+        let candidates: Vec<usize> = if alive.is_empty() {
+            (0..self.snakes.len()).collect()
+        } else {
+            alive
+        };
+        let best_score = candidates
+            .iter()
+            .map(|index| self.snakes[*index].score)
+            .max()?;
+        let best: Vec<usize> = candidates
+            .into_iter()
+            .filter(|index| self.snakes[*index].score == best_score)
+            .collect();
+        if best.len() == 1 {
+            Some(best[0])
+        } else {
+            None
+        }
+    }
+
+    pub fn is_wall(&self, pos: &Vector2D) -> bool {
+        self.settings.level.walls.contains(pos)
+    }
+
+    fn get_apple_space(&mut self) -> Option<Vector2D> {
+        let empty_squares = self.get_empty_squares();
+        let apples: Vec<Vector2D> = self.apples.iter().map(|apple| apple.pos).collect();
+        let blocked = self.get_blocked_squares();
+        let board = SpawnBoard {
+            width: self.settings.width,
+            height: self.settings.height,
+            // spawners that care about where the head is look at the first player's
+            head: self.snakes.first().map(Snake::head),
+            empty_squares: &empty_squares,
+            apples: &apples,
+            walls: &self.settings.level.walls,
+            blocked: &blocked,
+        };
+        self.spawn_strategies[self.settings.spawn_strategy].choose(&board, &mut self.rng)
+    }
+
+    // squares nothing can move into: walls and every snake that's still alive
+    pub fn get_blocked_squares(&self) -> HashSet<Vector2D> {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.is_alive)
+            .flat_map(|snake| snake.path.iter())
+            .chain(self.settings.level.walls.iter())
+            .copied()
+            .collect()
+    }
+
+    fn get_random_empty_space(&mut self) -> Option<Vector2D> {
+        let empty_squares = self.get_empty_squares();
+        empty_squares.choose(&mut self.rng).copied()
+    }
+
+    fn get_empty_squares(&self) -> Vec<Vector2D> {
+        let mut rv = vec![];
+        for x in 0..self.settings.width {
+            for y in 0..self.settings.height {
+                if let ICellContents::Empty = self.contents_of_square(x, y) {
+                    rv.push(Vector2D { x, y });
+                }
+            }
+        }
+        rv
+    }
+
+    fn contents_of_square(&self, x: i32, y: i32) -> ICellContents {
+        if self.is_wall(&Vector2D { x, y }) {
+            return ICellContents::Wall;
+        }
+
+        for snake in self.snakes.iter().filter(|snake| snake.is_alive) {
+            if snake.contains(&Vector2D { x, y }) {
+                return ICellContents::Snake;
+            }
+        }
+
+        for apple in self.apples.iter() {
+            if apple.pos.x == x && apple.pos.y == y {
+                return ICellContents::Apple;
+            }
+        }
+
+        ICellContents::Empty
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+
+use super::controller::{BoardView, Command, SnakeController, SnakeView};
+use super::vector::Vector2D;
+
+pub const MAX_KEY_BUFF_LEN: usize = 3; // how many keys we'll keep track of before ignoring inputs
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeyBindings {
+    pub up: &'static str,
+    pub down: &'static str,
+    pub left: &'static str,
+    pub right: &'static str,
+    pub swap: &'static str,
+}
+
+impl KeyBindings {
+    pub fn arrows() -> KeyBindings {
+        KeyBindings {
+            up: "ArrowUp",
+            down: "ArrowDown",
+            left: "ArrowLeft",
+            right: "ArrowRight",
+            swap: " ",
+        }
+    }
+
+    pub fn wasd() -> KeyBindings {
+        KeyBindings {
+            up: "w",
+            down: "s",
+            left: "a",
+            right: "d",
+            swap: "q",
+        }
+    }
+
+    pub fn ijkl() -> KeyBindings {
+        KeyBindings {
+            up: "i",
+            down: "k",
+            left: "j",
+            right: "l",
+            swap: "u",
+        }
+    }
+
+    pub fn numpad() -> KeyBindings {
+        KeyBindings {
+            up: "8",
+            down: "5",
+            left: "4",
+            right: "6",
+            swap: "0",
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        [self.up, self.down, self.left, self.right, self.swap].contains(&key)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SnakeColors {
+    pub body: &'static str,
+    pub head: &'static str,
+    pub tail: &'static str,
+}
+
+// everything that's different from one player to the next
+#[derive(Debug, Clone, Copy)]
+pub struct Player {
+    pub name: &'static str,
+    pub bindings: KeyBindings,
+    pub colors: SnakeColors,
+}
+
+pub fn players() -> [Player; 4] {
+    [
+        Player {
+            name: "PLAYER 1",
+            bindings: KeyBindings::arrows(),
+            colors: SnakeColors {
+                body: "green",
+                head: "yellow",
+                tail: "yellow",
+            },
+        },
+        Player {
+            name: "PLAYER 2",
+            bindings: KeyBindings::wasd(),
+            colors: SnakeColors {
+                body: "royalblue",
+                head: "lightskyblue",
+                tail: "lightskyblue",
+            },
+        },
+        Player {
+            name: "PLAYER 3",
+            bindings: KeyBindings::ijkl(),
+            colors: SnakeColors {
+                body: "darkorchid",
+                head: "violet",
+                tail: "violet",
+            },
+        },
+        Player {
+            name: "PLAYER 4",
+            bindings: KeyBindings::numpad(),
+            colors: SnakeColors {
+                body: "darkorange",
+                head: "gold",
+                tail: "gold",
+            },
+        },
+    ]
+}
+
+// whoever's steering a snake: the keyboard, or a controller if one's plugged in. This lives
+// outside the simulation so a game is nothing but the commands that were fed into it
+pub struct Pilot {
+    pub bindings: KeyBindings,
+    pub key_buff: VecDeque<String>,
+
+    // pilots with a controller don't listen to the keyboard
+    pub controller: Option<Box<dyn SnakeController>>,
+}
+
+impl Pilot {
+    pub fn new(bindings: KeyBindings) -> Pilot {
+        Pilot {
+            bindings,
+            key_buff: VecDeque::with_capacity(MAX_KEY_BUFF_LEN),
+            controller: None,
+        }
+    }
+
+    // returns whether the key was meant for this pilot
+    pub fn handle_key(&mut self, key: &str) -> bool {
+        if self.controller.is_some() || !self.bindings.contains(key) {
+            return false;
+        }
+
+        if self.key_buff.len() < MAX_KEY_BUFF_LEN {
+            self.key_buff.push_back(key.to_string());
+        }
+        true
+    }
+
+    pub fn next_command(&mut self, board: &BoardView) -> Command {
+        if let Some(controller) = self.controller.as_mut() {
+            return controller.next_command(board);
+        }
+
+        match self.key_buff.pop_front() {
+            Some(key) => {
+                let bindings = &self.bindings;
+                if key == bindings.up {
+                    Command::Up
+                } else if key == bindings.down {
+                    Command::Down
+                } else if key == bindings.right {
+                    Command::Right
+                } else if key == bindings.left {
+                    Command::Left
+                } else if key == bindings.swap {
+                    Command::SwapHead
+                } else {
+                    Command::Continue
+                }
+            }
+            None => Command::Continue,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Snake {
+    pub player: Player,
+
+    pub head_direction: Vector2D,
+    pub head_is_tail: bool,
+    pub path: VecDeque<Vector2D>,
+    pub is_growing: bool,
+
+    pub score: u32,
+    pub is_alive: bool,
+}
+
+impl Snake {
+    pub fn new(player: Player, start: Vector2D, head_direction: Vector2D) -> Snake {
+        let mut path = VecDeque::new();
+        path.push_front(start);
+
+        Snake {
+            player,
+
+            head_direction,
+            head_is_tail: true,
+            path,
+            is_growing: false,
+
+            score: 0,
+            is_alive: true,
+        }
+    }
+
+    pub fn head(&self) -> Vector2D {
+        // head will never be null
+        if self.head_is_tail {
+            *self.path.back().unwrap()
+        } else {
+            *self.path.front().unwrap()
+        }
+    }
+
+    pub fn tail(&self) -> Vector2D {
+        if self.head_is_tail {
+            *self.path.front().unwrap()
+        } else {
+            *self.path.back().unwrap()
+        }
+    }
+
+    // where the head ends up next update, wrapping around the edges of the board
+    pub fn next_head(&self, width: i32, height: i32) -> Vector2D {
+        let head = self.head();
+        let mut next_head = Vector2D {
+            x: head.x + self.head_direction.x,
+            y: head.y + self.head_direction.y,
+        };
+
+        if next_head.x < 0 {
+            next_head.x = width - 1;
+        }
+
+        if next_head.x >= width {
+            next_head.x = 0;
+        }
+
+        if next_head.y < 0 {
+            next_head.y = height - 1;
+        }
+
+        if next_head.y >= height {
+            next_head.y = 0;
+        }
+
+        next_head
+    }
+
+    pub fn advance(&mut self, new_head: Vector2D) {
+        if self.is_growing {
+            self.is_growing = false;
+        } else if self.head_is_tail {
+            self.path.pop_front();
+        } else {
+            self.path.pop_back();
+        }
+
+        if self.head_is_tail {
+            self.path.push_back(new_head);
+        } else {
+            self.path.push_front(new_head);
+        }
+    }
+
+    pub fn view(&self) -> SnakeView<'_> {
+        SnakeView {
+            path: &self.path,
+            head: self.head(),
+            tail: self.tail(),
+            head_direction: self.head_direction,
+            head_is_tail: self.head_is_tail,
+            is_alive: self.is_alive,
+            score: self.score,
+        }
+    }
+
+    pub fn contains(&self, pos: &Vector2D) -> bool {
+        self.path.contains(pos)
+    }
+
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::Continue => {}
+            // reverse head
+            Command::SwapHead => self.head_is_tail = !self.head_is_tail,
+            _ => {
+                if let Some(direction) = command.direction() {
+                    self.head_direction = direction;
+                }
+            }
+        }
+    }
+}
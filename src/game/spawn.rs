@@ -0,0 +1,252 @@
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use std::collections::{HashSet, VecDeque};
+
+use super::level::Level;
+use super::vector::Vector2D;
+
+const NEAR_WALL_DISTANCE: i32 = 2; // how close to a wall counts as "near" it
+const CLUSTER_RADIUS: i32 = 3; // how far from an existing apple a clustered apple may land
+const FAR_FROM_HEAD_FRACTION: f64 = 0.75; // fraction of the furthest distance that counts as "far"
+
+// everything a spawn strategy gets to look at when picking a square
+pub struct SpawnBoard<'a> {
+    pub width: i32,
+    pub height: i32,
+    // the first player's head, unless there aren't any players
+    pub head: Option<Vector2D>,
+    pub empty_squares: &'a [Vector2D],
+    pub apples: &'a [Vector2D],
+    pub walls: &'a [Vector2D],
+    // squares nothing can move through, i.e. snake and walls
+    pub blocked: &'a HashSet<Vector2D>,
+}
+
+// Send so whole games can be handed off to other threads, and CloneStrategy so they can be
+// snapshotted
+pub trait SpawnStrategy: Send + CloneStrategy {
+    fn name(&self) -> &str;
+
+    // returns None if there's nowhere it's willing to put an apple
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D>;
+
+    // how far through a list of its own it's got, for strategies that keep one, so a game
+    // written out by state.rs carries on from the same place
+    fn progress(&self) -> usize {
+        0
+    }
+
+    fn set_progress(&mut self, _progress: usize) {}
+}
+
+// boxed strategies can't be Clone themselves, so anything that is gets this for free
+pub trait CloneStrategy {
+    fn clone_box(&self) -> Box<dyn SpawnStrategy>;
+}
+
+impl<T: SpawnStrategy + Clone + 'static> CloneStrategy for T {
+    fn clone_box(&self) -> Box<dyn SpawnStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn SpawnStrategy> {
+    fn clone(&self) -> Box<dyn SpawnStrategy> {
+        self.clone_box()
+    }
+}
+
+pub fn builtin_strategies(level: &Level) -> Vec<Box<dyn SpawnStrategy>> {
+    vec![
+        Box::new(Uniform),
+        Box::new(FarFromHead),
+        Box::new(NearWalls),
+        Box::new(Clustered),
+        Box::new(ReachableOnly),
+        Box::new(Scripted::new(level.apples.clone())),
+    ]
+}
+
+#[derive(Clone)]
+pub struct Uniform;
+
+impl SpawnStrategy for Uniform {
+    fn name(&self) -> &str {
+        "uniform"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        board.empty_squares.choose(rng).copied()
+    }
+}
+
+#[derive(Clone)]
+pub struct FarFromHead;
+
+impl SpawnStrategy for FarFromHead {
+    fn name(&self) -> &str {
+        "far from head"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        let head = match board.head {
+            Some(head) => head,
+            None => return board.empty_squares.choose(rng).copied(),
+        };
+        let distance = |pos: &Vector2D| pos.wrapped_distance(&head, board.width, board.height);
+        let furthest = board.empty_squares.iter().map(distance).max()?;
+        let cutoff = (furthest as f64 * FAR_FROM_HEAD_FRACTION) as i32;
+        choose_where(board.empty_squares, rng, |pos| distance(pos) >= cutoff)
+    }
+}
+
+// hugs the walls, or the edges of the board if the level doesn't have any
+#[derive(Clone)]
+pub struct NearWalls;
+
+impl SpawnStrategy for NearWalls {
+    fn name(&self) -> &str {
+        "near walls"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        let distance = |pos: &Vector2D| {
+            if board.walls.is_empty() {
+                pos.x
+                    .min(pos.y)
+                    .min(board.width - 1 - pos.x)
+                    .min(board.height - 1 - pos.y)
+            } else {
+                board
+                    .walls
+                    .iter()
+                    .map(|wall| pos.wrapped_distance(wall, board.width, board.height) - 1)
+                    .min()
+                    .unwrap()
+            }
+        };
+
+        let closest = board.empty_squares.iter().map(distance).min()?;
+        let cutoff = closest.max(NEAR_WALL_DISTANCE);
+        choose_where(board.empty_squares, rng, |pos| distance(pos) <= cutoff)
+    }
+}
+
+// keeps new apples close to the ones already on the board
+#[derive(Clone)]
+pub struct Clustered;
+
+impl SpawnStrategy for Clustered {
+    fn name(&self) -> &str {
+        "clustered"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        let near_apple = |pos: &Vector2D| {
+            board.apples.iter().any(|apple| {
+                pos.wrapped_distance(apple, board.width, board.height) <= CLUSTER_RADIUS
+            })
+        };
+        choose_where(board.empty_squares, rng, near_apple)
+    }
+}
+
+// only picks squares the head can actually get to, so apples never end up sealed off. If
+// there aren't any, it's better to wait than to put one where it can't be eaten
+#[derive(Clone)]
+pub struct ReachableOnly;
+
+impl SpawnStrategy for ReachableOnly {
+    fn name(&self) -> &str {
+        "reachable only"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        // with nobody on the board, nowhere's cut off from anyone
+        let head = match board.head {
+            Some(head) => head,
+            None => return board.empty_squares.choose(rng).copied(),
+        };
+        let reachable = flood_fill(board.width, board.height, board.blocked, &head);
+        let squares: Vec<Vector2D> = board
+            .empty_squares
+            .iter()
+            .copied()
+            .filter(|pos| reachable.contains(pos))
+            .collect();
+        squares.choose(rng).copied()
+    }
+}
+
+// plays back the apple positions from the level in order, skipping any that are taken
+#[derive(Clone)]
+pub struct Scripted {
+    positions: Vec<Vector2D>,
+    next: usize,
+}
+
+impl Scripted {
+    pub fn new(positions: Vec<Vector2D>) -> Scripted {
+        Scripted { positions, next: 0 }
+    }
+}
+
+impl SpawnStrategy for Scripted {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn choose(&mut self, board: &SpawnBoard, rng: &mut dyn RngCore) -> Option<Vector2D> {
+        for _ in 0..self.positions.len() {
+            let pos = self.positions[self.next];
+            self.next = (self.next + 1) % self.positions.len();
+            if board.empty_squares.contains(&pos) {
+                return Some(pos);
+            }
+        }
+
+        // ran out of script
+        board.empty_squares.choose(rng).copied()
+    }
+
+    fn progress(&self) -> usize {
+        self.next
+    }
+
+    fn set_progress(&mut self, progress: usize) {
+        self.next = progress.checked_rem(self.positions.len()).unwrap_or(0);
+    }
+}
+
+// picks from the squares matching the predicate, or from all of them if none do
+fn choose_where<F>(squares: &[Vector2D], rng: &mut dyn RngCore, predicate: F) -> Option<Vector2D>
+where
+    F: Fn(&Vector2D) -> bool,
+{
+    let preferred: Vec<Vector2D> = squares.iter().copied().filter(predicate).collect();
+    if preferred.is_empty() {
+        squares.choose(rng).copied()
+    } else {
+        preferred.choose(rng).copied()
+    }
+}
+
+// every square you can get to from start without going through anything blocked
+pub fn flood_fill(
+    width: i32,
+    height: i32,
+    blocked: &HashSet<Vector2D>,
+    start: &Vector2D,
+) -> HashSet<Vector2D> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(*start);
+    while let Some(pos) = queue.pop_front() {
+        for neighbor in pos.neighbors(width, height).iter() {
+            if !blocked.contains(neighbor) && seen.insert(*neighbor) {
+                queue.push_back(*neighbor);
+            }
+        }
+    }
+    seen
+}
@@ -0,0 +1,645 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::net::Message;
+use super::simulation::{Apple, Settings, Simulation};
+use super::snake::{self, Snake};
+use super::vector::Vector2D;
+
+pub const SNAPSHOT_INTERVAL: u64 = 100; // how often a whole snapshot goes out, for late watchers
+
+// What a game being watched looks like, as plain text. A snapshot is everything needed to draw
+// the game from scratch:
+//
+//   snapshot 120 0 0     the tick, whether the game's over, and whether it was won
+//   board 30 20
+//   wall 3 4             one line per wall square
+//   lifetime none        or a number of updates
+//   apple 10 5 3         where an apple is and how old it is
+//   snake 0 1 0 1 1 12 5,4 6,4 ...
+//                        which player, the way it's heading, whether its head is the end of
+//                        its path, whether it's alive, its score, and its path
+//
+// and every tick after that only sends what changed since the one before:
+//
+//   delta 121 0 0
+//   lifetime none
+//   apple 10 5 4         every apple, since there aren't many and they all age
+//   move 0 1 0 1 1 12 0 1 - 7,4
+//                        which snake, the same as above up to the score, then how many
+//                        squares came off the front and back of its path, and what went on
+//                        each end, as x,y;x,y or - for nothing
+//
+// Snake paths are the only thing big enough to be worth the trouble.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub is_over: bool,
+    pub did_win: bool,
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<Vector2D>,
+    pub apple_lifetime: Option<u32>,
+    pub apples: Vec<Apple>,
+    pub snakes: Vec<SnakeState>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SnakeState {
+    // index into snake::players()
+    pub player: usize,
+    pub head_direction: Vector2D,
+    pub head_is_tail: bool,
+    pub is_alive: bool,
+    pub score: u32,
+    pub path: VecDeque<Vector2D>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delta {
+    pub tick: u64,
+    pub is_over: bool,
+    pub did_win: bool,
+    pub apple_lifetime: Option<u32>,
+    pub apples: Vec<Apple>,
+    pub snakes: Vec<SnakeDelta>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SnakeDelta {
+    pub index: usize,
+    pub head_direction: Vector2D,
+    pub head_is_tail: bool,
+    pub is_alive: bool,
+    pub score: u32,
+    pub popped_front: usize,
+    pub popped_back: usize,
+    // in path order, so after the pops the path is pushed_front, what was left, pushed_back
+    pub pushed_front: Vec<Vector2D>,
+    pub pushed_back: Vec<Vector2D>,
+}
+
+impl SnakeState {
+    fn of(snake: &Snake) -> SnakeState {
+        SnakeState {
+            player: player_index(snake),
+            head_direction: snake.head_direction,
+            head_is_tail: snake.head_is_tail,
+            is_alive: snake.is_alive,
+            score: snake.score,
+            path: snake.path.clone(),
+        }
+    }
+}
+
+// by color, since computer players get renamed
+pub fn player_index(snake: &Snake) -> usize {
+    snake::players()
+        .iter()
+        .position(|player| player.colors.body == snake.player.colors.body)
+        .unwrap_or(0)
+}
+
+impl Snapshot {
+    pub fn of(sim: &Simulation, is_over: bool) -> Snapshot {
+        Snapshot {
+            tick: sim.tick,
+            is_over,
+            did_win: sim.did_win,
+            width: sim.settings.width,
+            height: sim.settings.height,
+            walls: sim.settings.level.walls.clone(),
+            apple_lifetime: sim.settings.apple_lifetime,
+            apples: sim.apples.iter().copied().collect(),
+            snakes: sim.snakes.iter().map(SnakeState::of).collect(),
+        }
+    }
+
+    // a game that looks just like the one that was sent. It's only good for drawing, since
+    // nothing about what happens next came with it
+    pub fn to_simulation(&self) -> Simulation {
+        let mut settings = Settings::new(self.width, self.height);
+        settings.num_snakes = 0;
+        settings.num_apples = 0;
+        settings.apple_lifetime = self.apple_lifetime;
+        settings.level.walls = self.walls.clone();
+        let mut sim = Simulation::new(settings, 0);
+
+        let players = snake::players();
+        for state in self.snakes.iter() {
+            let start = state
+                .path
+                .front()
+                .copied()
+                .unwrap_or(Vector2D { x: 0, y: 0 });
+            let mut snake = Snake::new(players[state.player], start, state.head_direction);
+            snake.head_is_tail = state.head_is_tail;
+            snake.is_alive = state.is_alive;
+            snake.score = state.score;
+            snake.path = state.path.clone();
+            sim.snakes.push(snake);
+        }
+        sim.settings.num_snakes = sim.snakes.len();
+        sim.apples = self.apples.iter().copied().collect();
+        sim.tick = self.tick;
+        sim.is_game_over = self.is_over;
+        sim.did_win = self.did_win;
+        sim
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "snapshot {} {} {}\n",
+            self.tick,
+            flag(self.is_over),
+            flag(self.did_win)
+        );
+        text.push_str(&format!("board {} {}\n", self.width, self.height));
+        for wall in self.walls.iter() {
+            text.push_str(&format!("wall {} {}\n", wall.x, wall.y));
+        }
+        push_apples(&mut text, self.apple_lifetime, &self.apples);
+        for snake in self.snakes.iter() {
+            let path: Vec<String> = snake
+                .path
+                .iter()
+                .map(|square| square_text(*square))
+                .collect();
+            text.push_str(&format!(
+                "snake {} {} {} {} {} {} {}\n",
+                snake.player,
+                snake.head_direction.x,
+                snake.head_direction.y,
+                flag(snake.head_is_tail),
+                flag(snake.is_alive),
+                snake.score,
+                path.join(" ")
+            ));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Snapshot, String> {
+        let mut snapshot = Snapshot {
+            tick: 0,
+            is_over: false,
+            did_win: false,
+            width: 0,
+            height: 0,
+            walls: vec![],
+            apple_lifetime: None,
+            apples: vec![],
+            snakes: vec![],
+        };
+
+        for (line_number, words) in lines(text) {
+            let error = |message: &str| format!("line {}: {}", line_number, message);
+            match words.as_slice() {
+                ["snapshot", tick, is_over, did_win] => {
+                    snapshot.tick = number(tick).ok_or_else(|| error("expected a tick"))?;
+                    snapshot.is_over =
+                        parse_flag(is_over).ok_or_else(|| error("expected 0 or 1"))?;
+                    snapshot.did_win =
+                        parse_flag(did_win).ok_or_else(|| error("expected 0 or 1"))?;
+                }
+                ["board", width, height] => match (number(width), number(height)) {
+                    (Some(width), Some(height)) if width > 0 && height > 0 => {
+                        snapshot.width = width;
+                        snapshot.height = height;
+                    }
+                    _ => return Err(error("expected a width and a height")),
+                },
+                ["wall", x, y] => match (number(x), number(y)) {
+                    (Some(x), Some(y)) => snapshot.walls.push(Vector2D { x, y }),
+                    _ => return Err(error("expected a square")),
+                },
+                ["lifetime", lifetime] => {
+                    snapshot.apple_lifetime =
+                        parse_lifetime(lifetime).ok_or_else(|| error("expected a lifetime"))?
+                }
+                ["apple", ..] => snapshot.apples.push(
+                    parse_apple(&words, snapshot.apple_lifetime)
+                        .ok_or_else(|| error("expected a square and an age"))?,
+                ),
+                ["snake", player, dx, dy, head_is_tail, is_alive, score, path @ ..] => {
+                    let state = (|| {
+                        let player =
+                            number(player).filter(|player| *player < snake::players().len())?;
+                        Some(SnakeState {
+                            player,
+                            head_direction: parse_direction(dx, dy)?,
+                            head_is_tail: parse_flag(head_is_tail)?,
+                            is_alive: parse_flag(is_alive)?,
+                            score: number(score)?,
+                            path: path
+                                .iter()
+                                .map(|square| parse_square(square))
+                                .collect::<Option<VecDeque<Vector2D>>>()?,
+                        })
+                    })();
+                    snapshot
+                        .snakes
+                        .push(state.ok_or_else(|| error("couldn't read the snake"))?);
+                }
+                _ => return Err(error("don't know what to do with this")),
+            }
+        }
+
+        if snapshot.width == 0 {
+            return Err("the snapshot doesn't say how big the board is".to_string());
+        }
+        snapshot.check()?;
+        Ok(snapshot)
+    }
+
+    // Snapshots come through the relay from whoever's broadcasting, so anything a real game
+    // couldn't have sent is turned down the same way a replay would be, before it gets drawn
+    fn check(&self) -> Result<(), String> {
+        let mut settings = Settings::new(self.width, self.height);
+        settings.num_snakes = self.snakes.len();
+        settings.level.walls = self.walls.clone();
+        settings.check()?;
+        if let Some(apple) = self
+            .apples
+            .iter()
+            .find(|apple| !settings.contains(&apple.pos))
+        {
+            return Err(format!("apple {} is off the board", square_text(apple.pos)));
+        }
+        for (index, snake) in self.snakes.iter().enumerate() {
+            if snake.path.is_empty() {
+                return Err(format!("snake {} has no squares", index));
+            }
+            if let Some(square) = snake.path.iter().find(|square| !settings.contains(square)) {
+                return Err(format!(
+                    "snake {} goes off the board at {}",
+                    index,
+                    square_text(*square)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Delta {
+    // what changed over one tick, or None if too much did to say it this way
+    pub fn between(old: &Simulation, new: &Simulation, is_over: bool) -> Option<Delta> {
+        if old.snakes.len() != new.snakes.len() || old.tick + 1 != new.tick {
+            return None;
+        }
+
+        let mut snakes = vec![];
+        for (index, (old_snake, new_snake)) in old.snakes.iter().zip(new.snakes.iter()).enumerate()
+        {
+            if old_snake.player.name != new_snake.player.name {
+                return None;
+            }
+            let (popped_front, popped_back, pushed_front, pushed_back) =
+                diff_paths(&old_snake.path, &new_snake.path);
+            snakes.push(SnakeDelta {
+                index,
+                head_direction: new_snake.head_direction,
+                head_is_tail: new_snake.head_is_tail,
+                is_alive: new_snake.is_alive,
+                score: new_snake.score,
+                popped_front,
+                popped_back,
+                pushed_front,
+                pushed_back,
+            });
+        }
+
+        Some(Delta {
+            tick: new.tick,
+            is_over,
+            did_win: new.did_win,
+            apple_lifetime: new.settings.apple_lifetime,
+            apples: new.apples.iter().copied().collect(),
+            snakes,
+        })
+    }
+
+    // moves a game made from a snapshot on by a tick. Nothing changes unless all of it fits
+    pub fn apply(&self, sim: &mut Simulation) -> Result<(), String> {
+        if self.tick != sim.tick + 1 {
+            return Err(format!("got tick {} while on tick {}", self.tick, sim.tick));
+        }
+        self.check(sim)?;
+        for change in self.snakes.iter() {
+            let snake = &mut sim.snakes[change.index];
+            snake.path.drain(..change.popped_front);
+            let keep = snake.path.len() - change.popped_back;
+            snake.path.truncate(keep);
+            for square in change.pushed_front.iter().rev() {
+                snake.path.push_front(*square);
+            }
+            snake.path.extend(change.pushed_back.iter());
+
+            snake.head_direction = change.head_direction;
+            snake.head_is_tail = change.head_is_tail;
+            snake.is_alive = change.is_alive;
+            snake.score = change.score;
+        }
+
+        sim.settings.apple_lifetime = self.apple_lifetime;
+        sim.apples = self.apples.iter().copied().collect();
+        sim.tick = self.tick;
+        sim.is_game_over = self.is_over;
+        sim.did_win = self.did_win;
+        Ok(())
+    }
+
+    // whether this could have come after the game as it is, since it came through the relay
+    fn check(&self, sim: &Simulation) -> Result<(), String> {
+        let settings = &sim.settings;
+        if let Some(apple) = self
+            .apples
+            .iter()
+            .find(|apple| !settings.contains(&apple.pos))
+        {
+            return Err(format!("apple {} is off the board", square_text(apple.pos)));
+        }
+        let mut moved = HashSet::new();
+        for change in self.snakes.iter() {
+            let snake = sim
+                .snakes
+                .get(change.index)
+                .ok_or_else(|| format!("there's no snake {}", change.index))?;
+            if !moved.insert(change.index) {
+                return Err(format!("snake {} moved twice", change.index));
+            }
+            let popped = change
+                .popped_front
+                .checked_add(change.popped_back)
+                .filter(|popped| *popped <= snake.path.len())
+                .ok_or_else(|| format!("snake {} isn't that long", change.index))?;
+            let pushed = change.pushed_front.len() + change.pushed_back.len();
+            if snake.path.len() - popped + pushed == 0 {
+                return Err(format!("snake {} would have no squares left", change.index));
+            }
+            let mut pushed = change.pushed_front.iter().chain(change.pushed_back.iter());
+            if let Some(square) = pushed.find(|square| !settings.contains(square)) {
+                return Err(format!(
+                    "snake {} goes off the board at {}",
+                    change.index,
+                    square_text(*square)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "delta {} {} {}\n",
+            self.tick,
+            flag(self.is_over),
+            flag(self.did_win)
+        );
+        push_apples(&mut text, self.apple_lifetime, &self.apples);
+        for snake in self.snakes.iter() {
+            text.push_str(&format!(
+                "move {} {} {} {} {} {} {} {} {} {}\n",
+                snake.index,
+                snake.head_direction.x,
+                snake.head_direction.y,
+                flag(snake.head_is_tail),
+                flag(snake.is_alive),
+                snake.score,
+                snake.popped_front,
+                snake.popped_back,
+                squares_text(&snake.pushed_front),
+                squares_text(&snake.pushed_back)
+            ));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Delta, String> {
+        let mut delta = Delta {
+            tick: 0,
+            is_over: false,
+            did_win: false,
+            apple_lifetime: None,
+            apples: vec![],
+            snakes: vec![],
+        };
+
+        for (line_number, words) in lines(text) {
+            let error = |message: &str| format!("line {}: {}", line_number, message);
+            match words.as_slice() {
+                ["delta", tick, is_over, did_win] => {
+                    delta.tick = number(tick).ok_or_else(|| error("expected a tick"))?;
+                    delta.is_over = parse_flag(is_over).ok_or_else(|| error("expected 0 or 1"))?;
+                    delta.did_win = parse_flag(did_win).ok_or_else(|| error("expected 0 or 1"))?;
+                }
+                ["lifetime", lifetime] => {
+                    delta.apple_lifetime =
+                        parse_lifetime(lifetime).ok_or_else(|| error("expected a lifetime"))?
+                }
+                ["apple", ..] => delta.apples.push(
+                    parse_apple(&words, delta.apple_lifetime)
+                        .ok_or_else(|| error("expected a square and an age"))?,
+                ),
+                ["move", index, dx, dy, head_is_tail, is_alive, score, popped_front, popped_back, pushed_front, pushed_back] =>
+                {
+                    let change = (|| {
+                        Some(SnakeDelta {
+                            index: number(index)?,
+                            head_direction: parse_direction(dx, dy)?,
+                            head_is_tail: parse_flag(head_is_tail)?,
+                            is_alive: parse_flag(is_alive)?,
+                            score: number(score)?,
+                            popped_front: number(popped_front)?,
+                            popped_back: number(popped_back)?,
+                            pushed_front: parse_squares(pushed_front)?,
+                            pushed_back: parse_squares(pushed_back)?,
+                        })
+                    })();
+                    delta
+                        .snakes
+                        .push(change.ok_or_else(|| error("couldn't read the move"))?);
+                }
+                _ => return Err(error("don't know what to do with this")),
+            }
+        }
+        Ok(delta)
+    }
+}
+
+// how to get from one path to the next with as little as possible: squares off the front and
+// back, then squares onto the front and back. A snake only moves one square a tick, so trying
+// a few from each end nearly always finds it, and otherwise the whole path gets sent
+fn diff_paths(
+    old: &VecDeque<Vector2D>,
+    new: &VecDeque<Vector2D>,
+) -> (usize, usize, Vec<Vector2D>, Vec<Vector2D>) {
+    const MAX_CHANGE: usize = 2;
+    let old: Vec<Vector2D> = old.iter().copied().collect();
+    let new: Vec<Vector2D> = new.iter().copied().collect();
+
+    for popped in 0..=MAX_CHANGE * 2 {
+        for popped_front in 0..=popped.min(MAX_CHANGE) {
+            let popped_back = popped - popped_front;
+            if popped_back > MAX_CHANGE || popped > old.len() {
+                continue;
+            }
+            let kept = &old[popped_front..old.len() - popped_back];
+            if kept.len() > new.len() {
+                continue;
+            }
+            for pushed_front in 0..=MAX_CHANGE.min(new.len() - kept.len()) {
+                let pushed_back = new.len() - kept.len() - pushed_front;
+                if pushed_back <= MAX_CHANGE
+                    && &new[pushed_front..pushed_front + kept.len()] == kept
+                {
+                    return (
+                        popped_front,
+                        popped_back,
+                        new[..pushed_front].to_vec(),
+                        new[pushed_front + kept.len()..].to_vec(),
+                    );
+                }
+            }
+        }
+    }
+    (old.len(), 0, new, vec![])
+}
+
+// Keeps track of what watchers have already seen, so each update only sends what's changed
+#[derive(Default)]
+pub struct Broadcaster {
+    // the last game we sent, and whether it was over
+    last: Option<(Simulation, bool)>,
+}
+
+impl Broadcaster {
+    pub fn new() -> Broadcaster {
+        Broadcaster::default()
+    }
+
+    // makes the next message a whole snapshot, say after the connection's been lost
+    pub fn restart(&mut self) {
+        self.last = None;
+    }
+
+    // what to send watchers about the game as it is now, if anything's changed
+    pub fn update(&mut self, sim: &Simulation, is_over: bool) -> Option<Message> {
+        let message = match &self.last {
+            Some((last, was_over))
+                if sim.tick == last.tick
+                    && is_over == *was_over
+                    && sim.state_hash() == last.state_hash() =>
+            {
+                return None
+            }
+            Some((last, _)) if !sim.tick.is_multiple_of(SNAPSHOT_INTERVAL) => {
+                Delta::between(last, sim, is_over).map(Message::Delta)
+            }
+            _ => None,
+        };
+        self.last = Some((sim.clone(), is_over));
+        Some(message.unwrap_or_else(|| Message::Snapshot(Snapshot::of(sim, is_over))))
+    }
+}
+
+// every line with something on it, split into words, along with its line number
+fn lines(text: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.split_whitespace().collect::<Vec<&str>>()))
+        .filter(|(_, words)| !words.is_empty())
+}
+
+pub fn number<T: std::str::FromStr>(word: &str) -> Option<T> {
+    word.parse().ok()
+}
+
+pub fn flag(value: bool) -> u8 {
+    value as u8
+}
+
+pub fn parse_flag(word: &str) -> Option<bool> {
+    match word {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_lifetime(word: &str) -> Option<Option<u32>> {
+    match word {
+        "none" => Some(None),
+        _ => number(word).map(Some),
+    }
+}
+
+fn push_apples(text: &mut String, lifetime: Option<u32>, apples: &[Apple]) {
+    match lifetime {
+        Some(lifetime) => text.push_str(&format!("lifetime {}\n", lifetime)),
+        None => text.push_str("lifetime none\n"),
+    }
+    for apple in apples.iter() {
+        text.push_str(&format!(
+            "apple {} {} {}\n",
+            apple.pos.x, apple.pos.y, apple.age
+        ));
+    }
+}
+
+fn parse_apple(words: &[&str], lifetime: Option<u32>) -> Option<Apple> {
+    match words {
+        [_, x, y, age] => Some(Apple {
+            pos: Vector2D {
+                x: number(x)?,
+                y: number(y)?,
+            },
+            lifetime,
+            age: number(age)?,
+        }),
+        _ => None,
+    }
+}
+
+// one step up, down, left or right
+pub fn parse_direction(dx: &str, dy: &str) -> Option<Vector2D> {
+    let direction = Vector2D {
+        x: number(dx)?,
+        y: number(dy)?,
+    };
+    match direction.x.abs() + direction.y.abs() {
+        1 => Some(direction),
+        _ => None,
+    }
+}
+
+pub fn square_text(square: Vector2D) -> String {
+    format!("{},{}", square.x, square.y)
+}
+
+pub fn parse_square(word: &str) -> Option<Vector2D> {
+    let mut numbers = word.split(',');
+    let square = Vector2D {
+        x: number(numbers.next()?)?,
+        y: number(numbers.next()?)?,
+    };
+    match numbers.next() {
+        Some(_) => None,
+        None => Some(square),
+    }
+}
+
+fn squares_text(squares: &[Vector2D]) -> String {
+    if squares.is_empty() {
+        return "-".to_string();
+    }
+    let squares: Vec<String> = squares.iter().map(|square| square_text(*square)).collect();
+    squares.join(";")
+}
+
+fn parse_squares(word: &str) -> Option<Vec<Vector2D>> {
+    if word == "-" {
+        return Some(vec![]);
+    }
+    word.split(';').map(parse_square).collect()
+}
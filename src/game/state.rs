@@ -0,0 +1,228 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::replay::Replay;
+use super::simulation::{Apple, Simulation};
+use super::snake::{self, Snake};
+use super::spectate::{flag, number, parse_flag, parse_square, player_index, square_text};
+use super::vector::Vector2D;
+
+// A game as it stands, as plain text that can be pasted into a bug report, diffed, or handed
+// to someone as a puzzle. It starts with the same settings as a replay, then everything on the
+// board:
+//
+//   board 30 20
+//   edges wrap           the only kind of edge there is, for now
+//   apples 5
+//   lifetime none
+//   spawn 0
+//   rules block die both-die
+//   level arena
+//   wall 3 4             the level, same as in a replay
+//   seed 1234            where the game's randomness started
+//   draws 87             and how many random numbers it had used, so it carries on the same
+//   script 3             how far the scripted spawner's got through the level's apples, left
+//                        out when it's not started
+//   state 120 0 0        the tick, whether the game's over, and whether it was won
+//   apple 10 5 3         where an apple is and how old it is
+//   snake 0 1 0 1 1 12 0 6,4 5,4 ...
+//                        which player, the way it's heading, whether its head is the end of
+//                        its path, whether it's alive, its score, whether it's about to grow,
+//                        and its squares from head to tail
+//
+// A game read back in plays on exactly the same as the one that was written out. When making
+// one up by hand, the draws line can be left out
+pub fn to_text(sim: &Simulation) -> String {
+    let (seed, draws) = sim.randomness();
+    let header = Replay::new(sim.settings.clone(), seed, vec![]).to_text();
+    let mut text = String::new();
+    for line in header.lines() {
+        text.push_str(line);
+        text.push('\n');
+        if line.starts_with("board ") {
+            text.push_str("edges wrap\n");
+        }
+    }
+    text.push_str(&format!("draws {}\n", draws));
+    if sim.spawn_progress() > 0 {
+        text.push_str(&format!("script {}\n", sim.spawn_progress()));
+    }
+
+    text.push_str(&format!(
+        "state {} {} {}\n",
+        sim.tick,
+        flag(sim.is_game_over),
+        flag(sim.did_win)
+    ));
+    for apple in sim.apples.iter() {
+        text.push_str(&format!(
+            "apple {} {} {}\n",
+            apple.pos.x, apple.pos.y, apple.age
+        ));
+    }
+    for snake in sim.snakes.iter() {
+        let mut squares: Vec<String> = snake.path.iter().map(|s| square_text(*s)).collect();
+        if snake.head_is_tail {
+            squares.reverse();
+        }
+        text.push_str(&format!(
+            "snake {} {} {} {} {} {} {} {}\n",
+            player_index(snake),
+            snake.head_direction.x,
+            snake.head_direction.y,
+            flag(snake.head_is_tail),
+            flag(snake.is_alive),
+            snake.score,
+            flag(snake.is_growing),
+            squares.join(" ")
+        ));
+    }
+    text
+}
+
+// a game that carries on from the one in the text
+pub fn parse(text: &str) -> Result<Simulation, String> {
+    // everything before the state line is a replay with no snakes or ticks
+    let mut header = String::new();
+    let mut draws = 0;
+    let mut script = 0;
+    let mut body = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.first() == Some(&"state") || !body.is_empty() {
+            if words.first().is_some_and(|word| !word.starts_with('#')) {
+                body.push((index + 1, words));
+            }
+            continue;
+        }
+        match words.as_slice() {
+            // a blank line instead, which keeps the line numbers right in the replay's errors
+            ["edges", "wrap"] => header.push('\n'),
+            ["edges", ..] => return Err(format!("line {}: the edges can only wrap", index + 1)),
+            ["draws", count] => {
+                draws = number(count)
+                    .ok_or_else(|| format!("line {}: expected a number of draws", index + 1))?;
+                header.push('\n');
+            }
+            ["script", progress] => {
+                script = number(progress).ok_or_else(|| {
+                    format!("line {}: expected how far the script's got", index + 1)
+                })?;
+                header.push('\n');
+            }
+            _ => {
+                header.push_str(line);
+                header.push('\n');
+            }
+        }
+    }
+    let replay = Replay::parse_header(&header)?;
+
+    let mut settings = replay.settings.clone();
+    let num_apples = settings.num_apples;
+    // an empty board, so nothing gets placed before we fill it in
+    settings.num_apples = 0;
+    let mut sim = Simulation::new(settings, replay.seed);
+    sim.settings.num_apples = num_apples;
+    sim.skip_randomness(draws);
+    sim.set_spawn_progress(script);
+
+    let (width, height) = (sim.settings.width, sim.settings.height);
+    let on_board =
+        |square: &Vector2D| (0..width).contains(&square.x) && (0..height).contains(&square.y);
+    let mut has_state = false;
+    // Squares live snakes are on, which no other live snake can share. Dead snakes stay where
+    // they died, and the live ones are free to go over them
+    let mut taken = HashSet::new();
+    for (line_number, words) in body {
+        let error = |message: &str| format!("line {}: {}", line_number, message);
+        match words.as_slice() {
+            ["state", tick, is_over, did_win] => {
+                sim.tick = number(tick).ok_or_else(|| error("expected a tick"))?;
+                sim.is_game_over = parse_flag(is_over).ok_or_else(|| error("expected 0 or 1"))?;
+                sim.did_win = parse_flag(did_win).ok_or_else(|| error("expected 0 or 1"))?;
+                has_state = true;
+            }
+            ["apple", x, y, age] => {
+                let apple = (|| {
+                    Some(Apple {
+                        pos: Vector2D {
+                            x: number(x)?,
+                            y: number(y)?,
+                        },
+                        lifetime: sim.settings.apple_lifetime,
+                        age: number(age)?,
+                    })
+                })()
+                .filter(|apple| on_board(&apple.pos))
+                .ok_or_else(|| error("expected a square on the board and an age"))?;
+                sim.apples.push_back(apple);
+            }
+            ["snake", player, dx, dy, head_is_tail, is_alive, score, is_growing, squares @ ..] => {
+                let snake = (|| {
+                    let player = snake::players().get(number::<usize>(player)?).copied()?;
+                    let head_direction = Vector2D {
+                        x: number(dx)?,
+                        y: number(dy)?,
+                    };
+                    if head_direction.x.abs() + head_direction.y.abs() != 1 {
+                        return None;
+                    }
+                    let mut path = squares
+                        .iter()
+                        .map(|square| parse_square(square).filter(on_board))
+                        .collect::<Option<VecDeque<Vector2D>>>()?;
+                    let head_is_tail = parse_flag(head_is_tail)?;
+                    if head_is_tail {
+                        path = path.into_iter().rev().collect();
+                    }
+                    let mut snake = Snake::new(player, *path.front()?, head_direction);
+                    snake.path = path;
+                    snake.head_is_tail = head_is_tail;
+                    snake.is_alive = parse_flag(is_alive)?;
+                    snake.score = number(score)?;
+                    snake.is_growing = parse_flag(is_growing)?;
+                    Some(snake)
+                })();
+                let snake = snake.ok_or_else(|| error("couldn't read the snake"))?;
+                if sim
+                    .snakes
+                    .iter()
+                    .any(|other| player_index(other) == player_index(&snake))
+                {
+                    return Err(error("that player's already got a snake"));
+                }
+                let path: Vec<&Vector2D> = snake.path.iter().collect();
+                if path
+                    .windows(2)
+                    .any(|pair| pair[0].wrapped_distance(pair[1], width, height) != 1)
+                {
+                    return Err(error(
+                        "every square of a snake has to be next to the one before",
+                    ));
+                }
+                if path.iter().any(|square| sim.is_wall(square)) {
+                    return Err(error("the snake's in a wall"));
+                }
+                let squares: HashSet<&Vector2D> = path.iter().copied().collect();
+                if squares.len() != path.len()
+                    || (snake.is_alive && !path.iter().all(|square| taken.insert(**square)))
+                {
+                    return Err(error("the snake's on top of itself or another snake"));
+                }
+                sim.snakes.push(snake);
+            }
+            _ => return Err(error("don't know what to do with this")),
+        }
+    }
+
+    if !has_state {
+        return Err("missing state".to_string());
+    }
+    // with nobody in it there's no game to carry on with
+    if sim.snakes.is_empty() {
+        return Err("there has to be at least one snake".to_string());
+    }
+    sim.settings.num_snakes = sim.snakes.len();
+    sim.settings.check()?;
+    Ok(sim)
+}
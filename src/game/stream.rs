@@ -0,0 +1,82 @@
+use web_sys::WebSocket;
+
+use super::net::Message;
+use super::spectate::Broadcaster;
+
+// what this page is doing with its channel
+pub enum Role {
+    // sending our game out to anyone watching
+    Broadcast(Box<Broadcaster>),
+    // drawing someone else's game, and not playing at all
+    Watch(Watching),
+}
+
+pub struct Watching {
+    // whether the first snapshot's arrived yet
+    pub has_game: bool,
+    // which snake the camera's following, if it isn't showing the whole board
+    pub follow: Option<usize>,
+}
+
+// the browser's end of a game being watched: the connection to the relay server, the channel,
+// and which end of it we're on
+pub struct Stream {
+    pub url: String,
+    pub channel: String,
+    socket: Option<WebSocket>,
+    pub role: Role,
+    // the last thing the server turned us down for
+    pub error: Option<String>,
+}
+
+impl Stream {
+    pub fn new(url: &str, channel: &str, role: Role) -> Stream {
+        Stream {
+            url: url.to_string(),
+            channel: channel.to_string(),
+            socket: None,
+            role,
+            error: None,
+        }
+    }
+
+    pub fn set_socket(&mut self, socket: Option<WebSocket>) {
+        self.socket = socket;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.socket
+            .as_ref()
+            .is_some_and(|socket| socket.ready_state() == WebSocket::OPEN)
+    }
+
+    pub fn watching(&self) -> Option<&Watching> {
+        match &self.role {
+            Role::Watch(watching) => Some(watching),
+            Role::Broadcast(_) => None,
+        }
+    }
+
+    // what to say once connected. The server forgets us whenever the connection drops, so it's
+    // the same every time
+    pub fn hello(&self) -> Message {
+        let channel = self.channel.clone();
+        match self.role {
+            Role::Broadcast(_) => Message::Broadcast { channel },
+            Role::Watch(_) => Message::Watch { channel },
+        }
+    }
+
+    // anything sent while the connection's down is lost, but broadcasts start over with a
+    // snapshot when it's back
+    pub fn send(&self, message: &Message) {
+        if !self.is_connected() {
+            return;
+        }
+        if let Some(socket) = &self.socket {
+            if let Err(err) = socket.send_with_str(&message.to_text()) {
+                log::warn!("couldn't send to {}: {:?}", self.url, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use super::render::{Align, Renderer};
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    // the canvas's [a, b, c, d, e, f]
+    transform: [f64; 6],
+    alpha: f64,
+}
+
+// Draws into SVG elements instead of onto a canvas. Each frame's a group of plain shapes, and
+// animate() turns a list of them into one file that plays them in a loop, with nothing to
+// load from anywhere else
+pub struct Svg {
+    elements: String,
+    state: State,
+    saved: Vec<State>,
+}
+
+impl Default for Svg {
+    fn default() -> Svg {
+        Svg::new()
+    }
+}
+
+impl Svg {
+    pub fn new() -> Svg {
+        Svg {
+            elements: String::new(),
+            state: State {
+                transform: [1., 0., 0., 1., 0., 0.],
+                alpha: 1.,
+            },
+            saved: vec![],
+        }
+    }
+
+    pub fn into_elements(self) -> String {
+        self.elements
+    }
+
+    // the attributes every shape gets for where it is and how see-through it is
+    fn attributes(&self) -> String {
+        let mut attributes = String::new();
+        let [a, b, c, d, e, f] = self.state.transform;
+        if self.state.transform != [1., 0., 0., 1., 0., 0.] {
+            write!(
+                attributes,
+                " transform=\"matrix({} {} {} {} {} {})\"",
+                round(a),
+                round(b),
+                round(c),
+                round(d),
+                round(e),
+                round(f)
+            )
+            .unwrap();
+        }
+        if self.state.alpha < 1. {
+            write!(attributes, " opacity=\"{}\"", round(self.state.alpha)).unwrap();
+        }
+        attributes
+    }
+}
+
+// keeps the numbers short, since there are a lot of them
+fn round(value: f64) -> f64 {
+    (value * 1000.).round() / 1000.
+}
+
+impl Renderer for Svg {
+    fn save(&mut self) {
+        self.saved.push(self.state);
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+        }
+    }
+
+    fn set_alpha(&mut self, alpha: f64) {
+        self.state.alpha = alpha;
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        let [a, b, c, d, e, f] = self.state.transform;
+        self.state.transform = [a, b, c, d, a * x + c * y + e, b * x + d * y + f];
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        let (sin, cos) = angle.sin_cos();
+        let [a, b, c, d, e, f] = self.state.transform;
+        self.state.transform = [
+            a * cos + c * sin,
+            b * cos + d * sin,
+            c * cos - a * sin,
+            d * cos - b * sin,
+            e,
+            f,
+        ];
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str) {
+        let attributes = self.attributes();
+        writeln!(
+            self.elements,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\"{}/>",
+            round(x),
+            round(y),
+            round(width),
+            round(height),
+            fill,
+            stroke,
+            attributes
+        )
+        .unwrap();
+    }
+
+    fn circle(&mut self, x: f64, y: f64, radius: f64, fill: &str, stroke: &str) {
+        let attributes = self.attributes();
+        writeln!(
+            self.elements,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\"{}/>",
+            round(x),
+            round(y),
+            round(radius),
+            fill,
+            stroke,
+            attributes
+        )
+        .unwrap();
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: &str, width: f64) {
+        let attributes = self.attributes();
+        writeln!(
+            self.elements,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{}/>",
+            round(from.0),
+            round(from.1),
+            round(to.0),
+            round(to.1),
+            color,
+            round(width),
+            attributes
+        )
+        .unwrap();
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], fill: &str) {
+        let attributes = self.attributes();
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{},{}", round(*x), round(*y)))
+            .collect();
+        writeln!(
+            self.elements,
+            "<polygon points=\"{}\" fill=\"{}\"{}/>",
+            points.join(" "),
+            fill,
+            attributes
+        )
+        .unwrap();
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: Align, color: &str) {
+        let attributes = self.attributes();
+        let anchor = match align {
+            Align::Left => "start",
+            Align::Center => "middle",
+            Align::Right => "end",
+        };
+        writeln!(
+            self.elements,
+            "<text x=\"{}\" y=\"{}\" font-family=\"Arial\" font-size=\"{}\" text-anchor=\"{}\" \
+             dominant-baseline=\"middle\" fill=\"{}\"{}>{}</text>",
+            round(x),
+            round(y),
+            round(size),
+            anchor,
+            color,
+            attributes,
+            escape(text)
+        )
+        .unwrap();
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// A file that shows each frame in turn for the given number of seconds, over and over. Every
+// frame's hidden except for its own slice of the loop
+pub fn animate(width: f64, height: f64, frames: &[String], seconds_per_frame: f64) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    )
+    .unwrap();
+    let count = frames.len() as f64;
+    let duration = round(seconds_per_frame * count);
+    for (index, frame) in frames.iter().enumerate() {
+        // not rounded, since there can be thousands of frames
+        let start = index as f64 / count;
+        let end = (index + 1) as f64 / count;
+        // discrete animations hold each value from its key time until the next one
+        let (values, key_times) = match (index == 0, index + 1 == frames.len()) {
+            (true, true) => ("inline".to_string(), "0".to_string()),
+            (true, false) => ("inline;none".to_string(), format!("0;{}", end)),
+            (false, true) => ("none;inline".to_string(), format!("0;{}", start)),
+            (false, false) => (
+                "none;inline;none".to_string(),
+                format!("0;{};{}", start, end),
+            ),
+        };
+        writeln!(
+            svg,
+            "<g display=\"none\"><animate attributeName=\"display\" values=\"{}\" keyTimes=\"{}\" \
+             dur=\"{}s\" calcMode=\"discrete\" repeatCount=\"indefinite\"/>",
+            values, key_times, duration
+        )
+        .unwrap();
+        svg.push_str(frame);
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
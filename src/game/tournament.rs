@@ -0,0 +1,196 @@
+use super::ai::{AiPlayer, Difficulty};
+use super::autopilot::Autopilot;
+use super::controller::{Command, ControllerFactory};
+use super::neural::{Network, NeuralBot};
+use super::replay::Replay;
+use super::simulation::Settings;
+
+pub const ELO_START: f64 = 1500.; // everybody's rating before their first game
+const ELO_K: f64 = 32.; // how far one game can move a rating
+
+// a controller with a name people can ask for it by
+pub struct Bot {
+    pub name: String,
+    pub factory: ControllerFactory,
+}
+
+impl Bot {
+    pub fn new(name: &str, factory: ControllerFactory) -> Bot {
+        Bot {
+            name: name.to_string(),
+            factory,
+        }
+    }
+}
+
+pub fn builtin_bots() -> Vec<Bot> {
+    vec![
+        Bot::new(
+            "easy",
+            Box::new(|| Box::new(AiPlayer::new(Difficulty::Easy))),
+        ),
+        Bot::new(
+            "medium",
+            Box::new(|| Box::new(AiPlayer::new(Difficulty::Medium))),
+        ),
+        Bot::new(
+            "hard",
+            Box::new(|| Box::new(AiPlayer::new(Difficulty::Hard))),
+        ),
+        Bot::new("autopilot", Box::new(|| Box::new(Autopilot::new()))),
+        Bot::new(
+            "neural",
+            Box::new(|| Box::new(NeuralBot::new(Network::builtin()))),
+        ),
+    ]
+}
+
+#[derive(Clone)]
+pub struct GameResult {
+    pub scores: Vec<u32>,
+    pub lengths: Vec<usize>,
+    // how many updates each snake lasted
+    pub survived: Vec<u64>,
+    // which snakes count as having won. In versus that's at most one, and playing alone it's
+    // whether the board got cleared
+    pub won: Vec<bool>,
+    pub replay: Replay,
+}
+
+impl GameResult {
+    pub fn best_score(&self) -> u32 {
+        self.scores.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.replay.ticks.len()
+    }
+
+    // how a game between two went for the one in the first seat, the way update_elo wants it
+    pub fn first_seat_score(&self) -> f64 {
+        match (self.won.first(), self.won.get(1)) {
+            (Some(true), _) => 1.,
+            (_, Some(true)) => 0.,
+            _ => 0.5,
+        }
+    }
+}
+
+// which of bots a and b sits first in each of the games between them. They take turns, so
+// neither gets the better starting spot every time
+pub fn seats(a: usize, b: usize, game: u64) -> (usize, usize) {
+    if game.is_multiple_of(2) {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// plays one game to the end, or until max_ticks go by, with a snake for each bot
+pub fn play_game(settings: &Settings, seed: u64, bots: &[&Bot], max_ticks: u64) -> GameResult {
+    let mut settings = settings.clone();
+    settings.num_snakes = bots.len();
+    let names = bots.iter().map(|bot| bot.name.clone()).collect();
+    let mut replay = Replay::new(settings, seed, names);
+    let mut sim = replay.start();
+    let mut controllers: Vec<_> = bots.iter().map(|bot| (bot.factory)()).collect();
+    let mut survived = vec![0; sim.snakes.len()];
+
+    while !sim.is_game_over && sim.tick < max_ticks {
+        let commands: Vec<Command> = controllers
+            .iter_mut()
+            .enumerate()
+            .map(|(index, controller)| {
+                // a crowded board might not have had room for every snake
+                if sim.snakes.get(index).is_some_and(|snake| snake.is_alive) {
+                    controller.next_command(&sim.board_view(index))
+                } else {
+                    Command::Continue
+                }
+            })
+            .collect();
+        sim.step(&commands);
+        replay.record(&commands);
+
+        for (index, snake) in sim.snakes.iter().enumerate() {
+            if snake.is_alive {
+                survived[index] = sim.tick;
+            }
+        }
+    }
+
+    let won = if sim.snakes.len() > 1 {
+        let winner = sim.get_winner();
+        (0..sim.snakes.len())
+            .map(|index| winner == Some(index))
+            .collect()
+    } else {
+        vec![sim.did_win; sim.snakes.len()]
+    };
+
+    GameResult {
+        scores: sim.snakes.iter().map(|snake| snake.score).collect(),
+        lengths: sim.snakes.iter().map(|snake| snake.path.len()).collect(),
+        survived,
+        won,
+        replay,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BotStats {
+    pub games: u32,
+    pub wins: u32,
+    pub total_score: u64,
+    pub total_length: u64,
+    pub total_survived: u64,
+}
+
+impl BotStats {
+    // adds in how the snake at index did
+    pub fn record(&mut self, result: &GameResult, index: usize) {
+        self.games += 1;
+        if result.won[index] {
+            self.wins += 1;
+        }
+        self.total_score += result.scores[index] as u64;
+        self.total_length += result.lengths[index] as u64;
+        self.total_survived += result.survived[index];
+    }
+
+    fn mean(&self, total: u64) -> f64 {
+        if self.games == 0 {
+            0.
+        } else {
+            total as f64 / self.games as f64
+        }
+    }
+
+    pub fn mean_score(&self) -> f64 {
+        self.mean(self.total_score)
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.mean(self.total_length)
+    }
+
+    pub fn mean_survived(&self) -> f64 {
+        self.mean(self.total_survived)
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.mean(self.wins as u64)
+    }
+}
+
+// the chance a player rated a beats one rated b
+pub fn expected_score(a: f64, b: f64) -> f64 {
+    1. / (1. + 10f64.powf((b - a) / 400.))
+}
+
+// score is 1 if a won, 0 if b did, and 0.5 for a draw
+pub fn update_elo(a: &mut f64, b: &mut f64, score: f64) {
+    let change = ELO_K * (score - expected_score(*a, *b));
+    *a += change;
+    *b -= change;
+}
@@ -0,0 +1,37 @@
+use std::cmp::min;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector2D {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Vector2D {
+    // the board wraps around, so the shortest way between two squares might go off the edge
+    pub fn wrapped_distance(&self, other: &Vector2D, width: i32, height: i32) -> i32 {
+        let dx = (self.x - other.x).abs();
+        let dy = (self.y - other.y).abs();
+        min(dx, width - dx) + min(dy, height - dy)
+    }
+
+    pub fn neighbors(&self, width: i32, height: i32) -> [Vector2D; 4] {
+        [
+            Vector2D {
+                x: (self.x + 1).rem_euclid(width),
+                y: self.y,
+            },
+            Vector2D {
+                x: (self.x - 1).rem_euclid(width),
+                y: self.y,
+            },
+            Vector2D {
+                x: self.x,
+                y: (self.y + 1).rem_euclid(height),
+            },
+            Vector2D {
+                x: self.x,
+                y: (self.y - 1).rem_euclid(height),
+            },
+        ]
+    }
+}
@@ -0,0 +1,153 @@
+use super::replay::Replay;
+
+// A replay along with the score its player says they got. It's a replay file with extra lines
+// saying what the first snake's score was as of each tick it changed:
+//
+//   score 41 1           after 41 ticks the score was 1
+//   score 97 3
+//
+// and the last of them is the final score being claimed. Keeping the whole history rather than
+// just the end means a verifier can say exactly where a claim stops adding up.
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub replay: Replay,
+    // (tick, score) in tick order
+    pub scores: Vec<(u64, u32)>,
+}
+
+// a claim that checked out
+#[derive(Debug, Clone)]
+pub struct Verified {
+    pub score: u32,
+    pub ticks: u64,
+    pub is_game_over: bool,
+}
+
+// a claim that didn't, and the first tick where it went wrong, if there was one
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub tick: Option<u64>,
+    pub reason: String,
+}
+
+impl Submission {
+    // the honest claim for a replay, which is what a game records for itself as it's played
+    pub fn new(replay: Replay) -> Submission {
+        let mut sim = replay.start();
+        let mut scores = vec![];
+        let mut last_score = 0;
+        for commands in replay.ticks.iter() {
+            sim.step(commands);
+            let score = first_score(&sim);
+            if score != last_score {
+                scores.push((sim.tick, score));
+                last_score = score;
+            }
+        }
+        Submission { replay, scores }
+    }
+
+    pub fn claimed_score(&self) -> u32 {
+        self.scores.last().map(|(_, score)| *score).unwrap_or(0)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = self.replay.to_text();
+        for (tick, score) in self.scores.iter() {
+            text.push_str(&format!("score {} {}\n", tick, score));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Submission, String> {
+        let mut scores = vec![];
+        let mut replay_text = String::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if !line.starts_with("score ") {
+                replay_text.push_str(line);
+                replay_text.push('\n');
+                continue;
+            }
+
+            let error = || format!("line {}: expected a tick and a score", line_number + 1);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (tick, score) = match words.as_slice() {
+                [_, tick, score] => (
+                    tick.parse::<u64>().map_err(|_| error())?,
+                    score.parse::<u32>().map_err(|_| error())?,
+                ),
+                _ => return Err(error()),
+            };
+            if scores.last().is_some_and(|(last, _)| *last >= tick) {
+                return Err(format!(
+                    "line {}: scores have to be in tick order",
+                    line_number + 1
+                ));
+            }
+            scores.push((tick, score));
+            // keeps the replay's line numbers lined up with the file's
+            replay_text.push('\n');
+        }
+
+        Ok(Submission {
+            replay: Replay::parse(&replay_text)?,
+            scores,
+        })
+    }
+}
+
+// the score that counts, which for versus is the first player's
+fn first_score(sim: &super::simulation::Simulation) -> u32 {
+    sim.snakes.first().map(|snake| snake.score).unwrap_or(0)
+}
+
+// Plays a submission back from scratch and checks every claimed score against what really
+// happened. Anything longer than max_ticks is turned down without playing it, since it's
+// someone else's replay we're spending time on
+pub fn verify(submission: &Submission, max_ticks: u64) -> Result<Verified, Rejection> {
+    let replay = &submission.replay;
+    let num_ticks = replay.ticks.len() as u64;
+    if num_ticks > max_ticks {
+        return Err(Rejection {
+            tick: None,
+            reason: format!("{} ticks is more than the {} allowed", num_ticks, max_ticks),
+        });
+    }
+    if let Some((tick, _)) = submission.scores.iter().find(|(tick, _)| *tick > num_ticks) {
+        return Err(Rejection {
+            tick: Some(*tick),
+            reason: format!("claims a score but the replay stops at tick {}", num_ticks),
+        });
+    }
+
+    let mut sim = replay.start();
+    let mut claims = submission.scores.iter().peekable();
+    let mut claimed = 0;
+    for commands in replay.ticks.iter() {
+        if sim.is_game_over {
+            return Err(Rejection {
+                tick: Some(sim.tick),
+                reason: "the game was already over but the replay goes on".to_string(),
+            });
+        }
+        sim.step(commands);
+
+        if let Some((_, score)) = claims.next_if(|(tick, _)| *tick == sim.tick) {
+            claimed = *score;
+        }
+        let actual = first_score(&sim);
+        if actual != claimed {
+            return Err(Rejection {
+                tick: Some(sim.tick),
+                reason: format!("claims a score of {} but it was really {}", claimed, actual),
+            });
+        }
+    }
+
+    Ok(Verified {
+        score: claimed,
+        ticks: sim.tick,
+        is_game_over: sim.is_game_over,
+    })
+}
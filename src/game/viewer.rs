@@ -0,0 +1,126 @@
+use super::replay::Replay;
+use super::simulation::{Moment, Simulation};
+
+// how many updates apart the keyframes are, which is as far as a seek ever has to play
+const KEYFRAME_INTERVAL: u64 = 500;
+// how fast playback can go, as multiples of the game's usual pace
+const SPEEDS: [f64; 9] = [0.25, 0.5, 1., 2., 4., 8., 16., 32., 64.];
+const NORMAL_SPEED: usize = 2;
+// how far ahead the keyframes get each frame, so long replays open straight away
+const KEYFRAME_UPDATES_PER_FRAME: u64 = 200;
+
+// A replay being watched rather than played: it can be paused, stepped through an update at a
+// time either way, sped up and jumped around in. A second copy of the game plays through the
+// whole replay in the background, keeping a moment every so often, so jumping anywhere only has
+// to play on from the last keyframe before it
+pub struct Viewer {
+    pub replay: Replay,
+    keyframes: Vec<Moment>,
+    // how far the keyframes have got
+    scout: Simulation,
+    // where the game ended, which is where the replay does unless it carries on regardless
+    end: u64,
+    pub is_playing: bool,
+    speed: usize,
+    // how far we are towards the next update, in updates
+    progress: f64,
+}
+
+impl Viewer {
+    // the game to show it in starts as replay.start()
+    pub fn new(replay: Replay) -> Viewer {
+        Viewer {
+            scout: replay.start(),
+            end: replay.ticks.len() as u64,
+            replay,
+            keyframes: vec![],
+            is_playing: true,
+            speed: NORMAL_SPEED,
+            progress: 0.,
+        }
+    }
+
+    // the last update there is to see, as far as we know so far
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    // takes the keyframes up to a given update, or as far as the game goes
+    fn find_keyframes(&mut self, tick: u64) {
+        while self.scout.tick <= tick.min(self.end) {
+            if self.scout.tick.is_multiple_of(KEYFRAME_INTERVAL)
+                && self.keyframes.len() as u64 == self.scout.tick / KEYFRAME_INTERVAL
+            {
+                self.keyframes.push(self.scout.moment());
+            }
+            match self.replay.ticks.get(self.scout.tick as usize) {
+                Some(commands) if !self.scout.is_game_over => self.scout.step(commands),
+                _ => {
+                    self.end = self.scout.tick;
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn seek(&mut self, sim: &mut Simulation, tick: u64) {
+        self.find_keyframes(tick);
+        let tick = tick.min(self.end);
+        let keyframe = (tick / KEYFRAME_INTERVAL) as usize;
+        // going back, or a long way forward, starts from a keyframe instead of where we are
+        if tick < sim.tick || keyframe as u64 > sim.tick / KEYFRAME_INTERVAL {
+            if let Some(moment) = self.keyframes.get(keyframe) {
+                sim.restore(moment);
+            }
+        }
+        // the game can't be over before the end, or find_keyframes would have found it
+        while sim.tick < tick {
+            sim.step(&self.replay.ticks[sim.tick as usize]);
+        }
+    }
+
+    pub fn step_forward(&mut self, sim: &mut Simulation) {
+        self.is_playing = false;
+        self.seek(sim, sim.tick + 1);
+    }
+
+    pub fn step_back(&mut self, sim: &mut Simulation) {
+        self.is_playing = false;
+        self.seek(sim, sim.tick.saturating_sub(1));
+    }
+
+    // playing from the end starts over
+    pub fn toggle_playing(&mut self, sim: &mut Simulation) {
+        self.is_playing = !self.is_playing;
+        if self.is_playing && sim.tick == self.end {
+            self.seek(sim, 0);
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    // call once a frame, with how many updates a frame the game usually makes
+    pub fn advance(&mut self, sim: &mut Simulation, updates_per_frame: f64) {
+        self.find_keyframes(self.scout.tick + KEYFRAME_UPDATES_PER_FRAME);
+        if !self.is_playing {
+            return;
+        }
+        self.progress += updates_per_frame * self.speed();
+        let updates = self.progress.floor();
+        self.progress -= updates;
+        self.seek(sim, sim.tick + updates as u64);
+        if sim.tick == self.end {
+            self.is_playing = false;
+        }
+    }
+}
@@ -1,5 +1,6 @@
-mod game;
+pub mod game;
 use game::game::Game;
+use game::net::Netcode;
 
 use wasm_bindgen::prelude::*;
 
@@ -9,6 +10,65 @@ pub fn start() -> Result<(), JsValue> {
 
 	log::info!("starting...");
 	let game = Game::create(30, 20, 20.)?;
+
+	// index.html?server=ws://localhost:9001&room=lobby plays online, and adding
+	// &netcode=rollback makes games we host use rollback instead of lockstep.
+	// &broadcast=CHANNEL sends the game out for others to watch, on its own or along with
+	// a room, and &watch=CHANNEL watches it instead of playing
+	let search = web_sys::window().unwrap().location().search()?;
+	let params = web_sys::UrlSearchParams::new_with_str(&search)?;
+	if let Some(server) = params.get("server") {
+		if let Some(channel) = params.get("watch") {
+			game.watch(&server, &channel)?;
+		} else {
+			// broadcasting on its own plays locally, otherwise there's always a room
+			let broadcast = params.get("broadcast");
+			if broadcast.is_none() || params.get("room").is_some() {
+				let room = params.get("room").unwrap_or_else(|| "lobby".to_string());
+				let netcode = params
+					.get("netcode")
+					.and_then(|name| Netcode::parse(&name))
+					.unwrap_or(Netcode::Lockstep);
+				game.go_online(&server, &room, netcode)?;
+			}
+			if let Some(channel) = broadcast {
+				game.broadcast(&server, &channel)?;
+			}
+		}
+	}
+
+	// &leaderboard=http://localhost:9002&name=NAME sends games played alone to a leaderboard
+	// server, which lists them if their replays hold up
+	if let Some(url) = params.get("leaderboard") {
+		let name = params.get("name").unwrap_or_else(|| "anonymous".to_string());
+		game.use_leaderboard(&url, &name);
+	}
+
+	// &rewinds=N&rewind_penalty=N sets how many times a game can be rewound after dying, and
+	// how many points each one costs
+	let number = |name: &str| params.get(name).and_then(|value| value.parse::<u32>().ok());
+	if number("rewinds").is_some() || number("rewind_penalty").is_some() {
+		game.set_rewinds(
+			number("rewinds").unwrap_or(game::rewind::DEFAULT_REWINDS),
+			number("rewind_penalty").unwrap_or(game::rewind::DEFAULT_PENALTY),
+		);
+	}
+
+	// &daily starts on today's daily challenge
+	if params.has("daily") {
+		game.play_daily();
+	}
+
+	// &view=URL fetches a replay and watches it instead of playing
+	if let Some(url) = params.get("view") {
+		game.view(&url)?;
+	}
+
+	// &state=URL fetches a game written out as text, say a puzzle, and plays on from there
+	if let Some(url) = params.get("state") {
+		game.load_state(&url)?;
+	}
+
 	game.start()?;
 
 	Ok(())
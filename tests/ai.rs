@@ -0,0 +1,27 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::simulation::{Settings, Simulation};
+
+// the state of the board after the computer's played against itself for a while
+fn play(seed: u64) -> u64 {
+    let mut settings = Settings::new(20, 15);
+    settings.num_snakes = 2;
+    let mut sim = Simulation::new(settings, seed);
+    let mut players: Vec<AiPlayer> = (0..2).map(|_| AiPlayer::new(Difficulty::Easy)).collect();
+    while sim.tick < 300 && !sim.is_game_over {
+        let commands: Vec<Command> = players
+            .iter_mut()
+            .enumerate()
+            .map(|(index, player)| player.next_command(&sim.board_view(index)))
+            .collect();
+        sim.step(&commands);
+    }
+    sim.state_hash()
+}
+
+#[test]
+fn computer_players_play_the_same_game_from_the_same_seed() {
+    for seed in 0..5 {
+        assert_eq!(play(seed), play(seed), "seed {}", seed);
+    }
+}
@@ -0,0 +1,98 @@
+// Timed apples: how long they stick around, where they go after, and what they're worth
+use std::collections::VecDeque;
+
+use rusty_snake::game::controller::Command;
+use rusty_snake::game::simulation::{Apple, Settings, Simulation};
+use rusty_snake::game::snake::{self, Snake};
+use rusty_snake::game::vector::Vector2D;
+
+const RIGHT: Vector2D = Vector2D { x: 1, y: 0 };
+
+// a snake along the top row with its head at 3,0, heading right, and just the one apple
+fn game(width: i32, height: i32, apple: Apple) -> Simulation {
+    let mut settings = Settings::new(width, height);
+    settings.num_snakes = 0;
+    settings.num_apples = 1;
+    settings.apple_lifetime = apple.lifetime;
+    let mut sim = Simulation::new(settings, 3);
+    let mut snake = Snake::new(snake::players()[0], Vector2D { x: 3, y: 0 }, RIGHT);
+    snake.head_is_tail = false;
+    snake.path = VecDeque::from(vec![Vector2D { x: 3, y: 0 }, Vector2D { x: 2, y: 0 }]);
+    sim.snakes.push(snake);
+    sim.apples.clear();
+    sim.apples.push_back(apple);
+    sim
+}
+
+fn apple(x: i32, y: i32, lifetime: Option<u32>, age: u32) -> Apple {
+    Apple {
+        pos: Vector2D { x, y },
+        lifetime,
+        age,
+    }
+}
+
+fn step(sim: &mut Simulation) {
+    sim.step(&[Command::Continue]);
+}
+
+#[test]
+fn timed_apples_move_once_theyre_too_old() {
+    let start = Vector2D { x: 5, y: 5 };
+    let mut sim = game(10, 10, apple(start.x, start.y, Some(4), 0));
+    for age in 1..4 {
+        step(&mut sim);
+        assert_eq!(sim.apples.len(), 1);
+        assert_eq!(sim.apples[0].pos, start);
+        assert_eq!(sim.apples[0].age, age);
+        assert!(sim.apples[0].freshness() < 1.);
+    }
+
+    step(&mut sim);
+    assert_eq!(sim.apples.len(), 1);
+    assert_ne!(sim.apples[0].pos, start);
+    assert_eq!(sim.apples[0].age, 0);
+    assert_eq!(sim.apples[0].freshness(), 1.);
+}
+
+#[test]
+fn apples_without_a_lifetime_stay_put() {
+    let start = Vector2D { x: 5, y: 5 };
+    let mut sim = game(10, 10, apple(start.x, start.y, None, 0));
+    for _ in 0..(4 * 10) {
+        step(&mut sim);
+    }
+    assert_eq!(sim.apples[0].pos, start);
+    assert_eq!(sim.apples[0].freshness(), 1.);
+}
+
+#[test]
+fn old_apples_stay_when_theres_nowhere_else_to_go() {
+    // a board just long enough for the snake and the apple, with nowhere for the snake to go
+    let mut sim = game(5, 1, apple(0, 0, Some(2), 0));
+    sim.settings.level.walls = vec![Vector2D { x: 1, y: 0 }, Vector2D { x: 4, y: 0 }];
+    for _ in 0..5 {
+        step(&mut sim);
+        assert!(!sim.is_game_over);
+        assert_eq!(sim.apples.len(), 1);
+        assert_eq!(sim.apples[0].pos, Vector2D { x: 0, y: 0 });
+        assert!(sim.apples[0].age < 2);
+    }
+}
+
+// eats an apple right in front of it, which has already been there age updates
+fn score_for(lifetime: Option<u32>, age: u32) -> u32 {
+    let mut sim = game(10, 10, apple(4, 0, lifetime, age));
+    step(&mut sim);
+    assert_eq!(sim.snakes[0].head(), Vector2D { x: 4, y: 0 });
+    sim.snakes[0].score
+}
+
+#[test]
+fn timed_apples_are_worth_more_the_sooner_theyre_eaten() {
+    assert_eq!(score_for(None, 0), 1);
+    assert_eq!(score_for(Some(10), 0), 6);
+    assert_eq!(score_for(Some(10), 5), 4);
+    assert_eq!(score_for(Some(10), 8), 2);
+    assert_eq!(score_for(Some(10), 10), 1);
+}
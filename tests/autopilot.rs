@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+
+use rusty_snake::game::autopilot::Autopilot;
+use rusty_snake::game::controller::SnakeController;
+use rusty_snake::game::simulation::{Settings, Simulation};
+use rusty_snake::game::vector::Vector2D;
+
+const WIDTH: i32 = 10;
+const HEIGHT: i32 = 8;
+// far more than filling the board could ever take, even going all the way round for each apple
+const MAX_TICKS: u64 = (WIDTH * HEIGHT * WIDTH * HEIGHT) as u64;
+
+fn game(seed: u64) -> Simulation {
+    let mut settings = Settings::new(WIDTH, HEIGHT);
+    settings.num_apples = 1;
+    Simulation::new(settings, seed)
+}
+
+fn play(sim: &mut Simulation) {
+    let mut player = Autopilot::new();
+    while !sim.is_game_over && sim.tick < MAX_TICKS {
+        let command = player.next_command(&sim.board_view(0));
+        sim.step(&[command]);
+    }
+}
+
+fn assert_filled(sim: &Simulation) {
+    assert!(sim.did_win, "died at tick {}", sim.tick);
+    assert_eq!(sim.snakes[0].path.len(), (WIDTH * HEIGHT) as usize);
+}
+
+#[test]
+fn autopilot_fills_the_board() {
+    for seed in 0..3 {
+        let mut sim = game(seed);
+        play(&mut sim);
+        assert_filled(&sim);
+    }
+}
+
+// Whoever was playing before didn't keep to the cycle, which goes up and down the columns, so
+// the body starts off lying across them
+#[test]
+fn autopilot_takes_over_from_a_body_off_the_cycle() {
+    let bodies: [&[(i32, i32)]; 2] = [
+        &[(8, 4), (7, 4), (6, 4), (5, 4), (4, 4), (3, 4), (2, 4)],
+        &[
+            (3, 2),
+            (4, 2),
+            (5, 2),
+            (6, 2),
+            (6, 3),
+            (6, 4),
+            (5, 4),
+            (4, 4),
+        ],
+    ];
+    for (seed, body) in bodies.iter().enumerate() {
+        let mut sim = game(seed as u64);
+        let path: VecDeque<Vector2D> = body.iter().map(|&(x, y)| Vector2D { x, y }).collect();
+        let snake = &mut sim.snakes[0];
+        snake.head_direction = Vector2D {
+            x: path[0].x - path[1].x,
+            y: path[0].y - path[1].y,
+        };
+        snake.head_is_tail = false;
+        snake.path = path;
+        // the apple from the start could be under the body now, so it gets put somewhere new
+        sim.apples.clear();
+        play(&mut sim);
+        assert_filled(&sim);
+    }
+}
@@ -0,0 +1,49 @@
+use rusty_snake::game::daily::Challenge;
+use rusty_snake::game::replay::Replay;
+
+// The challenges for these days, as the browser and the leaderboard server both have to come up
+// with them. If these change, everyone's daily scores end up on different boards
+#[test]
+fn challenges_stay_the_same() {
+    let settings = |day: u64| {
+        let challenge = Challenge::for_day(day);
+        let text = Replay::new(challenge.settings, challenge.seed, vec![]).to_text();
+        // the level's walls and apples are checked by its name
+        text.lines()
+            .filter(|line| !line.starts_with("wall ") && !line.starts_with("apple "))
+            .collect::<Vec<&str>>()
+            .join(" ")
+    };
+
+    assert_eq!(Challenge::for_day(20_745).date(), "2026-10-19");
+    assert_eq!(
+        settings(20_745),
+        "board 24 16 apples 3 lifetime 150 spawn 2 rules die die both-die level empty seed \
+         13978751693354507917"
+    );
+    assert_eq!(
+        settings(20_746),
+        "board 20 20 apples 2 lifetime 150 spawn 3 rules block die both-die level empty seed \
+         4551287892486467812"
+    );
+    assert_eq!(
+        settings(20_760),
+        "board 30 20 apples 3 lifetime none spawn 4 rules block die both-die level arena seed \
+         4775774949127109695"
+    );
+}
+
+#[test]
+fn replays_of_the_challenge_match_it() {
+    let challenge = Challenge::for_day(20_760);
+    let mut replay = Replay::new(
+        challenge.settings.clone(),
+        challenge.seed,
+        vec!["me".to_string()],
+    );
+    assert!(challenge.matches(&replay));
+    assert!(!Challenge::for_day(20_761).matches(&replay));
+
+    replay.seed += 1;
+    assert!(!challenge.matches(&replay));
+}
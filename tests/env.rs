@@ -0,0 +1,21 @@
+use rusty_snake::game::env::{Env, EnvConfig};
+
+#[test]
+fn boards_with_no_squares_are_turned_down() {
+    for &(width, height) in [(0, 20), (30, 0), (-3, 20), (30, -1), (100_000, 20)].iter() {
+        assert!(
+            EnvConfig::new(width, height).is_err(),
+            "{}x{}",
+            width,
+            height
+        );
+    }
+}
+
+#[test]
+fn the_smallest_board_still_plays() {
+    let mut env = Env::new(EnvConfig::new(1, 1).unwrap());
+    let observation = env.reset(7);
+    assert_eq!(observation.len(), env.observation_len());
+    env.step(0);
+}
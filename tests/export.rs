@@ -0,0 +1,149 @@
+// Checks what the exporters actually put out: the pixels the software renderer fills in, and
+// the SVG that snake_export writes, for a small board with a bit of everything on it
+use std::collections::{HashSet, VecDeque};
+
+use rusty_snake::game::raster::Pixmap;
+use rusty_snake::game::render::{self, APPLE_COLOR, BACKGROUND_COLOR, WALL_COLOR};
+use rusty_snake::game::simulation::{Apple, Settings, Simulation};
+use rusty_snake::game::snake::{self, Snake};
+use rusty_snake::game::svg::{self, Svg};
+use rusty_snake::game::vector::Vector2D;
+
+const RECT_SIZE: f64 = 20.;
+
+// A 6x4 board with a wall at 0,0, an apple at 5,3 and a snake heading right along the second
+// row, with its head at 3,1 and its tail at 0,1
+fn board(is_alive: bool) -> Simulation {
+    let mut settings = Settings::new(6, 4);
+    settings.num_snakes = 0;
+    settings.num_apples = 0;
+    settings.level.walls = vec![Vector2D { x: 0, y: 0 }];
+    let mut sim = Simulation::new(settings, 0);
+    sim.apples.push_back(Apple {
+        pos: Vector2D { x: 5, y: 3 },
+        lifetime: None,
+        age: 0,
+    });
+
+    let path: VecDeque<Vector2D> = (0..4).rev().map(|x| Vector2D { x, y: 1 }).collect();
+    let mut snake = Snake::new(snake::players()[0], path[0], Vector2D { x: 1, y: 0 });
+    snake.head_is_tail = false;
+    snake.path = path;
+    snake.is_alive = is_alive;
+    sim.snakes.push(snake);
+    sim
+}
+
+fn draw(sim: &Simulation) -> Pixmap {
+    let mut pixmap = Pixmap::new(6 * RECT_SIZE as u32, 4 * RECT_SIZE as u32);
+    render::draw_frame(&mut pixmap, sim, RECT_SIZE);
+    pixmap
+}
+
+// a pixel near the top left of a square, clear of its outline and of anything drawn over the
+// middle of it
+fn corner_of(pixmap: &Pixmap, x: u32, y: u32) -> [u8; 3] {
+    let size = RECT_SIZE as u32;
+    pixmap.pixel(x * size + 3, y * size + 3)
+}
+
+fn middle_of(pixmap: &Pixmap, x: u32, y: u32) -> [u8; 3] {
+    let size = RECT_SIZE as u32;
+    pixmap.pixel(x * size + size / 2, y * size + size / 2)
+}
+
+fn color(name: &str) -> [u8; 3] {
+    render::parse_color(name).unwrap()
+}
+
+#[test]
+fn pictures_show_whats_on_the_board() {
+    let pixmap = draw(&board(true));
+    let colors = snake::players()[0].colors;
+    assert_eq!(corner_of(&pixmap, 4, 2), color(BACKGROUND_COLOR));
+    assert_eq!(corner_of(&pixmap, 0, 0), color(WALL_COLOR));
+    assert_eq!(middle_of(&pixmap, 5, 3), color(APPLE_COLOR));
+    assert_eq!(corner_of(&pixmap, 3, 1), color(colors.head));
+    assert_eq!(corner_of(&pixmap, 2, 1), color(colors.body));
+    assert_eq!(corner_of(&pixmap, 0, 1), color(colors.tail));
+    // the line joining the body up runs through the middle
+    assert_eq!(middle_of(&pixmap, 2, 1), color("white"));
+}
+
+#[test]
+fn dead_snakes_are_see_through() {
+    let pixmap = draw(&board(false));
+    let [r, g, b] = color(snake::players()[0].colors.body);
+    let faded = |value: u8| (value as f64 * render::DEAD_SNAKE_ALPHA).round() as u8;
+    assert_eq!(corner_of(&pixmap, 2, 1), [faded(r), faded(g), faded(b)]);
+}
+
+#[test]
+fn svg_frames_have_a_shape_for_everything() {
+    let sim = board(true);
+    let mut svg = Svg::new();
+    render::draw_frame(&mut svg, &sim, RECT_SIZE);
+    render::draw_banner(&mut svg, 120., 80., "<you & me>");
+    let elements = svg.into_elements();
+
+    assert!(elements.starts_with(&format!(
+        "<rect x=\"0\" y=\"0\" width=\"120\" height=\"80\" fill=\"{}\"",
+        BACKGROUND_COLOR
+    )));
+    assert!(elements.contains(&format!(
+        "<rect x=\"0\" y=\"0\" width=\"20\" height=\"20\" fill=\"{}\"",
+        WALL_COLOR
+    )));
+    assert!(elements.contains(&format!(
+        "<circle cx=\"110\" cy=\"70\" r=\"8\" fill=\"{}\"",
+        APPLE_COLOR
+    )));
+    // one square for each part of the snake, and one more each for its head and tail
+    let colors = snake::players()[0].colors;
+    let squares = [colors.body, colors.head, colors.tail]
+        .iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|color| {
+            elements
+                .matches(&format!("height=\"20\" fill=\"{}\"", color))
+                .count()
+        })
+        .sum::<usize>();
+    assert_eq!(squares, 6);
+    assert!(elements.contains(">&lt;you &amp; me&gt;</text>"));
+}
+
+#[test]
+fn animations_show_each_frame_in_turn() {
+    let frames: Vec<String> = (0..3)
+        .map(|index| format!("<i n=\"{}\"/>", index))
+        .collect();
+    let text = svg::animate(120., 80., &frames, 0.075);
+    assert!(text.starts_with(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"80\" \
+         viewBox=\"0 0 120 80\">"
+    ));
+    assert!(text.ends_with("</svg>\n"));
+    assert_eq!(text.matches("<g display=\"none\">").count(), 3);
+    assert_eq!(text.matches("dur=\"0.225s\"").count(), 3);
+
+    let third = 1. / 3.;
+    let two_thirds = 2. / 3.;
+    let key_times = [
+        format!("values=\"inline;none\" keyTimes=\"0;{}\"", third),
+        format!(
+            "values=\"none;inline;none\" keyTimes=\"0;{};{}\"",
+            third, two_thirds
+        ),
+        format!("values=\"none;inline\" keyTimes=\"0;{}\"", two_thirds),
+    ];
+    for (index, key_times) in key_times.iter().enumerate() {
+        let group = text.find(key_times).unwrap();
+        let frame = text.find(&frames[index]).unwrap();
+        assert!(group < frame);
+    }
+
+    let single = svg::animate(120., 80., &frames[..1], 0.075);
+    assert!(single.contains("values=\"inline\" keyTimes=\"0\""));
+}
@@ -0,0 +1,70 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::SnakeController;
+use rusty_snake::game::ghost::Ghost;
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::simulation::{Settings, Simulation};
+use rusty_snake::game::verify::Submission;
+
+const TICKS: u64 = 200;
+
+// a personal best the computer set
+fn best() -> Submission {
+    let mut replay = Replay::new(Settings::new(12, 10), 8, vec!["hard ai".to_string()]);
+    let mut sim = replay.start();
+    let mut player = AiPlayer::new(Difficulty::Hard);
+    while sim.tick < TICKS && !sim.is_game_over {
+        let commands = vec![player.next_command(&sim.board_view(0))];
+        replay.record(&commands);
+        sim.step(&commands);
+    }
+    Submission::new(replay)
+}
+
+// the best run as it was at the given update
+fn at(best: &Submission, tick: u64) -> Simulation {
+    let mut sim = best.replay.start();
+    for commands in best.replay.ticks.iter().take(tick as usize) {
+        sim.step(commands);
+    }
+    sim
+}
+
+fn assert_matches(ghost: &Ghost, best: &Submission, tick: u64) {
+    let sim = at(best, tick);
+    let snake = ghost.snake().unwrap();
+    assert_eq!(snake.path, sim.snakes[0].path, "tick {}", tick);
+    assert_eq!(ghost.score(), sim.snakes[0].score, "tick {}", tick);
+}
+
+#[test]
+fn ghosts_keep_up_with_the_game() {
+    let best = best();
+    let mut ghost = Ghost::new(best.clone());
+    assert_eq!(ghost.best_score, best.claimed_score());
+    assert!(ghost.best_score > 0);
+    for tick in 0..TICKS {
+        ghost.follow(tick);
+        assert_matches(&ghost, &best, tick);
+    }
+    assert_eq!(ghost.score(), at(&best, TICKS).snakes[0].score);
+}
+
+#[test]
+fn ghosts_start_over_when_the_game_goes_back() {
+    let best = best();
+    let mut ghost = Ghost::new(best.clone());
+    ghost.follow(150);
+    ghost.follow(40);
+    assert_matches(&ghost, &best, 40);
+    // and skip ahead when it jumps forward
+    ghost.follow(120);
+    assert_matches(&ghost, &best, 120);
+}
+
+#[test]
+fn ghosts_stop_where_the_best_run_did() {
+    let best = best();
+    let mut ghost = Ghost::new(best.clone());
+    ghost.follow(TICKS * 3);
+    assert_matches(&ghost, &best, TICKS);
+}
@@ -0,0 +1,90 @@
+use rusty_snake::game::leaderboard::{self, Entry, Leaderboard, Period};
+use rusty_snake::game::level::Level;
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::simulation::Settings;
+
+// midnight UTC at the start of Monday the 19th of October 2026
+const MONDAY: u64 = 1_792_368_000;
+const DAY: u64 = leaderboard::SECONDS_PER_DAY;
+
+#[test]
+fn periods_start_at_midnight_and_weeks_on_monday() {
+    let wednesday_noon = MONDAY + 2 * DAY + DAY / 2;
+    assert_eq!(Period::Daily.start(wednesday_noon), MONDAY + 2 * DAY);
+    assert_eq!(Period::Weekly.start(wednesday_noon), MONDAY);
+    assert_eq!(Period::Weekly.start(MONDAY), MONDAY);
+    // the last second of Sunday is still the week before
+    assert_eq!(Period::Weekly.start(MONDAY + 7 * DAY - 1), MONDAY);
+    assert_eq!(Period::Weekly.start(MONDAY + 7 * DAY), MONDAY + 7 * DAY);
+    assert_eq!(Period::Weekly.start(MONDAY - 1), MONDAY - 7 * DAY);
+    assert_eq!(Period::AllTime.start(wednesday_noon), 0);
+}
+
+fn entry(time: u64, score: u32, ticks: u64, name: &str) -> Entry {
+    Entry {
+        time,
+        board: "30x20".to_string(),
+        score,
+        ticks,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn top_lists_everyone_once_with_their_best() {
+    let leaderboard = Leaderboard {
+        entries: vec![
+            entry(MONDAY - DAY, 50, 900, "old"),
+            entry(MONDAY + 10, 12, 300, "ann"),
+            entry(MONDAY + 20, 20, 500, "ann"),
+            entry(MONDAY + 30, 20, 400, "bob"),
+            entry(MONDAY + 40, 20, 400, "cat"),
+            entry(MONDAY + 50, 5, 100, "dan"),
+            Entry {
+                board: "20x20".to_string(),
+                ..entry(MONDAY + 60, 99, 100, "eve")
+            },
+        ],
+    };
+    let names = |period: Period, limit: usize| -> Vec<&str> {
+        leaderboard
+            .top("30x20", period, MONDAY + DAY, limit)
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    };
+
+    // ties go to fewer updates, then whoever got there first
+    assert_eq!(names(Period::Weekly, 10), ["bob", "cat", "ann", "dan"]);
+    assert_eq!(
+        names(Period::AllTime, 10),
+        ["old", "bob", "cat", "ann", "dan"]
+    );
+    assert_eq!(names(Period::Weekly, 2), ["bob", "cat"]);
+    assert_eq!(leaderboard.rank(&entry(MONDAY + 20, 20, 500, "ann")), 4);
+    assert_eq!(leaderboard.rank(&entry(MONDAY, 1, 1, "new")), 6);
+}
+
+#[test]
+fn entries_round_trip() {
+    let entry = entry(MONDAY, 7, 250, "two words");
+    assert_eq!(Entry::parse(&entry.to_text()), Ok(entry));
+}
+
+#[test]
+fn only_boards_the_game_is_played_on_count() {
+    let replay = |width, height, level: &str| {
+        let mut settings = Settings::new(width, height);
+        settings.level = Level::find_builtin(level).unwrap();
+        Replay::new(settings, 1, vec!["me".to_string()])
+    };
+
+    let board = leaderboard::board_for(&replay(30, 20, "arena"), MONDAY).unwrap();
+    assert!(board.starts_with("30x20-"), "{}", board);
+    assert!(leaderboard::board_for(&replay(200, 200, "empty"), MONDAY).is_err());
+    assert!(leaderboard::board_for(&replay(31, 20, "empty"), MONDAY).is_err());
+
+    let mut walled_off = replay(30, 20, "arena");
+    walled_off.settings.level.walls.pop();
+    assert!(leaderboard::board_for(&walled_off, MONDAY).is_err());
+}
@@ -0,0 +1,22 @@
+use rusty_snake::game::level::Level;
+use rusty_snake::game::simulation::Settings;
+
+#[test]
+fn built_in_levels_fit_the_default_board() {
+    for level in Level::builtin() {
+        assert_eq!(level.check_fits(30, 20), Ok(()), "{}", level.name);
+    }
+}
+
+#[test]
+fn levels_that_go_off_the_board_are_turned_down() {
+    let mut settings = Settings::new(10, 10);
+    settings.level = Level::find_builtin("arena").unwrap();
+    assert_eq!(
+        settings.check(),
+        Err("level arena has wall 10 0, off the edge of a 10x10 board".to_string())
+    );
+
+    settings.level = Level::parse("apples", "apple 3 12").unwrap();
+    assert!(settings.check().unwrap_err().contains("apple 3 12"));
+}
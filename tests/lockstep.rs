@@ -0,0 +1,165 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::lockstep::{HashLog, Lockstep};
+use rusty_snake::game::net::Message;
+use rusty_snake::game::simulation::{Settings, Simulation};
+
+const LATENCY: u64 = 2; // how many updates every message takes to get to the other player
+const MAX_TICKS: u64 = 300;
+
+struct Peer {
+    lockstep: Lockstep,
+    sim: Simulation,
+    player: AiPlayer,
+}
+
+// Plays a two player game with lockstep, passing every message across as text a few updates
+// late, and gives back both players' games once they're done
+fn play(seed: u64, input_delay: u64) -> Vec<Peer> {
+    let mut settings = Settings::new(20, 14);
+    settings.num_snakes = 2;
+    let mut peers: Vec<Peer> = (0..2)
+        .map(|me| Peer {
+            lockstep: Lockstep::new(me, 2, input_delay),
+            sim: Simulation::new(settings.clone(), seed),
+            player: AiPlayer::new(Difficulty::Hard),
+        })
+        .collect();
+    // (arrival, to, text)
+    let mut in_flight: Vec<(u64, usize, String)> = vec![];
+
+    let mut updates = 0;
+    while peers
+        .iter()
+        .any(|peer| !peer.sim.is_game_over && peer.sim.tick < MAX_TICKS)
+    {
+        assert!(updates < MAX_TICKS * 10, "the game got stuck");
+        let (arrived, waiting) = in_flight
+            .drain(..)
+            .partition(|(arrival, _, _)| *arrival <= updates);
+        in_flight = waiting;
+        for (_, to, text) in arrived {
+            peers[to].lockstep.receive(&Message::parse(&text).unwrap());
+        }
+
+        for (me, peer) in peers.iter_mut().enumerate() {
+            let mut messages = vec![];
+            let tick = peer.sim.tick;
+            if !peer.sim.is_game_over && tick < MAX_TICKS && peer.lockstep.wants_input(tick) {
+                let command = peer.player.next_command(&peer.sim.board_view(me));
+                messages.extend(peer.lockstep.send(tick, command));
+            }
+            if !peer.sim.is_game_over && tick < MAX_TICKS {
+                if let Some(commands) = peer.lockstep.take_commands(tick) {
+                    peer.sim.step(&commands);
+                    let hash = peer.sim.state_hash();
+                    messages.extend(peer.lockstep.record_hash(peer.sim.tick, hash));
+                }
+            }
+            for message in messages {
+                in_flight.push((updates + LATENCY, 1 - me, message.to_text()));
+            }
+        }
+        updates += 1;
+    }
+
+    // let the last hashes arrive
+    for (_, to, text) in in_flight {
+        peers[to].lockstep.receive(&Message::parse(&text).unwrap());
+    }
+    peers
+}
+
+#[test]
+fn both_players_play_out_the_same_game() {
+    for &input_delay in [0, 1, 3].iter() {
+        let peers = play(7, input_delay);
+        assert!(
+            peers[0].sim.tick >= 100,
+            "only got to tick {}",
+            peers[0].sim.tick
+        );
+        assert_eq!(peers[0].sim.tick, peers[1].sim.tick);
+        assert_eq!(peers[0].sim.state_hash(), peers[1].sim.state_hash());
+        for peer in peers.iter() {
+            assert_eq!(peer.lockstep.hashes.desync, None);
+        }
+    }
+}
+
+#[test]
+fn only_every_tenth_tick_gets_hashed() {
+    let mut hashes = HashLog::new(0);
+    assert!(hashes.record(7, 1).is_none());
+    match hashes.record(20, 1) {
+        Some(Message::Hash { slot, tick, hash }) => assert_eq!((slot, tick, hash), (0, 20, 1)),
+        other => panic!("expected a hash, not {:?}", other),
+    }
+}
+
+#[test]
+fn hashes_that_disagree_are_a_desync() {
+    // theirs arriving after ours
+    let mut hashes = HashLog::new(0);
+    hashes.record(10, 1);
+    hashes.record(20, 2);
+    hashes.receive(&Message::Hash {
+        slot: 1,
+        tick: 10,
+        hash: 1,
+    });
+    hashes.receive(&Message::Hash {
+        slot: 1,
+        tick: 20,
+        hash: 3,
+    });
+    assert_eq!(hashes.desync, Some(20));
+
+    // and before
+    let mut hashes = HashLog::new(0);
+    hashes.receive(&Message::Hash {
+        slot: 1,
+        tick: 30,
+        hash: 3,
+    });
+    hashes.record(30, 2);
+    assert_eq!(hashes.desync, Some(30));
+
+    // our own hashes coming back from the server don't count
+    let mut hashes = HashLog::new(0);
+    hashes.record(10, 1);
+    hashes.receive(&Message::Hash {
+        slot: 0,
+        tick: 10,
+        hash: 5,
+    });
+    assert_eq!(hashes.desync, None);
+}
+
+#[test]
+fn missed_ticks_keep_going_the_same_way() {
+    let mut lockstep = Lockstep::new(0, 1, 2);
+    // nobody gets a say over the first input_delay ticks
+    assert_eq!(lockstep.take_commands(0), Some(vec![Command::Continue]));
+    assert!(lockstep.wants_input(0));
+
+    // falling behind a few ticks fills them in with Continue
+    let messages = lockstep.send(3, Command::Up);
+    assert_eq!(messages.len(), 4);
+    assert_eq!(lockstep.ready_ticks(1), 5);
+    assert!(!lockstep.wants_input(3));
+    let commands: Vec<Command> = (1..6)
+        .map(|tick| lockstep.take_commands(tick).unwrap()[0])
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            Command::Continue,
+            Command::Continue,
+            Command::Continue,
+            Command::Continue,
+            Command::Up
+        ]
+    );
+    assert_eq!(lockstep.take_commands(6), None);
+}
@@ -0,0 +1,206 @@
+// Golden tests for the drawing code the browser and the exporters share. Each one draws through
+// a Recorder and compares what was drawn with tests/golden/NAME.txt. After a change that's meant
+// to draw things differently, run them with UPDATE_GOLDEN=1 to write the files afresh, and check
+// the diff
+use std::collections::VecDeque;
+use std::{env, fs, path::PathBuf};
+
+use rusty_snake::game::level::Level;
+use rusty_snake::game::record::{DrawCall, Recorder};
+use rusty_snake::game::render::{self, Grid};
+use rusty_snake::game::simulation::{Apple, Settings, Simulation};
+use rusty_snake::game::snake::{self, Snake};
+use rusty_snake::game::vector::Vector2D;
+
+const RECT_SIZE: f64 = 20.;
+
+fn check(name: &str, recorder: &Recorder) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.txt", name));
+    let actual = recorder.to_text();
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("there's no {}, UPDATE_GOLDEN=1 writes it", path.display()));
+    if actual == expected {
+        return;
+    }
+    let mismatch = actual
+        .lines()
+        .zip(expected.lines())
+        .position(|(actual, expected)| actual != expected)
+        .unwrap_or_else(|| actual.lines().count().min(expected.lines().count()));
+    panic!(
+        "{} doesn't match {} from line {}:\n  expected: {}\n  drew:     {}",
+        name,
+        path.display(),
+        mismatch + 1,
+        expected.lines().nth(mismatch).unwrap_or("(nothing)"),
+        actual.lines().nth(mismatch).unwrap_or("(nothing)")
+    );
+}
+
+fn grid(width: i32, height: i32) -> Grid {
+    Grid::new(&Settings::new(width, height), RECT_SIZE)
+}
+
+// a snake along the given squares, head first
+fn snake(squares: &[(i32, i32)], direction: (i32, i32)) -> Snake {
+    let path: VecDeque<Vector2D> = squares.iter().map(|&(x, y)| Vector2D { x, y }).collect();
+    let mut snake = Snake::new(
+        snake::players()[0],
+        path[0],
+        Vector2D {
+            x: direction.0,
+            y: direction.1,
+        },
+    );
+    snake.head_is_tail = false;
+    snake.path = path;
+    snake
+}
+
+fn draw_snake(width: i32, height: i32, snake: &Snake) -> Recorder {
+    let mut recorder = Recorder::new();
+    grid(width, height).draw_snake(&mut recorder, snake);
+    recorder
+}
+
+#[test]
+fn snake_wraps_off_the_right() {
+    let snake = snake(&[(1, 2), (0, 2), (5, 2), (4, 2)], (1, 0));
+    check("wraps_right", &draw_snake(6, 4, &snake));
+}
+
+#[test]
+fn snake_wraps_off_the_left() {
+    let snake = snake(&[(4, 1), (5, 1), (0, 1), (1, 1)], (-1, 0));
+    check("wraps_left", &draw_snake(6, 4, &snake));
+}
+
+#[test]
+fn snake_wraps_off_the_bottom() {
+    let snake = snake(&[(2, 1), (2, 0), (2, 3), (2, 2)], (0, 1));
+    check("wraps_bottom", &draw_snake(6, 4, &snake));
+}
+
+#[test]
+fn snake_wraps_off_the_top() {
+    let snake = snake(&[(3, 2), (3, 3), (3, 0), (3, 1)], (0, -1));
+    check("wraps_top", &draw_snake(6, 4, &snake));
+}
+
+// wherever a snake goes, the lines joining up its squares only ever go from one square to the
+// next, even when it goes off an edge and comes back on the other side
+#[test]
+fn connectors_only_join_neighbours() {
+    let (width, height): (i32, i32) = (5, 4);
+    for start in 0..width * height {
+        for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            let squares: Vec<(i32, i32)> = (0..7)
+                .map(|step| {
+                    let x = (start % width - dx * step).rem_euclid(width);
+                    let y = (start / width - dy * step).rem_euclid(height);
+                    (x, y)
+                })
+                .collect();
+            let recorder = draw_snake(width, height, &snake(&squares, (dx, dy)));
+            let lines: Vec<&DrawCall> = recorder
+                .calls
+                .iter()
+                .filter(|call| matches!(call, DrawCall::Line { .. }))
+                .collect();
+            let crossings = squares
+                .windows(2)
+                .filter(|pair| (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs() > 1)
+                .count();
+            assert_eq!(lines.len(), squares.len() - 1 + crossings, "{:?}", squares);
+            for call in lines {
+                if let DrawCall::Line { from, to, .. } = call {
+                    let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+                    assert!(
+                        (length - RECT_SIZE).abs() < 1e-9,
+                        "{:?} is {} long, for {:?}",
+                        call,
+                        length,
+                        squares
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn heads_point_the_way_they_are_going() {
+    let mut recorder = Recorder::new();
+    let grid = grid(6, 4);
+    for (index, &direction) in [(0, -1), (1, 0), (0, 1), (-1, 0)].iter().enumerate() {
+        grid.draw_snake(&mut recorder, &snake(&[(index as i32, 1)], direction));
+    }
+    check("heads", &recorder);
+}
+
+#[test]
+fn dead_snakes_and_fading_apples() {
+    let mut dead = snake(&[(2, 2), (1, 2), (0, 2)], (1, 0));
+    dead.is_alive = false;
+    let apples: Vec<Apple> = [(0, 10), (1, 30), (2, 0)]
+        .iter()
+        .enumerate()
+        .map(|(index, &(age, lifetime))| Apple {
+            pos: Vector2D {
+                x: index as i32,
+                y: 0,
+            },
+            lifetime: if lifetime == 0 { None } else { Some(lifetime) },
+            age,
+        })
+        .collect();
+
+    let mut recorder = Recorder::new();
+    let grid = grid(6, 4);
+    grid.draw_apples(&mut recorder, apples.iter());
+    grid.draw_snake(&mut recorder, &dead);
+    check("dead_and_fading", &recorder);
+}
+
+#[test]
+fn grid_lines() {
+    let mut recorder = Recorder::new();
+    grid(3, 2).draw_grid_lines(&mut recorder, "grey");
+    check("grid_lines", &recorder);
+}
+
+#[test]
+fn whole_frame() {
+    let mut settings = Settings::new(8, 6);
+    settings.num_apples = 2;
+    settings.level = Level::parse("box", "wall 0 0 7 0\nwall 3 3").unwrap();
+    let sim = Simulation::new(settings, 7);
+    let mut recorder = Recorder::new();
+    render::draw_frame(&mut recorder, &sim, RECT_SIZE);
+    check("frame", &recorder);
+}
+
+#[test]
+fn banners() {
+    let mut recorder = Recorder::new();
+    render::draw_banner(&mut recorder, 600., 400., "PAUSED");
+    render::draw_results(
+        &mut recorder,
+        600.,
+        400.,
+        "GAME OVER",
+        &[
+            ("backspace to rewind".to_string(), "white"),
+            ("PLAYER 1    score 12".to_string(), "lime"),
+        ],
+    );
+    check("banners", &recorder);
+}
@@ -0,0 +1,107 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::rewind::Rewind;
+use rusty_snake::game::simulation::{Settings, Simulation, APPLE_LIFETIME};
+
+const TICKS: usize = 150;
+
+fn settings() -> Settings {
+    let mut settings = Settings::new(16, 12);
+    settings.num_snakes = 2;
+    settings.apple_lifetime = Some(APPLE_LIFETIME / 10);
+    settings
+}
+
+// two computer players going at it for a while, with every update recorded for rewinding
+fn play(rewind: &mut Rewind) -> (Simulation, Vec<Vec<Command>>) {
+    let mut sim = Simulation::new(settings(), 5);
+    let mut players: Vec<AiPlayer> = (0..2).map(|_| AiPlayer::new(Difficulty::Hard)).collect();
+    let mut ticks = vec![];
+    while ticks.len() < TICKS && !sim.is_game_over {
+        let commands: Vec<Command> = players
+            .iter_mut()
+            .enumerate()
+            .map(|(index, player)| {
+                if sim.snakes[index].is_alive {
+                    player.next_command(&sim.board_view(index))
+                } else {
+                    Command::Continue
+                }
+            })
+            .collect();
+        rewind.record(&sim);
+        sim.step(&commands);
+        ticks.push(commands);
+    }
+    assert_eq!(ticks.len(), TICKS, "the game ended early");
+    (sim, ticks)
+}
+
+// the same game played from the start, up to the given update
+fn replayed(ticks: &[Vec<Command>], to: usize) -> Simulation {
+    let mut sim = Simulation::new(settings(), 5);
+    for commands in ticks[..to].iter() {
+        sim.step(commands);
+    }
+    sim
+}
+
+// holds rewind down for the given number of updates
+fn rewind_by(rewind: &mut Rewind, sim: &mut Simulation, updates: usize) {
+    assert!(rewind.start(sim));
+    for _ in 1..updates {
+        rewind.step_back(sim);
+    }
+    assert!(rewind.release());
+}
+
+#[test]
+fn rewinding_is_the_same_as_replaying_to_earlier() {
+    for &updates in [1, 7, 40].iter() {
+        let mut rewind = Rewind::new(3, 3);
+        let (mut sim, ticks) = play(&mut rewind);
+        rewind_by(&mut rewind, &mut sim, updates);
+        let earlier = replayed(&ticks, TICKS - updates);
+        assert_eq!(sim.tick, earlier.tick);
+        assert_eq!(sim.state_hash(), earlier.state_hash(), "{}", updates);
+
+        // and carries on the same way from there, apples and all
+        let mut replayed = earlier;
+        for commands in ticks[TICKS - updates..].iter() {
+            sim.step(commands);
+            replayed.step(commands);
+        }
+        assert_eq!(sim.state_hash(), replayed.state_hash(), "{}", updates);
+    }
+}
+
+#[test]
+fn rewinding_only_goes_back_so_far() {
+    let mut rewind = Rewind::new(3, 3);
+    let (mut sim, ticks) = play(&mut rewind);
+    rewind_by(&mut rewind, &mut sim, TICKS);
+    // it only remembers the last 100 updates
+    assert_eq!(sim.tick as usize, TICKS - 100);
+    assert_eq!(
+        sim.state_hash(),
+        replayed(&ticks, sim.tick as usize).state_hash()
+    );
+}
+
+#[test]
+fn every_game_only_gets_so_many_rewinds() {
+    let mut rewind = Rewind::new(2, 3);
+    let (mut sim, _) = play(&mut rewind);
+    rewind_by(&mut rewind, &mut sim, 1);
+    rewind_by(&mut rewind, &mut sim, 1);
+    assert_eq!(rewind.rewinds_left, 0);
+    assert!(!rewind.can_rewind());
+    let tick = sim.tick;
+    assert!(!rewind.start(&mut sim));
+    assert!(!rewind.release());
+    assert_eq!(sim.tick, tick);
+
+    rewind.reset();
+    assert_eq!(rewind.rewinds_left, 2);
+    assert!(!rewind.can_rewind());
+}
@@ -0,0 +1,69 @@
+use rusty_snake::game::loopback::{self, LoopbackConfig, LoopbackReport};
+use rusty_snake::game::rollback::DEFAULT_INPUT_DELAY;
+use rusty_snake::game::simulation::Settings;
+use rusty_snake::game::tournament::{self, Bot};
+
+const MAX_TICKS: u64 = 200;
+
+fn config(latency: u64, jitter: u64, loss: f64) -> LoopbackConfig {
+    LoopbackConfig {
+        settings: Settings::new(20, 14),
+        seed: 4,
+        input_delay: DEFAULT_INPUT_DELAY,
+        latency,
+        jitter,
+        loss,
+        max_ticks: MAX_TICKS,
+    }
+}
+
+// plays the hard bot against itself
+fn run(config: &LoopbackConfig) -> LoopbackReport {
+    let bots = tournament::builtin_bots();
+    let find = |name: &str| bots.iter().find(|bot| bot.name == name).unwrap();
+    let players: Vec<&Bot> = vec![find("hard"), find("hard")];
+    loopback::run(config, &players)
+}
+
+fn assert_ok(report: &LoopbackReport) {
+    assert!(report.finished, "gave up after {} updates", report.updates);
+    assert!(report.histories_agree);
+    for (index, peer) in report.peers.iter().enumerate() {
+        assert_eq!(peer.desync, None, "player {}", index);
+        assert!(peer.matches_replay, "player {}", index);
+        // nobody dies in this game, so it always runs to the end
+        assert_eq!(peer.confirmed, MAX_TICKS, "player {}", index);
+    }
+    assert!(report.is_ok());
+}
+
+#[test]
+fn a_perfect_connection_never_rolls_back() {
+    let report = run(&config(0, 0, 0.0));
+    assert_ok(&report);
+    for peer in report.peers.iter() {
+        assert_eq!(peer.stats.rollbacks, 0);
+    }
+}
+
+#[test]
+fn late_commands_get_rolled_back_and_everyone_ends_up_the_same() {
+    let report = run(&config(4, 3, 0.0));
+    assert_ok(&report);
+    assert!(report.peers.iter().any(|peer| peer.stats.rollbacks > 0));
+}
+
+#[test]
+fn lost_messages_get_sent_again() {
+    for &loss in [0.1, 0.5].iter() {
+        let report = run(&config(2, 2, loss));
+        assert_ok(&report);
+    }
+}
+
+#[test]
+fn connections_too_slow_to_guess_over_stall_instead() {
+    let report = run(&config(12, 0, 0.0));
+    assert_ok(&report);
+    assert!(report.peers.iter().any(|peer| peer.stats.stalls > 0));
+}
@@ -0,0 +1,180 @@
+// Sets up each kind of collision on purpose and checks who's still standing afterwards, under
+// every rule that covers it
+use std::collections::VecDeque;
+
+use rusty_snake::game::controller::Command;
+use rusty_snake::game::rules::{CollisionRule, HeadToHeadRule, Rules};
+use rusty_snake::game::simulation::{Apple, Settings, Simulation};
+use rusty_snake::game::snake::{self, Snake};
+use rusty_snake::game::vector::Vector2D;
+
+const LEFT: Vector2D = Vector2D { x: -1, y: 0 };
+const RIGHT: Vector2D = Vector2D { x: 1, y: 0 };
+const DOWN: Vector2D = Vector2D { x: 0, y: 1 };
+
+// a snake going along the given squares, head first
+fn snake(index: usize, squares: &[(i32, i32)], direction: Vector2D) -> Snake {
+    let path: VecDeque<Vector2D> = squares.iter().map(|&(x, y)| Vector2D { x, y }).collect();
+    let mut snake = Snake::new(snake::players()[index], path[0], direction);
+    snake.head_is_tail = false;
+    snake.path = path;
+    snake
+}
+
+// A 10x10 board with an apple out of everybody's way in the far corner, so nobody eating or
+// the apples running out gets mixed up in it. Gives back the game after one tick
+fn after_one_tick(rules: Rules, walls: &[(i32, i32)], snakes: Vec<Snake>) -> Simulation {
+    let mut settings = Settings::new(10, 10);
+    settings.num_snakes = 0;
+    settings.num_apples = 0;
+    settings.rules = rules;
+    settings.level.walls = walls.iter().map(|&(x, y)| Vector2D { x, y }).collect();
+    let mut sim = Simulation::new(settings, 0);
+    sim.apples.push_back(Apple {
+        pos: Vector2D { x: 9, y: 9 },
+        lifetime: None,
+        age: 0,
+    });
+    sim.snakes = snakes;
+    let commands = vec![Command::Continue; sim.snakes.len()];
+    sim.step(&commands);
+    sim
+}
+
+fn rules(collision: CollisionRule, head_to_head: HeadToHeadRule) -> Rules {
+    Rules {
+        self_collision: collision,
+        snake_collision: collision,
+        head_to_head,
+    }
+}
+
+fn heads(sim: &Simulation) -> Vec<Vector2D> {
+    sim.snakes.iter().map(Snake::head).collect()
+}
+
+fn alive(sim: &Simulation) -> Vec<bool> {
+    sim.snakes.iter().map(|snake| snake.is_alive).collect()
+}
+
+#[test]
+fn running_into_a_wall() {
+    let me = || vec![snake(0, &[(3, 1), (2, 1), (1, 1)], RIGHT)];
+    let walls = [(4, 1)];
+
+    let blocked = after_one_tick(
+        rules(CollisionRule::Block, HeadToHeadRule::Block),
+        &walls,
+        me(),
+    );
+    assert_eq!(alive(&blocked), vec![true]);
+    assert_eq!(heads(&blocked), vec![Vector2D { x: 3, y: 1 }]);
+
+    let died = after_one_tick(
+        rules(CollisionRule::Die, HeadToHeadRule::Block),
+        &walls,
+        me(),
+    );
+    assert_eq!(alive(&died), vec![false]);
+}
+
+#[test]
+fn running_into_yourself() {
+    // curled round so going left runs into the middle of its own body
+    let me = || vec![snake(0, &[(2, 2), (2, 1), (1, 1), (1, 2), (1, 3)], LEFT)];
+
+    let blocked = after_one_tick(
+        rules(CollisionRule::Block, HeadToHeadRule::Block),
+        &[],
+        me(),
+    );
+    assert_eq!(alive(&blocked), vec![true]);
+    assert_eq!(heads(&blocked), vec![Vector2D { x: 2, y: 2 }]);
+
+    let died = after_one_tick(rules(CollisionRule::Die, HeadToHeadRule::Block), &[], me());
+    assert_eq!(alive(&died), vec![false]);
+}
+
+#[test]
+fn running_into_somebody_else() {
+    // the first snake heads into the middle of the second, which is going off out of the way
+    let snakes = || {
+        vec![
+            snake(0, &[(3, 1), (2, 1)], RIGHT),
+            snake(1, &[(4, 2), (4, 1), (4, 0)], DOWN),
+        ]
+    };
+
+    let blocked = after_one_tick(
+        rules(CollisionRule::Block, HeadToHeadRule::Block),
+        &[],
+        snakes(),
+    );
+    assert_eq!(alive(&blocked), vec![true, true]);
+    assert_eq!(
+        heads(&blocked),
+        vec![Vector2D { x: 3, y: 1 }, Vector2D { x: 4, y: 3 }]
+    );
+
+    let died = after_one_tick(
+        rules(CollisionRule::Die, HeadToHeadRule::Block),
+        &[],
+        snakes(),
+    );
+    assert_eq!(alive(&died), vec![false, true]);
+    assert_eq!(died.snakes[1].head(), Vector2D { x: 4, y: 3 });
+}
+
+#[test]
+fn running_into_a_dead_snake() {
+    let snakes = || {
+        let mut dead = snake(1, &[(4, 2), (4, 1), (4, 0)], DOWN);
+        dead.is_alive = false;
+        vec![snake(0, &[(3, 1), (2, 1)], RIGHT), dead]
+    };
+    let sim = after_one_tick(
+        rules(CollisionRule::Die, HeadToHeadRule::BothDie),
+        &[],
+        snakes(),
+    );
+    assert!(sim.snakes[0].is_alive);
+    assert_eq!(sim.snakes[0].head(), Vector2D { x: 4, y: 1 });
+}
+
+// both snakes go for 4,1 at once. The first is the longer one unless they're to be the same
+fn head_to_head(rule: HeadToHeadRule, same_length: bool) -> Simulation {
+    let first: &[(i32, i32)] = if same_length {
+        &[(3, 1), (2, 1)]
+    } else {
+        &[(3, 1), (2, 1), (1, 1)]
+    };
+    let snakes = vec![snake(0, first, RIGHT), snake(1, &[(5, 1), (6, 1)], LEFT)];
+    after_one_tick(rules(CollisionRule::Die, rule), &[], snakes)
+}
+
+#[test]
+fn head_to_head_blocking_stops_both() {
+    let sim = head_to_head(HeadToHeadRule::Block, false);
+    assert_eq!(alive(&sim), vec![true, true]);
+    assert_eq!(
+        heads(&sim),
+        vec![Vector2D { x: 3, y: 1 }, Vector2D { x: 5, y: 1 }]
+    );
+}
+
+#[test]
+fn head_to_head_can_kill_both() {
+    let sim = head_to_head(HeadToHeadRule::BothDie, false);
+    assert_eq!(alive(&sim), vec![false, false]);
+}
+
+#[test]
+fn head_to_head_goes_to_the_longer_snake() {
+    let sim = head_to_head(HeadToHeadRule::LongerWins, false);
+    assert_eq!(alive(&sim), vec![true, false]);
+    assert_eq!(sim.snakes[0].head(), Vector2D { x: 4, y: 1 });
+
+    // and nobody, if neither of them is
+    let sim = head_to_head(HeadToHeadRule::LongerWins, true);
+    assert_eq!(alive(&sim), vec![false, false]);
+}
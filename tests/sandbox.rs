@@ -0,0 +1,141 @@
+// Small hand-written bots that misbehave in every way the sandbox is meant to put up with
+use std::rc::Rc;
+
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::sandbox::{WasmBot, WasmModule};
+use rusty_snake::game::simulation::{Settings, Simulation};
+
+const MOVES: u32 = 3;
+
+// a bot with the given body for next_move, and anything else it needs alongside
+fn module(next_move: &str, extra: &str) -> String {
+    format!(
+        r#"(module
+  (memory (export "memory") 1)
+  {}
+  (func (export "init") (param i32 i32) (result i32) i32.const 0)
+  (func (export "next_move") (param i32 i32 i32) (result i32) {}))"#,
+        extra, next_move
+    )
+}
+
+// plays a few moves and gives back what the bot said, and how many of them it forfeited
+fn play(text: &str, setup: impl Fn(&mut WasmModule)) -> (Vec<Command>, u32) {
+    let mut module = WasmModule::load("test", text.as_bytes()).unwrap();
+    setup(&mut module);
+    let module = Rc::new(module);
+    let mut bot = WasmBot::new(module.clone());
+    let sim = Simulation::new(Settings::new(10, 8), 0);
+    let commands = (0..MOVES)
+        .map(|_| bot.next_command(&sim.board_view(0)))
+        .collect();
+    (commands, module.forfeits())
+}
+
+fn assert_forfeits(text: &str, setup: impl Fn(&mut WasmModule)) {
+    let (commands, forfeits) = play(text, setup);
+    assert_eq!(forfeits, MOVES, "{}", text);
+    assert!(commands.iter().all(|&command| command == Command::Continue));
+}
+
+#[test]
+fn bots_see_the_board_and_get_their_way() {
+    // goes right once it's found its own head where the board says it is, on a 10 wide board
+    let text = module(
+        "(i32.load8_u (i32.add (i32.mul (local.get 1) (i32.const 10)) (local.get 0)))
+         (if (result i32) (i32.eq (i32.const 3))
+           (then (i32.const 4))
+           (else (i32.const 0)))",
+        "",
+    );
+    let (commands, forfeits) = play(&text, |_| {});
+    assert_eq!(forfeits, 0);
+    assert_eq!(commands, vec![Command::Right; MOVES as usize]);
+}
+
+#[test]
+fn bots_that_run_out_of_fuel_forfeit_the_move() {
+    assert_forfeits(
+        &module("(loop $forever (br $forever)) i32.const 4", ""),
+        |_| {},
+    );
+
+    // but get a full tank again the next move
+    let once = module(
+        "(if (i32.eqz (global.get $looped))
+           (then (global.set $looped (i32.const 1)) (loop $forever (br $forever))))
+         i32.const 4",
+        "(global $looped (mut i32) (i32.const 0))",
+    );
+    let (commands, forfeits) = play(&once, |module| module.fuel = 1000);
+    assert_eq!(forfeits, 1);
+    assert_eq!(
+        commands,
+        vec![Command::Continue, Command::Right, Command::Right]
+    );
+}
+
+#[test]
+fn bots_cant_have_more_memory_than_theyre_allowed() {
+    let page = 64 * 1024;
+    let grows = module("(drop (memory.grow (i32.const 4))) i32.const 4", "");
+    assert_forfeits(&grows, |module| module.memory = 2 * page);
+    assert_eq!(play(&grows, |module| module.memory = 16 * page).1, 0);
+
+    let starts_big = module("i32.const 4", "").replace(
+        "(memory (export \"memory\") 1)",
+        "(memory (export \"memory\") 3)",
+    );
+    assert_forfeits(&starts_big, |module| module.memory = 2 * page);
+}
+
+#[test]
+fn bots_cant_have_huge_tables() {
+    assert_forfeits(&module("i32.const 4", "(table 100000 funcref)"), |_| {});
+    assert_eq!(
+        play(&module("i32.const 4", "(table 10 funcref)"), |_| {}).1,
+        0
+    );
+}
+
+#[test]
+fn answers_that_dont_mean_anything_are_forfeit() {
+    for answer in ["-1", "6", "99"].iter() {
+        assert_forfeits(&module(&format!("i32.const {}", answer), ""), |_| {});
+    }
+
+    // nor can it have the board put somewhere that isn't there
+    for offset in ["-1", "65500", "1000000"].iter() {
+        let text = module("i32.const 4", "").replace(
+            "(result i32) i32.const 0",
+            &format!("(result i32) i32.const {}", offset),
+        );
+        assert_forfeits(&text, |_| {});
+    }
+}
+
+#[test]
+fn bots_that_trap_forfeit_the_move() {
+    assert_forfeits(&module("unreachable", ""), |_| {});
+    assert_forfeits(
+        &module("(i32.div_s (i32.const 1) (i32.const 0))", ""),
+        |_| {},
+    );
+    assert_forfeits(&module("(i32.load (i32.const 1000000))", ""), |_| {});
+}
+
+#[test]
+fn bots_cant_import_anything() {
+    let text = module("i32.const 4", "").replacen(
+        "(module",
+        "(module\n  (import \"env\" \"outside\" (func))",
+        1,
+    );
+    assert_forfeits(&text, |_| {});
+}
+
+#[test]
+fn bots_that_arent_wasm_dont_load() {
+    assert!(WasmModule::load("test", b"(module").is_err());
+    assert!(WasmModule::load("test", b"\0asm nonsense").is_err());
+}
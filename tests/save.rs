@@ -0,0 +1,105 @@
+// Saves from every version there's been have to keep loading, so there's one of each in
+// tests/saves, written by the game as it was then
+use std::{fs, path::PathBuf};
+
+use rusty_snake::game::rewind;
+use rusty_snake::game::save::{Save, VERSION};
+use rusty_snake::game::simulation::{Settings, Simulation};
+use rusty_snake::game::state;
+
+fn fixture(name: &str) -> String {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "saves", name]
+        .iter()
+        .collect();
+    fs::read_to_string(&path).unwrap()
+}
+
+// version 1 was just the game, as state.rs writes it
+#[test]
+fn version_1_loads() {
+    let text = fixture("v1.txt");
+    let save = Save::parse(&text).unwrap();
+    assert_eq!(state::to_text(&save.sim), text);
+    assert_eq!(save.num_players, 2);
+    assert_eq!(save.rewinds_left, rewind::DEFAULT_REWINDS);
+    assert!(!save.is_fair);
+    assert!(save.replay.is_none());
+}
+
+#[test]
+fn version_2_loads() {
+    let save = Save::parse(&fixture("v2.txt")).unwrap();
+    assert_eq!(save.sim.tick, 12);
+    assert_eq!(save.sim.snakes.len(), 2);
+    assert_eq!(save.num_players, 1);
+    assert_eq!(save.rewinds_left, 2);
+    assert!(save.is_fair);
+    let replay = save.replay.unwrap();
+    assert_eq!(replay.ticks.len(), 12);
+    assert_eq!(replay.play().state_hash(), save.sim.state_hash());
+}
+
+#[test]
+fn saves_round_trip() {
+    let text = fixture(&format!("v{}.txt", VERSION));
+    assert_eq!(Save::parse(&text).unwrap().to_text(), text);
+
+    // and older ones come back as the current version
+    let upgraded = Save::parse(&fixture("v1.txt")).unwrap().to_text();
+    assert!(upgraded.starts_with(&format!("version {}\n", VERSION)));
+    assert_eq!(
+        Save::parse(&upgraded).unwrap().to_text(),
+        upgraded,
+        "{}",
+        upgraded
+    );
+}
+
+#[test]
+fn saves_from_newer_versions_are_turned_down() {
+    let text = fixture(&format!("v{}.txt", VERSION));
+    let newer = text.replacen(
+        &format!("version {}", VERSION),
+        &format!("version {}", VERSION + 1),
+        1,
+    );
+    let err = Save::parse(&newer).err().unwrap();
+    assert!(err.contains("newer"), "{}", err);
+
+    for version in ["version 0", "version two", "version -1"].iter() {
+        let text = text.replacen(&format!("version {}", VERSION), version, 1);
+        assert!(Save::parse(&text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn malformed_saves_are_turned_down() {
+    let text = fixture("v2.txt");
+    let malformed = [
+        text.replace("game\n", ""),
+        text.replace("players 1", "players 3"),
+        text.replace("players 1\n", ""),
+        text.replace("fair 1", "fair 2"),
+        text.replace("rewinds 2", "rewinds lots"),
+        text.replace("tick RU", "tick RUU"),
+    ];
+    for text in malformed.iter() {
+        assert!(Save::parse(text).is_err(), "{}", text);
+    }
+}
+
+// every snake nobody's playing goes to the computer, which only has names for three of them
+#[test]
+fn saves_with_too_many_computer_players_are_turned_down() {
+    let mut settings = Settings::new(12, 8);
+    settings.num_snakes = 4;
+    let save = |num_players| Save {
+        sim: Simulation::new(settings.clone(), 5),
+        replay: None,
+        num_players,
+        rewinds_left: rewind::DEFAULT_REWINDS,
+        is_fair: false,
+    };
+    assert!(Save::parse(&save(1).to_text()).is_ok());
+    assert!(Save::parse(&save(0).to_text()).is_err());
+}
@@ -0,0 +1,63 @@
+use rand::SeedableRng;
+use std::collections::HashSet;
+
+use rusty_snake::game::simulation::GameRng;
+use rusty_snake::game::spawn::{ReachableOnly, SpawnBoard, SpawnStrategy, Uniform};
+use rusty_snake::game::vector::Vector2D;
+
+const WIDTH: i32 = 6;
+const HEIGHT: i32 = 6;
+const HEAD: Vector2D = Vector2D { x: 0, y: 0 };
+
+// walls all the way down the third and last columns, so with the board wrapping round the head
+// is stuck in the two columns on the left
+fn walls() -> Vec<Vector2D> {
+    (0..HEIGHT)
+        .flat_map(|y| vec![Vector2D { x: 2, y }, Vector2D { x: 5, y }])
+        .collect()
+}
+
+// picks a few times from whichever of the empty squares are_empty lets through
+fn choices<S, F>(strategy: &mut S, are_empty: F) -> Vec<Option<Vector2D>>
+where
+    S: SpawnStrategy,
+    F: Fn(&Vector2D) -> bool,
+{
+    let walls = walls();
+    let mut blocked: HashSet<Vector2D> = walls.iter().copied().collect();
+    blocked.insert(HEAD);
+    let empty_squares: Vec<Vector2D> = (0..WIDTH)
+        .flat_map(|x| (0..HEIGHT).map(move |y| Vector2D { x, y }))
+        .filter(|pos| !blocked.contains(pos) && are_empty(pos))
+        .collect();
+    let board = SpawnBoard {
+        width: WIDTH,
+        height: HEIGHT,
+        head: Some(HEAD),
+        empty_squares: &empty_squares,
+        apples: &[],
+        walls: &walls,
+        blocked: &blocked,
+    };
+    let mut rng = GameRng::seed_from_u64(1);
+    (0..20).map(|_| strategy.choose(&board, &mut rng)).collect()
+}
+
+#[test]
+fn reachable_apples_stay_where_the_head_can_get_to() {
+    for choice in choices(&mut ReachableOnly, |_| true) {
+        assert!(choice.unwrap().x < 2, "{:?}", choice);
+    }
+}
+
+#[test]
+fn reachable_apples_wait_when_nowhere_can_be_got_to() {
+    let walled_off = |pos: &Vector2D| pos.x > 2;
+    assert!(choices(&mut ReachableOnly, walled_off)
+        .iter()
+        .all(Option::is_none));
+    // strategies that don't care still put them behind the walls
+    assert!(choices(&mut Uniform, walled_off)
+        .iter()
+        .all(Option::is_some));
+}
@@ -0,0 +1,213 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::net::Message;
+use rusty_snake::game::simulation::{Settings, Simulation, APPLE_LIFETIME};
+use rusty_snake::game::spectate::{Broadcaster, Delta, Snapshot, SNAPSHOT_INTERVAL};
+use rusty_snake::game::vector::Vector2D;
+
+fn settings() -> Settings {
+    let mut settings = Settings::new(20, 14);
+    settings.num_snakes = 3;
+    settings.num_apples = 4;
+    settings.apple_lifetime = Some(APPLE_LIFETIME / 20);
+    settings
+}
+
+// one move for every snake, with the last one swapping its head every so often so paths
+// change at both ends. Dead snakes just keep going, the same as in loopback.rs
+fn moves(sim: &Simulation, players: &mut [AiPlayer]) -> Vec<Command> {
+    players
+        .iter_mut()
+        .enumerate()
+        .map(|(index, player)| {
+            if !sim.snakes[index].is_alive {
+                Command::Continue
+            } else if index == 2 && sim.tick % 25 == 24 {
+                Command::SwapHead
+            } else {
+                player.next_command(&sim.board_view(index))
+            }
+        })
+        .collect()
+}
+
+// what a watcher draws, as text so any difference shows up in the failure
+fn looks_like(sim: &Simulation) -> String {
+    Snapshot::of(sim, sim.is_game_over).to_text()
+}
+
+// What a watcher ends up with after reading every message sent from the given tick on, as
+// text, the same as it comes over the wire. Messages before the first snapshot are ignored,
+// the same as the server does for anyone starting to watch partway through
+fn watch(sim: &mut Simulation, ticks: u64, from: u64) {
+    let mut players: Vec<AiPlayer> = (0..sim.snakes.len())
+        .map(|_| AiPlayer::new(Difficulty::Hard))
+        .collect();
+    let mut broadcaster = Broadcaster::new();
+    let mut watched: Option<Simulation> = None;
+    let mut deltas = 0;
+    for _ in 0..ticks {
+        if let Some(message) = broadcaster.update(sim, sim.is_game_over) {
+            if sim.tick >= from {
+                match Message::parse(&message.to_text()).unwrap() {
+                    Message::Snapshot(snapshot) => watched = Some(snapshot.to_simulation()),
+                    Message::Delta(delta) => {
+                        if let Some(watched) = watched.as_mut() {
+                            delta.apply(watched).unwrap();
+                            deltas += 1;
+                        }
+                    }
+                    other => panic!("expected a snapshot or delta, not {:?}", other),
+                }
+            }
+        }
+        if let Some(watched) = watched.as_ref() {
+            assert_eq!(looks_like(watched), looks_like(sim), "tick {}", sim.tick);
+        }
+        if sim.is_game_over {
+            break;
+        }
+        let commands = moves(sim, &mut players);
+        sim.step(&commands);
+    }
+    assert!(watched.is_some());
+    assert!(deltas > 0);
+}
+
+#[test]
+fn watchers_see_the_game_the_way_it_is() {
+    let mut sim = Simulation::new(settings(), 4);
+    watch(&mut sim, 250, 0);
+    assert!(
+        sim.tick > SNAPSHOT_INTERVAL,
+        "only got to tick {}",
+        sim.tick
+    );
+}
+
+#[test]
+fn late_watchers_catch_up_at_the_next_snapshot() {
+    let mut sim = Simulation::new(settings(), 4);
+    watch(&mut sim, 250, 40);
+}
+
+#[test]
+fn nothing_gets_sent_when_nothing_changed() {
+    let sim = Simulation::new(settings(), 4);
+    let mut broadcaster = Broadcaster::new();
+    assert!(broadcaster.update(&sim, false).is_some());
+    assert!(broadcaster.update(&sim, false).is_none());
+    // the game ending is worth telling people about
+    assert!(broadcaster.update(&sim, true).is_some());
+
+    broadcaster.restart();
+    match broadcaster.update(&sim, true) {
+        Some(Message::Snapshot(_)) => {}
+        other => panic!("expected a snapshot, not {:?}", other),
+    }
+}
+
+#[test]
+fn deltas_only_go_on_the_tick_they_came_after() {
+    let mut sim = Simulation::new(settings(), 4);
+    let mut players: Vec<AiPlayer> = (0..3).map(|_| AiPlayer::new(Difficulty::Hard)).collect();
+    let start = sim.clone();
+    let commands = moves(&sim, &mut players);
+    sim.step(&commands);
+    let before = sim.clone();
+    let commands = moves(&sim, &mut players);
+    sim.step(&commands);
+
+    // two ticks apart can't be said as one delta
+    assert!(Delta::between(&start, &sim, false).is_none());
+    let delta = Delta::between(&before, &sim, false).unwrap();
+    let mut watched = Snapshot::of(&start, false).to_simulation();
+    assert!(delta.apply(&mut watched).is_err());
+}
+
+#[test]
+fn messages_read_back_the_same() {
+    let mut sim = Simulation::new(settings(), 4);
+    let mut players: Vec<AiPlayer> = (0..3).map(|_| AiPlayer::new(Difficulty::Hard)).collect();
+    let before = sim.clone();
+    let commands = moves(&sim, &mut players);
+    sim.step(&commands);
+
+    let snapshot = Snapshot::of(&sim, false).to_text();
+    assert_eq!(Snapshot::parse(&snapshot).unwrap().to_text(), snapshot);
+    let delta = Delta::between(&before, &sim, false).unwrap().to_text();
+    assert_eq!(Delta::parse(&delta).unwrap().to_text(), delta);
+
+    assert!(Snapshot::parse("snapshot 1 0 0\nwall 1 1\n").is_err());
+    assert!(Delta::parse("delta x 0 0\n").is_err());
+    assert!(Delta::parse("delta 1 0 0\nmove 0 1 0 1 1 12 0 1 - 7\n").is_err());
+}
+
+#[test]
+fn snapshots_that_no_game_could_send_are_turned_down() {
+    let header = "snapshot 4 0 0\nboard 6 4\nlifetime none\n";
+    let snake = "snake 0 1 0 0 1 0 2,1 1,1\n";
+    assert!(Snapshot::parse(&format!("{}apple 5 3 0\n{}", header, snake)).is_ok());
+
+    let malformed = [
+        // a snake with nowhere to draw its head
+        format!("{}snake 0 1 0 0 1 0\n", header),
+        format!("{}snake 0 1 1 0 1 0 2,1\n", header),
+        format!("{}snake 0 0 0 0 1 0 2,1\n", header),
+        format!("{}snake 0 1 0 0 1 0 6,1\n", header),
+        format!("{}snake 0 1 0 0 1 0 2,-1\n", header),
+        format!("{}wall 6 0\n{}", header, snake),
+        format!("{}apple 0 4 0\n{}", header, snake),
+        header.replace("board 6 4", "board 30000 30000") + snake,
+        header.replace("board 6 4", "board 1 1") + "wall 0 0\n" + snake,
+    ];
+    for text in malformed.iter() {
+        assert!(Snapshot::parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn deltas_that_dont_fit_the_game_change_nothing() {
+    let snapshot = "snapshot 4 0 0\nboard 6 4\nlifetime none\nsnake 0 1 0 0 1 0 2,1 1,1\n";
+    let watched = Snapshot::parse(snapshot).unwrap().to_simulation();
+    let header = "delta 5 0 0\nlifetime none\n";
+    let mut sim = watched.clone();
+    Delta::parse(&format!("{}move 0 1 0 0 1 0 0 1 3,1 -\n", header))
+        .unwrap()
+        .apply(&mut sim)
+        .unwrap();
+    assert_eq!(sim.snakes[0].head(), Vector2D { x: 3, y: 1 });
+
+    assert!(Delta::parse(&format!("{}move 0 2 0 0 1 0 0 1 3,1 -\n", header)).is_err());
+    let wont_fit = [
+        // popping every square
+        format!("{}move 0 1 0 0 1 0 1 1 - -\n", header),
+        format!("{}move 0 1 0 0 1 0 2 1 3,1 -\n", header),
+        format!("{}move 0 1 0 0 1 0 18446744073709551615 1 3,1 -\n", header),
+        format!("{}move 0 1 0 0 1 0 0 1 6,1 -\n", header),
+        format!("{}move 1 1 0 0 1 0 0 1 3,1 -\n", header),
+        format!(
+            "{}move 0 1 0 0 1 0 0 1 3,1 -\nmove 0 1 0 0 1 0 0 1 4,1 -\n",
+            header
+        ),
+        format!("{}apple 9 9 0\nmove 0 1 0 0 1 0 0 1 3,1 -\n", header),
+    ];
+    for text in wont_fit.iter() {
+        let mut sim = watched.clone();
+        assert!(
+            Delta::parse(text).unwrap().apply(&mut sim).is_err(),
+            "{}",
+            text
+        );
+        assert_eq!(sim.state_hash(), watched.state_hash(), "{}", text);
+    }
+}
+
+#[test]
+fn renamed_players_keep_their_colors() {
+    let mut sim = Simulation::new(settings(), 2);
+    sim.snakes[1].player.name = "CPU 1";
+    let snapshot = Snapshot::of(&sim, false);
+    let players: Vec<usize> = snapshot.snakes.iter().map(|snake| snake.player).collect();
+    assert_eq!(players, vec![0, 1, 2]);
+}
@@ -0,0 +1,153 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::{Command, SnakeController};
+use rusty_snake::game::level::Level;
+use rusty_snake::game::simulation::{Settings, Simulation, APPLE_LIFETIME};
+use rusty_snake::game::state;
+use rusty_snake::game::vector::Vector2D;
+
+// plays on with the computer steering every snake
+fn play(sim: &mut Simulation, ticks: u64) {
+    let mut players: Vec<AiPlayer> = (0..sim.snakes.len())
+        .map(|_| AiPlayer::new(Difficulty::Hard))
+        .collect();
+    for _ in 0..ticks {
+        if sim.is_game_over {
+            return;
+        }
+        let commands: Vec<Command> = players
+            .iter_mut()
+            .enumerate()
+            .map(|(index, player)| player.next_command(&sim.board_view(index)))
+            .collect();
+        sim.step(&commands);
+    }
+}
+
+// Writes a game out partway through and reads it back in, then checks the copy plays on exactly
+// the same as the original from there. Returns what was written
+fn check_plays_on_the_same(settings: Settings, seed: u64) -> String {
+    let mut original = Simulation::new(settings, seed);
+    play(&mut original, 150);
+
+    let text = state::to_text(&original);
+    let mut copy = state::parse(&text).unwrap();
+    assert_eq!(state::to_text(&copy), text);
+    assert_eq!(copy.state_hash(), original.state_hash());
+
+    play(&mut original, 300);
+    play(&mut copy, 300);
+    assert_eq!(state::to_text(&copy), state::to_text(&original));
+    assert_eq!(copy.state_hash(), original.state_hash());
+    text
+}
+
+#[test]
+fn games_play_on_the_same_after_being_read_back_in() {
+    let mut settings = Settings::new(16, 12);
+    settings.num_snakes = 2;
+    check_plays_on_the_same(settings, 5);
+}
+
+#[test]
+fn timed_apples_use_up_the_same_randomness() {
+    let mut settings = Settings::new(16, 12);
+    settings.num_apples = 8;
+    settings.apple_lifetime = Some(APPLE_LIFETIME / 10);
+    let text = check_plays_on_the_same(settings, 11);
+    assert!(!text.contains("draws 0\n"), "{}", text);
+}
+
+#[test]
+fn scripted_apples_carry_on_from_the_same_place() {
+    let mut settings = Settings::new(30, 20);
+    settings.level = Level::find_builtin("pillars").unwrap();
+    settings.spawn_strategy = 5;
+    let text = check_plays_on_the_same(settings, 2);
+    assert!(text.contains("\nscript "), "{}", text);
+}
+
+// skipping ahead works it out rather than drawing every number, so a made up count can't
+// hold anything up
+#[test]
+fn huge_draw_counts_load_straight_away() {
+    let sim = Simulation::new(Settings::new(10, 10), 3);
+    let draws = format!("draws {}\n", sim.randomness().1);
+    let text = state::to_text(&sim).replace(&draws, "draws 10000000000000\n");
+    let mut sim = state::parse(&text).unwrap();
+    assert_eq!(sim.randomness(), (3, 10_000_000_000_000));
+    play(&mut sim, 10);
+}
+
+#[test]
+fn malformed_games_are_turned_down() {
+    let text = state::to_text(&Simulation::new(Settings::new(10, 10), 3));
+    let malformed = [
+        text.replace("edges wrap", "edges solid"),
+        text.replace("state 0 0 0\n", ""),
+        text.lines()
+            .filter(|line| !line.starts_with("snake "))
+            .map(|line| format!("{}\n", line))
+            .collect(),
+        text.replace("snake 0 1 0", "snake 0 1 1"),
+        text.replace("snake 0 1 0", "snake 7 1 0"),
+        text + "apple 10 3 0\n",
+    ];
+    for text in malformed.iter() {
+        assert!(state::parse(text).is_err(), "{}", text);
+    }
+}
+
+// a game on a 10x10 board with a wall at 0,0, with the given snake lines in place of its own
+fn with_snakes(snakes: &[&str]) -> String {
+    let mut settings = Settings::new(10, 10);
+    settings.level.walls = vec![Vector2D { x: 0, y: 0 }];
+    let text = state::to_text(&Simulation::new(settings, 3));
+    let mut text: String = text
+        .lines()
+        .filter(|line| !line.starts_with("snake "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    for snake in snakes {
+        text.push_str(snake);
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn snakes_have_to_fit_on_the_board() {
+    let fine = [
+        with_snakes(&["snake 0 1 0 0 1 0 0 5,5 4,5 3,5"]),
+        // round the edge
+        with_snakes(&["snake 0 1 0 0 1 0 0 0,5 9,5"]),
+        // live snakes can go over dead ones
+        with_snakes(&[
+            "snake 0 1 0 0 1 0 0 5,5 4,5 3,5",
+            "snake 1 -1 0 0 0 0 0 4,5 4,6",
+        ]),
+    ];
+    for text in fine.iter() {
+        assert!(state::parse(text).is_ok(), "{}", text);
+    }
+
+    let malformed = [
+        with_snakes(&["snake 0 1 0 0 1 0 0 5,5", "snake 0 1 0 0 1 0 0 2,2"]),
+        with_snakes(&[
+            "snake 0 1 0 0 1 0 0 1,1",
+            "snake 1 1 0 0 1 0 0 2,2",
+            "snake 2 1 0 0 1 0 0 3,3",
+            "snake 3 1 0 0 1 0 0 4,4",
+            "snake 0 1 0 0 1 0 0 5,5",
+        ]),
+        with_snakes(&["snake 0 1 0 0 1 0 0 5,5 3,5"]),
+        with_snakes(&["snake 0 1 0 0 1 0 0 1,0 0,0"]),
+        with_snakes(&["snake 0 1 0 0 1 0 0 5,5 4,5 5,5"]),
+        with_snakes(&[
+            "snake 0 1 0 0 1 0 0 5,5 4,5",
+            "snake 1 -1 0 0 1 0 0 4,5 4,6",
+        ]),
+    ];
+    for text in malformed.iter() {
+        assert!(state::parse(text).is_err(), "{}", text);
+    }
+}
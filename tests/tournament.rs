@@ -0,0 +1,97 @@
+use rusty_snake::game::controller::{BoardView, Command, SnakeController};
+use rusty_snake::game::rules::CollisionRule;
+use rusty_snake::game::simulation::Settings;
+use rusty_snake::game::tournament::{self, Bot, ELO_START};
+use rusty_snake::game::vector::Vector2D;
+
+const MAX_TICKS: u64 = 500;
+
+// heads straight up no matter what, into the wall along the top
+struct Lemming;
+
+impl SnakeController for Lemming {
+    fn name(&self) -> &str {
+        "lemming"
+    }
+
+    fn next_command(&mut self, _board: &BoardView) -> Command {
+        Command::Up
+    }
+}
+
+fn bots() -> (Bot, Bot) {
+    let hard = tournament::builtin_bots()
+        .into_iter()
+        .find(|bot| bot.name == "hard")
+        .unwrap();
+    let lemming = Bot::new("lemming", Box::new(|| Box::new(Lemming)));
+    (hard, lemming)
+}
+
+// a wall all the way along the top row, and running into it is the end
+fn settings() -> Settings {
+    let mut settings = Settings::new(12, 10);
+    settings.level.walls = (0..12).map(|x| Vector2D { x, y: 0 }).collect();
+    settings.rules.self_collision = CollisionRule::Die;
+    settings
+}
+
+#[test]
+fn elo_moves_as_far_as_the_result_was_a_surprise() {
+    let (mut a, mut b) = (ELO_START, ELO_START);
+    tournament::update_elo(&mut a, &mut b, 1.);
+    assert!((a - (ELO_START + 16.)).abs() < 1e-9, "{}", a);
+    assert!((b - (ELO_START - 16.)).abs() < 1e-9, "{}", b);
+
+    // a draw between equals changes nothing
+    let (mut a, mut b) = (ELO_START, ELO_START);
+    tournament::update_elo(&mut a, &mut b, 0.5);
+    assert_eq!((a, b), (ELO_START, ELO_START));
+
+    // beating somebody much better is worth more than beating an equal
+    let (mut a, mut b) = (1400., 1800.);
+    tournament::update_elo(&mut a, &mut b, 1.);
+    assert!(a - 1400. > 16., "{}", a);
+    assert!((tournament::expected_score(1400., 1800.) - 1. / 11.).abs() < 1e-9);
+}
+
+#[test]
+fn elo_only_ever_changes_hands() {
+    let mut ratings = [ELO_START, 1620., 1390.];
+    let total: f64 = ratings.iter().sum();
+    let results = [(0, 1, 1.), (1, 2, 0.5), (2, 0, 0.), (0, 1, 0.), (2, 1, 1.)];
+    for &(first, second, score) in results.iter() {
+        let (mut a, mut b) = (ratings[first], ratings[second]);
+        tournament::update_elo(&mut a, &mut b, score);
+        ratings[first] = a;
+        ratings[second] = b;
+    }
+    assert!((ratings.iter().sum::<f64>() - total).abs() < 1e-9);
+}
+
+#[test]
+fn bots_take_turns_sitting_first() {
+    assert_eq!(tournament::seats(1, 3, 0), (1, 3));
+    assert_eq!(tournament::seats(1, 3, 1), (3, 1));
+    assert_eq!(tournament::seats(1, 3, 2), (1, 3));
+}
+
+#[test]
+fn results_follow_the_bots_whichever_seat_theyre_in() {
+    let (hard, lemming) = bots();
+    let players = [&hard, &lemming];
+    for game in 0..2 {
+        let (first, second) = tournament::seats(0, 1, game);
+        let seated = [players[first], players[second]];
+        let result = tournament::play_game(&settings(), 7, &seated, MAX_TICKS);
+
+        let names: Vec<&str> = seated.iter().map(|bot| bot.name.as_str()).collect();
+        assert_eq!(result.replay.names, names);
+        let hard_seat = if first == 0 { 0 } else { 1 };
+        assert!(result.won[hard_seat], "game {}", game);
+        assert!(!result.won[1 - hard_seat], "game {}", game);
+        assert!(result.survived[hard_seat] > result.survived[1 - hard_seat]);
+        let expected = if hard_seat == 0 { 1. } else { 0. };
+        assert_eq!(result.first_seat_score(), expected);
+    }
+}
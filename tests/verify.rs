@@ -0,0 +1,100 @@
+use rusty_snake::game::ai::{AiPlayer, Difficulty};
+use rusty_snake::game::controller::SnakeController;
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::simulation::{Settings, Simulation};
+use rusty_snake::game::spawn;
+use rusty_snake::game::verify::{self, Submission};
+
+const MAX_TICKS: u64 = 10_000;
+
+// an honest submission for a game the computer played for a while
+fn played(seed: u64) -> Submission {
+    let mut replay = Replay::new(Settings::new(12, 10), seed, vec!["hard ai".to_string()]);
+    let mut sim = replay.start();
+    let mut player = AiPlayer::new(Difficulty::Hard);
+    while sim.tick < 200 && !sim.is_game_over {
+        let commands = vec![player.next_command(&sim.board_view(0))];
+        replay.record(&commands);
+        sim.step(&commands);
+    }
+    Submission::new(replay)
+}
+
+#[test]
+fn honest_claims_check_out() {
+    let submission = played(3);
+    assert!(submission.claimed_score() > 0);
+    // the same as what gets sent, having gone through text and back
+    let submission = Submission::parse(&submission.to_text()).unwrap();
+    let verified = verify::verify(&submission, MAX_TICKS).unwrap();
+    assert_eq!(verified.score, submission.claimed_score());
+    assert_eq!(verified.ticks, submission.replay.ticks.len() as u64);
+}
+
+#[test]
+fn claims_that_dont_add_up_say_where() {
+    let honest = played(3);
+    let (tick, score) = honest.scores[1];
+
+    let mut inflated = honest.clone();
+    inflated.scores[1] = (tick, score + 1);
+    let rejection = verify::verify(&inflated, MAX_TICKS).unwrap_err();
+    assert_eq!(rejection.tick, Some(tick));
+
+    // scoring an update before it really happened
+    let index = (1..honest.scores.len())
+        .find(|&index| honest.scores[index].0 - 1 > honest.scores[index - 1].0)
+        .unwrap();
+    let (tick, score) = honest.scores[index];
+    let mut early = honest.clone();
+    early.scores[index] = (tick - 1, score);
+    let rejection = verify::verify(&early, MAX_TICKS).unwrap_err();
+    assert_eq!(rejection.tick, Some(tick - 1));
+
+    let rejection = verify::verify(&honest, 10).unwrap_err();
+    assert_eq!(rejection.tick, None);
+}
+
+#[test]
+fn malformed_replays_are_turned_down() {
+    let header = "board 5 5\napples 1\nlifetime none\nspawn 0\nrules block die both-die\nlevel \
+                  empty\nseed 1\n";
+    assert!(Replay::parse(&format!("{}snake me\ntick R\n", header)).is_ok());
+
+    let malformed = [
+        // no snakes
+        header.to_string(),
+        format!("{}snake me\ntick RR\n", header),
+        format!("{}snake me\ntick X\n", header),
+        format!("{}wall 100 100\nsnake me\n", header),
+        format!("{}apple 5 0\nsnake me\n", header),
+        format!("{}snake 1\nsnake 2\nsnake 3\nsnake 4\nsnake 5\n", header),
+        header.replace("board 5 5", "board 30000 30000") + "snake me\n",
+        header.replace("board 5 5", "board 0 5") + "snake me\n",
+        // nowhere for the snake to start
+        header.replace("board 5 5", "board 1 1") + "wall 0 0\nsnake me\n",
+        header.replace("board 5 5", "board 2 1") + "wall 0 0\nsnake me\nsnake you\n",
+        header.replace("board 5 5", "board 99999999999 5") + "snake me\n",
+        header.replace("spawn 0", "spawn 9") + "snake me\n",
+        header.replace("seed 1\n", "") + "snake me\n",
+    ];
+    for text in malformed.iter() {
+        assert!(Replay::parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn games_with_nobody_in_them_still_get_apples() {
+    let mut settings = Settings::new(6, 4);
+    settings.num_snakes = 0;
+    for strategy in 0..spawn::builtin_strategies(&settings.level).len() {
+        settings.spawn_strategy = strategy;
+        let sim = Simulation::new(settings.clone(), 1);
+        assert_eq!(
+            sim.apples.len(),
+            settings.num_apples,
+            "{}",
+            sim.spawn_strategy_name()
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use rusty_snake::game::autopilot::Autopilot;
+use rusty_snake::game::controller::SnakeController;
+use rusty_snake::game::replay::Replay;
+use rusty_snake::game::simulation::{Settings, Simulation};
+use rusty_snake::game::viewer::Viewer;
+
+// long enough for a few keyframes
+const TICKS: u64 = 1300;
+
+fn replay() -> Replay {
+    let mut replay = Replay::new(Settings::new(20, 14), 6, vec!["autopilot".to_string()]);
+    let mut sim = replay.start();
+    let mut player = Autopilot::new();
+    while sim.tick < TICKS {
+        assert!(!sim.is_game_over);
+        let commands = vec![player.next_command(&sim.board_view(0))];
+        replay.record(&commands);
+        sim.step(&commands);
+    }
+    replay
+}
+
+// the replay played from the very start up to the given update
+fn at(replay: &Replay, tick: u64) -> Simulation {
+    let mut sim = replay.start();
+    for commands in replay.ticks.iter().take(tick as usize) {
+        sim.step(commands);
+    }
+    sim
+}
+
+#[test]
+fn seeking_anywhere_shows_the_game_as_it_was_then() {
+    let replay = replay();
+    let mut viewer = Viewer::new(replay.clone());
+    let mut sim = replay.start();
+    // back and forth, either side of keyframes, and across several at once
+    for &tick in [1200, 30, 700, 501, 499, 500, 1000, 0, TICKS].iter() {
+        viewer.seek(&mut sim, tick);
+        assert_eq!(sim.tick, tick);
+        assert_eq!(sim.state_hash(), at(&replay, tick).state_hash(), "{}", tick);
+    }
+
+    // and no further than the end
+    viewer.seek(&mut sim, TICKS + 50);
+    assert_eq!(viewer.end(), TICKS);
+    assert_eq!(sim.tick, TICKS);
+}
+
+#[test]
+fn stepping_goes_one_update_at_a_time() {
+    let replay = replay();
+    let mut viewer = Viewer::new(replay.clone());
+    let mut sim = replay.start();
+    viewer.seek(&mut sim, 500);
+    viewer.step_back(&mut sim);
+    assert!(!viewer.is_playing);
+    assert_eq!(sim.state_hash(), at(&replay, 499).state_hash());
+    viewer.step_forward(&mut sim);
+    viewer.step_forward(&mut sim);
+    assert_eq!(sim.state_hash(), at(&replay, 501).state_hash());
+
+    viewer.seek(&mut sim, 0);
+    viewer.step_back(&mut sim);
+    assert_eq!(sim.tick, 0);
+}
+
+#[test]
+fn playing_goes_at_the_chosen_speed_and_stops_at_the_end() {
+    let replay = replay();
+    let mut viewer = Viewer::new(replay.clone());
+    let mut sim = replay.start();
+    viewer.advance(&mut sim, 0.5);
+    viewer.advance(&mut sim, 0.5);
+    assert_eq!(sim.tick, 1);
+
+    viewer.faster();
+    viewer.faster();
+    assert_eq!(viewer.speed(), 4.);
+    viewer.advance(&mut sim, 1.);
+    assert_eq!(sim.tick, 5);
+    assert_eq!(sim.state_hash(), at(&replay, 5).state_hash());
+
+    while viewer.is_playing {
+        viewer.advance(&mut sim, 1.);
+    }
+    assert_eq!(sim.tick, TICKS);
+    assert_eq!(sim.state_hash(), at(&replay, TICKS).state_hash());
+
+    // playing again from the end starts over
+    viewer.toggle_playing(&mut sim);
+    assert!(viewer.is_playing);
+    assert_eq!(sim.tick, 0);
+}